@@ -0,0 +1 @@
+ALTER TABLE scores ADD COLUMN mode TEXT NOT NULL DEFAULT 'classic';
@@ -0,0 +1 @@
+ALTER TABLE scores ADD COLUMN lives_used INTEGER NOT NULL DEFAULT 0;
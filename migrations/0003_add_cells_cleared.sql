@@ -0,0 +1 @@
+ALTER TABLE scores ADD COLUMN cells_cleared INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,8 @@
+CREATE TABLE daily_scores(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username TEXT NOT NULL,
+    date TEXT NOT NULL,
+    time_in_seconds INTEGER NOT NULL,
+    won BOOLEAN NOT NULL,
+    UNIQUE(username, date)
+);
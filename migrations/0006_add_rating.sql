@@ -0,0 +1,2 @@
+ALTER TABLE scores ADD COLUMN rating REAL;
+ALTER TABLE scores ADD COLUMN bbbv INTEGER;
@@ -0,0 +1,9 @@
+CREATE TABLE adaptive_games(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username TEXT NOT NULL,
+    density REAL NOT NULL,
+    size TEXT NOT NULL,
+    won BOOLEAN NOT NULL,
+    bbbv INTEGER NOT NULL,
+    time_in_seconds INTEGER NOT NULL
+);
@@ -0,0 +1,7 @@
+CREATE TABLE replays(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username TEXT NOT NULL,
+    seed TEXT NOT NULL,
+    time_millis INTEGER NOT NULL,
+    replay TEXT NOT NULL
+);
@@ -0,0 +1 @@
+ALTER TABLE scores ADD COLUMN verified INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+ALTER TABLE scores ADD COLUMN suspicions TEXT NOT NULL DEFAULT '';
+ALTER TABLE scores ADD COLUMN approved INTEGER NOT NULL DEFAULT 1;
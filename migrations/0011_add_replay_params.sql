@@ -0,0 +1,5 @@
+-- 之前保存的回放未经服务器验证, 也没有记录布局的设置, 因此全部删除
+DELETE FROM replays;
+ALTER TABLE replays ADD COLUMN difficulty TEXT NOT NULL DEFAULT '';
+ALTER TABLE replays ADD COLUMN size TEXT NOT NULL DEFAULT '';
+ALTER TABLE replays ADD COLUMN mode TEXT NOT NULL DEFAULT '';
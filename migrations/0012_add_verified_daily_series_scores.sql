@@ -0,0 +1,3 @@
+-- 与 0009 相同, 之前由客户端提交用时的每日挑战和连续挑战成绩未经验证, 默认不再显示在排行榜上
+ALTER TABLE daily_scores ADD COLUMN verified INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE series_scores ADD COLUMN verified INTEGER NOT NULL DEFAULT 0;
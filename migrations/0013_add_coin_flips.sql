@@ -0,0 +1,7 @@
+-- 每局 (包括失败的对局) 五五开的猜测次数和猜中次数, 只记录服务器保存的每日挑战尝试
+CREATE TABLE coin_flips(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username TEXT NOT NULL,
+    attempted INTEGER NOT NULL,
+    won INTEGER NOT NULL
+);
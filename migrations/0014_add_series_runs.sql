@@ -0,0 +1,7 @@
+-- 服务器为每次连续挑战分配的种子, 各块棋盘的种子由它和棋盘的序号推出, 每次挑战只能提交一次成绩
+CREATE TABLE series_runs(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username TEXT NOT NULL,
+    seed TEXT NOT NULL,
+    submitted INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,5 @@
+-- 每日挑战的排名尝试在获取棋盘时记录, 布局由服务器保存: replay 为到目前为止的操作,
+-- started_at 为第一次挖掘的时间(毫秒), 之前的尝试都已结束
+ALTER TABLE daily_scores ADD COLUMN replay TEXT NOT NULL DEFAULT '';
+ALTER TABLE daily_scores ADD COLUMN started_at INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE daily_scores ADD COLUMN finished INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,5 @@
+-- 与 0010 相同, 每日挑战和连续挑战的成绩同样经过分析, 可疑的成绩等待审核后才显示在排行榜上
+ALTER TABLE daily_scores ADD COLUMN suspicions TEXT NOT NULL DEFAULT '';
+ALTER TABLE daily_scores ADD COLUMN approved INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE series_scores ADD COLUMN suspicions TEXT NOT NULL DEFAULT '';
+ALTER TABLE series_scores ADD COLUMN approved INTEGER NOT NULL DEFAULT 1;
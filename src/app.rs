@@ -4,7 +4,9 @@ use leptos_router::*;
 
 use crate::app_error::AppError;
 use crate::game_settings::{apply_setting, fetch_setting, Theme, Username};
-use crate::pages::{Error, Game, HomePage, Scores};
+use crate::pages::{
+    Daily, Error, Game, HomePage, Lobby, Race, Review, Scores, Trainer, Verified, Versus, Watch,
+};
 
 // 定义两个常量，分别包含浅色和深色模式的SVG图标
 const LIGHTBULB_SVG: &str = include_str!("../svgs/lightbulb.svg"); // 浅色模式图标
@@ -78,6 +80,14 @@ pub fn App() -> impl IntoView {
                     <Route path="" view=HomePage />
                     <Route path="game" view=Game />
                     <Route path="scores" view=Scores />
+                    <Route path="daily" view=Daily />
+                    <Route path="trainer" view=Trainer />
+                    <Route path="race" view=Race />
+                    <Route path="lobby" view=Lobby />
+                    <Route path="versus" view=Versus />
+                    <Route path="verified" view=Verified />
+                    <Route path="watch/:id" view=Watch />
+                    <Route path="review" view=Review />
                 </Routes>
             </main>
         </Router>
@@ -2,6 +2,8 @@ use http::status::StatusCode;
 use leptos_router::ParamsError;
 use thiserror::Error;
 
+use crate::board_mask::ParseMaskError;
+
 // 应用错误枚举类型
 #[derive(Clone, Debug, Error)]
 pub enum AppError {
@@ -11,6 +13,18 @@ pub enum AppError {
     // 参数读取错误，并包含原始错误信息
     #[error("Error reading new game settings: {0}")]
     ParamsError(#[from] ParamsError),
+    // 自定义棋盘形状错误
+    #[error("Error reading custom board shape: {0}")]
+    MaskError(#[from] ParseMaskError),
+    // 每日挑战不可用 (服务器未配置密钥或数据库错误)
+    #[error("Daily challenge is unavailable")]
+    DailyUnavailable,
+    // 自适应模式不可用 (数据库错误)
+    #[error("Adaptive mode is unavailable")]
+    AdaptiveUnavailable,
+    // 连续挑战不可用 (数据库错误)
+    #[error("Series mode is unavailable")]
+    SeriesUnavailable,
 }
 
 impl AppError {
@@ -21,6 +35,14 @@ impl AppError {
             AppError::NotFound => StatusCode::NOT_FOUND,
             // 参数错误对应400状态码
             AppError::ParamsError(_) => StatusCode::BAD_REQUEST,
+            // 自定义形状错误对应400状态码
+            AppError::MaskError(_) => StatusCode::BAD_REQUEST,
+            // 每日挑战不可用对应503状态码
+            AppError::DailyUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            // 自适应模式不可用对应503状态码
+            AppError::AdaptiveUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            // 连续挑战不可用对应503状态码
+            AppError::SeriesUnavailable => StatusCode::SERVICE_UNAVAILABLE,
         }
     }
 }
@@ -0,0 +1,217 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::game_settings::Shape;
+
+// 解析自定义棋盘形状错误类型
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ParseMaskError {
+    #[error("Board mask is empty")]
+    Empty,
+    #[error("Board mask contains invalid character '{0}'")]
+    InvalidChar(char),
+    #[error("Board mask needs at least {} cells", BoardMask::MIN_CELLS)]
+    TooSmall,
+}
+
+// 棋盘形状遮罩, 记录网格中每个位置是否存在单元格
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BoardMask {
+    rows: isize,
+    columns: isize,
+    cells: Vec<bool>, // 按行存储, true 表示该位置存在单元格
+}
+
+impl BoardMask {
+    // 自定义形状至少需要的单元格数量, 保证第一次点击的安全区域之外仍有位置放置地雷
+    pub const MIN_CELLS: usize = 16;
+
+    // 根据内置形状生成遮罩
+    pub fn from_shape(shape: Shape, rows: isize, columns: isize) -> Self {
+        // 将网格坐标映射到以中心为原点的 [-1, 1] 区间
+        let normalized = |row: isize, column: isize| {
+            (
+                (column as f64 + 0.5) / columns as f64 * 2.0 - 1.0,
+                1.0 - (row as f64 + 0.5) / rows as f64 * 2.0,
+            )
+        };
+
+        let contains = |row: isize, column: isize| {
+            let (x, y) = normalized(row, column);
+
+            match shape {
+                Shape::Rectangle | Shape::Custom => true,
+                Shape::Circle => x * x + y * y <= 1.0,
+                Shape::Donut => (0.16..=1.0).contains(&(x * x + y * y)),
+                Shape::Heart => {
+                    // 心形曲线 (x² + y² - 1)³ - x²y³ <= 0, 缩放到网格范围内
+                    let (x, y) = (x * 1.2, y * 1.25 + 0.2);
+                    (x * x + y * y - 1.0).powi(3) - x * x * y.powi(3) <= 0.0
+                }
+            }
+        };
+
+        Self {
+            rows,
+            columns,
+            cells: (0..rows)
+                .flat_map(|row| (0..columns).map(move |column| (row, column)))
+                .map(|(row, column)| contains(row, column))
+                .collect(),
+        }
+    }
+
+    // 使用按行存储的单元格创建遮罩
+    pub fn from_cells(rows: isize, columns: isize, cells: Vec<bool>) -> Self {
+        Self {
+            rows,
+            columns,
+            cells,
+        }
+    }
+
+    // 获取遮罩的行数和列数
+    pub fn dimensions(&self) -> (isize, isize) {
+        (self.rows, self.columns)
+    }
+
+    // 判断指定位置是否存在单元格
+    pub fn contains(&self, row: isize, column: isize) -> bool {
+        row >= 0
+            && column >= 0
+            && row < self.rows
+            && column < self.columns
+            && self.cells[(row * self.columns + column) as usize]
+    }
+
+    // 存在的单元格数量
+    pub fn count(&self) -> usize {
+        self.cells.iter().filter(|&&exists| exists).count()
+    }
+}
+
+// 从文本解析自定义形状, 行之间使用换行或 '-' 分隔, '#' 或 'x' 表示单元格, '.' 表示空位
+impl FromStr for BoardMask {
+    type Err = ParseMaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s
+            .split(['\n', '-'])
+            .map(str::trim_end)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>();
+
+        let rows = lines.len() as isize;
+        let columns = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0) as isize;
+        if rows == 0 || columns == 0 {
+            return Err(ParseMaskError::Empty);
+        }
+
+        let mut cells = Vec::with_capacity((rows * columns) as usize);
+        for line in lines {
+            let mut width = 0;
+            for c in line.chars() {
+                cells.push(match c {
+                    '#' | 'x' => true,
+                    '.' | ' ' => false,
+                    c => return Err(ParseMaskError::InvalidChar(c)),
+                });
+                width += 1;
+            }
+            // 较短的行用空位补齐
+            cells.extend(std::iter::repeat(false).take(columns as usize - width));
+        }
+
+        let mask = Self {
+            rows,
+            columns,
+            cells,
+        };
+
+        if mask.count() < Self::MIN_CELLS {
+            return Err(ParseMaskError::TooSmall);
+        }
+
+        Ok(mask)
+    }
+}
+
+// 实现Display trait, 输出可被重新解析的文本形式 (只使用无需URL编码的字符, 便于存入 cookie)
+impl Display for BoardMask {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.columns == 0 {
+            return Ok(());
+        }
+
+        let text = self
+            .cells
+            .chunks(self.columns as usize)
+            .map(|row| {
+                row.iter()
+                    .map(|&exists| if exists { 'x' } else { '.' })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("-");
+
+        write!(f, "{text}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_split_on_newlines_or_dashes() {
+        let dashes = "xxxxx-x...x-xxxxx-x...x-xxxxx".parse::<BoardMask>();
+        let newlines = "#####\n#...#\n#####\n#...#\n#####\n".parse::<BoardMask>();
+
+        assert_eq!(dashes, newlines);
+        let mask = dashes.expect("mask parses");
+        assert_eq!(mask.dimensions(), (5, 5));
+        assert_eq!(mask.count(), 19);
+        assert!(mask.contains(0, 0));
+        assert!(!mask.contains(1, 1));
+    }
+
+    #[test]
+    fn short_rows_are_padded_with_gaps() {
+        let mask = "xxxxxx-xx-xxxxxx-xxxxxx"
+            .parse::<BoardMask>()
+            .expect("mask parses");
+
+        assert_eq!(mask.dimensions(), (4, 6));
+        assert!(mask.contains(1, 1));
+        assert!(!mask.contains(1, 2));
+        assert!(!mask.contains(1, 5));
+    }
+
+    #[test]
+    fn invalid_masks_are_rejected() {
+        assert_eq!("".parse::<BoardMask>(), Err(ParseMaskError::Empty));
+        assert_eq!("-\n-".parse::<BoardMask>(), Err(ParseMaskError::Empty));
+        assert_eq!(
+            "xxxx-xxox-xxxx-xxxx".parse::<BoardMask>(),
+            Err(ParseMaskError::InvalidChar('o'))
+        );
+        assert_eq!(
+            "xxxx-x..x-xxxx".parse::<BoardMask>(),
+            Err(ParseMaskError::TooSmall)
+        );
+    }
+
+    #[test]
+    fn built_in_shapes_round_trip_through_text() {
+        for shape in [Shape::Rectangle, Shape::Circle, Shape::Heart, Shape::Donut] {
+            let mask = BoardMask::from_shape(shape, 10, 15);
+            assert_eq!(mask.to_string().parse::<BoardMask>(), Ok(mask));
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::str::FromStr;
+
+use rand::{seq::SliceRandom, Rng};
+use serde::{de::IntoDeserializer, Deserialize, Serialize};
+
+use crate::{
+    game_logic::GameStatus,
+    solver::{Knowledge, Layout, Solver},
+};
+
+// 电脑对手的强度
+#[derive(PartialEq, Copy, Clone, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BotStrength {
+    Casual,
+    #[default]
+    Steady,
+    Expert,
+}
+
+impl BotStrength {
+    pub const ALL: [BotStrength; 3] = [Self::Casual, Self::Steady, Self::Expert];
+
+    // 每一步之前的思考时间(毫秒)
+    pub fn think_ms(&self) -> u32 {
+        match self {
+            Self::Casual => 1_500,
+            Self::Steady => 800,
+            Self::Expert => 300,
+        }
+    }
+
+    // 猜测时可以接受的额外风险: 在概率不超过 最低概率+容差 的单元格中随机选择
+    fn guess_tolerance(&self) -> f64 {
+        match self {
+            Self::Casual => 0.25,
+            Self::Steady => 0.1,
+            Self::Expert => 0.0,
+        }
+    }
+}
+
+// 实现从字符串解析BotStrength
+impl FromStr for BotStrength {
+    type Err = serde::de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(s.into_deserializer())
+    }
+}
+
+// 实现Display trait用于格式化输出电脑对手的强度BotStrength
+impl std::fmt::Display for BotStrength {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.serialize(f)
+    }
+}
+
+// 电脑对手: 在与玩家相同的布局上, 从同一个安全起点开始, 只根据已翻开的数字推理和猜测
+pub struct Bot {
+    layout: Layout,
+    mines: usize,
+    strength: BotStrength,
+    start: usize,
+    revealed: Vec<bool>, // 已翻开的单元格
+    opened: Vec<usize>,  // 按顺序挖开的单元格, 用于重建推理
+    cleared: usize,
+    clear_total: usize,
+    status: GameStatus,
+}
+
+impl Bot {
+    // 创建电脑对手, start 为安全起点的索引
+    pub fn new(layout: Layout, strength: BotStrength, start: usize) -> Self {
+        let mines = layout
+            .indices()
+            .filter(|&index| layout.is_mine(index))
+            .count();
+        let clear_total = layout.indices().count() - mines;
+        let revealed = vec![false; layout.indices().max().map_or(0, |index| index + 1)];
+
+        Self {
+            layout,
+            mines,
+            strength,
+            start,
+            revealed,
+            opened: Vec::new(),
+            cleared: 0,
+            clear_total,
+            status: GameStatus::Started,
+        }
+    }
+
+    pub fn strength(&self) -> BotStrength {
+        self.strength
+    }
+
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+
+    // 已清除的单元格数量和总共需要清除的单元格数量
+    pub fn progress(&self) -> (usize, usize) {
+        (self.cleared, self.clear_total)
+    }
+
+    // 走一步: 第一步挖开起点, 之后挖开推理确定安全的单元格, 推理卡住时按概率猜测
+    pub fn step(&mut self, rng: &mut impl Rng) {
+        if self.status != GameStatus::Started {
+            return;
+        }
+
+        let index = if self.opened.is_empty() {
+            self.start
+        } else {
+            self.choose(rng)
+        };
+        self.dig(index);
+    }
+
+    // 选择下一个要挖开的单元格
+    fn choose(&self, rng: &mut impl Rng) -> usize {
+        let mut solver = Solver::new(&self.layout, self.opened.iter().copied());
+
+        // 推理得出的第一个尚未翻开的安全单元格
+        loop {
+            let found = solver.step();
+            if found.is_empty() {
+                break;
+            }
+            if let Some(&(index, _)) = found
+                .iter()
+                .find(|&&(index, knowledge)| knowledge == Knowledge::Safe && !self.revealed[index])
+            {
+                return index;
+            }
+        }
+
+        // 推理卡住, 在风险最低的单元格中猜测 (较弱的对手会接受更高的风险)
+        let probabilities = solver.mine_probabilities(self.mines);
+        let lowest = probabilities
+            .iter()
+            .map(|&(_, probability)| probability)
+            .reduce(f64::min)
+            .unwrap_or_default();
+        let candidates = probabilities
+            .into_iter()
+            .filter(|&(_, probability)| probability <= lowest + self.strength.guess_tolerance())
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+
+        *candidates
+            .choose(rng)
+            .expect("unfinished board has unknown cells")
+    }
+
+    // 挖开一个单元格, 踩到地雷则失败, 清除所有安全单元格则获胜
+    fn dig(&mut self, index: usize) {
+        self.opened.push(index);
+
+        if self.layout.is_mine(index) {
+            self.status = GameStatus::GameOver;
+            return;
+        }
+
+        for cell in self.layout.opening(index) {
+            if !self.revealed[cell] {
+                self.revealed[cell] = true;
+                self.cleared += 1;
+            }
+        }
+
+        if self.cleared == self.clear_total {
+            self.status = GameStatus::Victory;
+        }
+    }
+}
@@ -0,0 +1,594 @@
+use std::collections::HashMap;
+
+use gloo_timers::future::TimeoutFuture;
+use leptos::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    game_logic::{submit_score, CellInteraction, CellKind, GameState, GameStatus, ADJACENTS},
+    game_settings::{Difficulty, Mode, Size, Username},
+    pages::scores::post_score,
+    replay::{EndlessMove, EndlessReplay, MoveKind},
+    utils::{now_millis, to_time},
+    validation::validate_username,
+};
+
+// 区块的边长 (单元格数量)
+pub const CHUNK_SIZE: isize = 8;
+
+// 区块坐标 (区块所在的行, 区块所在的列)
+pub type ChunkPos = (isize, isize);
+
+// 获取单元格所在的区块坐标
+pub(crate) fn chunk_of(row: isize, column: isize) -> ChunkPos {
+    (row.div_euclid(CHUNK_SIZE), column.div_euclid(CHUNK_SIZE))
+}
+
+// 获取单元格在区块内的索引
+pub(crate) fn index_in_chunk(row: isize, column: isize) -> usize {
+    (row.rem_euclid(CHUNK_SIZE) * CHUNK_SIZE + column.rem_euclid(CHUNK_SIZE)) as usize
+}
+
+// 由游戏种子和区块坐标计算区块种子 (splitmix64 混合), 同一种子下每个区块的地雷布局固定
+fn chunk_seed(seed: u64, (chunk_row, chunk_column): ChunkPos) -> u64 {
+    let mut z = seed
+        ^ (chunk_row as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (chunk_column as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// 生成区块的地雷布局, 客户端和服务器验证回放时使用同样的规则
+pub(crate) fn chunk_mines(seed: u64, chunk: ChunkPos, probability: f64) -> Vec<bool> {
+    let mut rng = StdRng::seed_from_u64(chunk_seed(seed, chunk));
+    (0..CHUNK_SIZE * CHUNK_SIZE)
+        .map(|_| rng.gen_bool(probability))
+        .collect()
+}
+
+// 定义无尽模式的游戏信息结构体
+#[derive(Default)]
+pub struct EndlessInfo {
+    seed: u64,                    // 当前局的种子, 用于忽略上一局提交成绩的结果
+    elapsed_seconds: i64,         // 游戏开始后经过的秒数
+    cleared: isize,               // 已清除的单元格数量
+    status: GameStatus,           // 游戏状态
+    submit_error: Option<String>, // 成绩没有被记录的原因 (验证失败或服务器拒绝)
+}
+
+// 将游戏信息转换为视图
+impl EndlessInfo {
+    pub fn to_view(&self) -> impl IntoView {
+        let get_username = move || (expect_context::<ReadSignal<Username>>())().name; // 获取用户名
+        let time = to_time(self.elapsed_seconds); // 转换时间为字符串
+        let submit_error = self
+            .submit_error
+            .as_ref()
+            .map(|error| format!(" · Not ranked: {error}"))
+            .unwrap_or_default();
+
+        match self.status {
+            GameStatus::Idle => {
+                view! {
+                    ""
+                    <br />
+                    ""
+                    <br />
+                    ""
+                    <br />
+                }
+            }
+            status if status.is_finished() => {
+                view! {
+                    {move || format!("Game over, {} 😭", get_username())}
+                    <br />
+                    {format!("{} cells cleared{submit_error}", self.cleared)}
+                    <br />
+                    "Time - " {time}
+                    <br />
+                }
+            }
+            _ => {
+                view! {
+                    {format!("{} cleared", self.cleared)}
+                    <br />
+                    {time}
+                    <br />
+                    ""
+                    <br />
+                }
+            }
+        }
+    }
+}
+
+// 定义无尽模式的单元格状态结构体 (单元格类型由种子计算, 无需保存)
+#[derive(Default, Clone, Copy)]
+struct EndlessCell {
+    interaction: CellInteraction,                             // 交互状态
+    signal: Option<WriteSignal<(CellInteraction, CellKind)>>, // 用于更新单元格状态的信号
+}
+
+// 定义无尽模式的游戏状态结构体
+pub struct EndlessState {
+    difficulty: Difficulty,
+    seed: u64,                                  // 地雷布局种子
+    safe_center: Option<(isize, isize)>,        // 第一次点击的位置, 其周围不会有地雷
+    mines: HashMap<ChunkPos, Vec<bool>>,        // 已生成的区块地雷布局
+    cells: HashMap<ChunkPos, Vec<EndlessCell>>, // 已显示区块的单元格状态
+    cleared: isize,
+    status: GameStatus,
+    chunks: ReadSignal<Vec<ChunkPos>>,      // 已显示的区块列表信号
+    set_chunks: WriteSignal<Vec<ChunkPos>>, // 更新区块列表信号
+    info: ReadSignal<EndlessInfo>,          // 游戏信息信号
+    set_info: WriteSignal<EndlessInfo>,     // 更新游戏信息信号
+    timer: Action<(), ()>,                  // 计时器
+    replay: EndlessReplay,                  // 本局的回放, 提交成绩时由服务器验证
+    started_at: i64,                        // 第一次点击的时间(毫秒)
+}
+
+impl EndlessState {
+    // 初始显示的区块范围 (以原点所在区块为中心)
+    const INITIAL_RADIUS: isize = 1;
+
+    // 初始化游戏状态
+    pub fn new(difficulty: Difficulty) -> Self {
+        let seed = rand::random();
+        let (info, set_info) = create_signal(EndlessInfo {
+            seed,
+            ..Default::default()
+        });
+
+        // 创建计时器
+        let timer = create_action(move |&()| async move {
+            for second in 0..i64::MAX {
+                let mut stop = false;
+
+                let disposed = set_info
+                    .try_update(|info| {
+                        if matches!(info.status, GameStatus::Started) {
+                            info.elapsed_seconds = second;
+                        } else {
+                            stop = true;
+                        }
+                    })
+                    .is_none();
+
+                if stop || disposed {
+                    break;
+                }
+
+                TimeoutFuture::new(1_000).await;
+            }
+        });
+
+        let initial_chunks = Self::initial_chunks();
+        let (chunks, set_chunks) = create_signal(initial_chunks.clone());
+
+        Self {
+            difficulty,
+            seed,
+            safe_center: None,
+            mines: HashMap::new(),
+            cells: initial_chunks
+                .into_iter()
+                .map(|chunk| (chunk, Self::empty_chunk()))
+                .collect(),
+            cleared: 0,
+            status: Default::default(),
+            chunks,
+            set_chunks,
+            info,
+            set_info,
+            timer,
+            replay: EndlessReplay {
+                seed,
+                moves: Vec::new(),
+            },
+            started_at: 0,
+        }
+    }
+
+    // 初始显示的区块
+    fn initial_chunks() -> Vec<ChunkPos> {
+        let radius = Self::INITIAL_RADIUS;
+
+        (-radius..=radius)
+            .flat_map(|chunk_row| {
+                (-radius..=radius).map(move |chunk_column| (chunk_row, chunk_column))
+            })
+            .collect()
+    }
+
+    // 新区块的单元格状态
+    fn empty_chunk() -> Vec<EndlessCell> {
+        vec![Default::default(); (CHUNK_SIZE * CHUNK_SIZE) as usize]
+    }
+
+    // 获取已显示的区块列表信号
+    pub fn chunks_signal(&self) -> ReadSignal<Vec<ChunkPos>> {
+        self.chunks
+    }
+
+    // 获取游戏信息信号
+    pub fn info_signal(&self) -> ReadSignal<EndlessInfo> {
+        self.info
+    }
+
+    // 判断指定位置是否为地雷 (按区块种子生成, 第一次点击及其相邻区域除外)
+    fn is_mine(&mut self, row: isize, column: isize) -> bool {
+        if let Some((safe_row, safe_column)) = self.safe_center {
+            if (row - safe_row).abs() <= 1 && (column - safe_column).abs() <= 1 {
+                return false;
+            }
+        }
+
+        let chunk = chunk_of(row, column);
+        let (seed, probability) = (self.seed, GameState::mine_probability(self.difficulty));
+
+        self.mines
+            .entry(chunk)
+            .or_insert_with(|| chunk_mines(seed, chunk, probability))[index_in_chunk(row, column)]
+    }
+
+    // 计算指定位置的单元格类型, 周围的区块即使尚未显示也会参与计算
+    fn kind(&mut self, row: isize, column: isize) -> CellKind {
+        if self.is_mine(row, column) {
+            CellKind::Mine(1)
+        } else {
+            let mines = ADJACENTS
+                .iter()
+                .filter(|(row_offset, column_offset)| {
+                    self.is_mine(row + row_offset, column + column_offset)
+                })
+                .count();
+
+            CellKind::Clear(mines as u32)
+        }
+    }
+
+    // 获取指定位置的单元格状态 (区块未显示时返回None)
+    fn cell(&self, row: isize, column: isize) -> Option<&EndlessCell> {
+        self.cells
+            .get(&chunk_of(row, column))
+            .map(|cells| &cells[index_in_chunk(row, column)])
+    }
+
+    // 获取指定位置的可变单元格状态 (区块未显示时先显示该区块)
+    fn cell_mut(&mut self, row: isize, column: isize) -> &mut EndlessCell {
+        let chunk = chunk_of(row, column);
+        if !self.cells.contains_key(&chunk) {
+            self.show_chunk(chunk);
+        }
+
+        &mut self.cells.get_mut(&chunk).expect("chunk shown")[index_in_chunk(row, column)]
+    }
+
+    // 显示新的区块
+    fn show_chunk(&mut self, chunk: ChunkPos) {
+        self.cells.insert(chunk, Self::empty_chunk());
+
+        // 新区块的单元格组件会在渲染时注册并更新游戏状态, 因此等到本次更新结束后再更新区块列表
+        let set_chunks = self.set_chunks;
+        queue_microtask(move || set_chunks.update(|chunks| chunks.push(chunk)));
+    }
+
+    // 确保指定单元格所在区块周围的区块都已显示, 使棋盘边缘始终有未挖掘的单元格
+    fn expand_around(&mut self, row: isize, column: isize) {
+        let (chunk_row, chunk_column) = chunk_of(row, column);
+
+        for (row_offset, column_offset) in std::iter::once((0, 0)).chain(ADJACENTS) {
+            let chunk = (chunk_row + row_offset, chunk_column + column_offset);
+            if !self.cells.contains_key(&chunk) {
+                self.show_chunk(chunk);
+            }
+        }
+    }
+
+    // 注册单元格状态更新信号, 并同步单元格当前的状态
+    pub fn register_cell(
+        &mut self,
+        row: isize,
+        column: isize,
+        set_cell_state: WriteSignal<(CellInteraction, CellKind)>,
+    ) {
+        let interaction = self.cell_mut(row, column).interaction;
+        let kind = match interaction {
+            CellInteraction::Cleared => self.kind(row, column),
+            _ => Default::default(),
+        };
+
+        self.cell_mut(row, column).signal = Some(set_cell_state);
+        set_cell_state((interaction, kind));
+    }
+
+    // 更新单元格状态并通知对应的单元格组件
+    fn set_cell(
+        &mut self,
+        row: isize,
+        column: isize,
+        interaction: CellInteraction,
+        kind: CellKind,
+    ) {
+        let cell = self.cell_mut(row, column);
+        cell.interaction = interaction;
+
+        if let Some(set_cell_state) = cell.signal {
+            set_cell_state((interaction, kind));
+        }
+    }
+
+    // 挖掘指定位置的单元格
+    pub fn dig(&mut self, row: isize, column: isize) {
+        match self.status {
+            status if status.is_finished() => {
+                return;
+            }
+            GameStatus::Idle => {
+                self.safe_center = Some((row, column));
+                self.status = GameStatus::Started;
+                self.started_at = now_millis();
+                self.timer.dispatch(());
+            }
+            _ => {}
+        }
+
+        self.record_move(MoveKind::Dig, row, column);
+        self.dig_inner(row, column);
+        self.update_score();
+    }
+
+    // 挖掘的内部逻辑, 使用显式栈代替递归, 避免连续的空白区域过大导致栈溢出
+    fn dig_inner(&mut self, row: isize, column: isize) {
+        let mut pending = match self.cell(row, column).map(|cell| cell.interaction) {
+            Some(CellInteraction::Cleared) => self.chord_targets(row, column),
+            _ => vec![(row, column)],
+        };
+
+        while let Some((row, column)) = pending.pop() {
+            if !matches!(
+                self.cell_mut(row, column).interaction,
+                CellInteraction::Untouched
+            ) {
+                continue;
+            }
+
+            let kind = self.kind(row, column);
+            self.set_cell(row, column, CellInteraction::Cleared, kind);
+
+            match kind {
+                CellKind::Mine(_) => {
+                    self.status = GameStatus::GameOver;
+                    return;
+                }
+                CellKind::Clear(0) => {
+                    pending.extend(ADJACENTS.iter().map(|(row_offset, column_offset)| {
+                        (row + row_offset, column + column_offset)
+                    }));
+                }
+                _ => {}
+            }
+
+            self.cleared += 1;
+            self.expand_around(row, column);
+        }
+    }
+
+    // 安全点击策略: 周围的旗帜数量等于单元格的数字时, 返回周围所有未触及的单元格
+    fn chord_targets(&mut self, row: isize, column: isize) -> Vec<(isize, isize)> {
+        let CellKind::Clear(mines) = self.kind(row, column) else {
+            return Vec::new();
+        };
+
+        let neighbours =
+            ADJACENTS.map(|(row_offset, column_offset)| (row + row_offset, column + column_offset));
+        let flags = neighbours
+            .iter()
+            .filter(|&&(row, column)| {
+                matches!(
+                    self.cell(row, column).map(|cell| cell.interaction),
+                    Some(CellInteraction::Flagged(_))
+                )
+            })
+            .count();
+
+        if flags as u32 != mines {
+            return Vec::new();
+        }
+
+        neighbours
+            .into_iter()
+            .filter(|&(row, column)| {
+                self.cell(row, column).map_or(true, |cell| {
+                    matches!(cell.interaction, CellInteraction::Untouched)
+                })
+            })
+            .collect()
+    }
+
+    // 标记或取消标记指定位置的单元格(插旗或拔旗)
+    pub fn flag(&mut self, row: isize, column: isize) {
+        if self.status.is_finished() {
+            return;
+        }
+        self.record_move(MoveKind::Flag, row, column);
+
+        let interaction = match self.cell_mut(row, column).interaction {
+            CellInteraction::Untouched => CellInteraction::Flagged(1),
+            CellInteraction::Flagged(_) => CellInteraction::Untouched,
+            _ => return,
+        };
+
+        self.set_cell(row, column, interaction, Default::default());
+    }
+
+    // 记录一次操作, 游戏开始前的操作 (插旗) 记为第0毫秒
+    fn record_move(&mut self, kind: MoveKind, row: isize, column: isize) {
+        let millis = match self.status {
+            GameStatus::Idle => 0,
+            _ => now_millis() - self.started_at,
+        };
+        self.replay.moves.push(EndlessMove {
+            millis,
+            kind,
+            row,
+            column,
+        });
+    }
+
+    // 更新得分, 踩到地雷时显示已显示区块中的所有地雷, 并以回放提交清除的单元格数量
+    fn update_score(&mut self) {
+        if matches!(self.status, GameStatus::GameOver) {
+            let positions = self
+                .cells
+                .keys()
+                .flat_map(|&(chunk_row, chunk_column)| {
+                    (0..CHUNK_SIZE * CHUNK_SIZE).map(move |index| {
+                        (
+                            chunk_row * CHUNK_SIZE + index / CHUNK_SIZE,
+                            chunk_column * CHUNK_SIZE + index % CHUNK_SIZE,
+                        )
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            for (row, column) in positions {
+                let untouched = matches!(
+                    self.cell(row, column).map(|cell| cell.interaction),
+                    Some(CellInteraction::Untouched)
+                );
+
+                if untouched && self.is_mine(row, column) {
+                    self.set_cell(row, column, CellInteraction::Cleared, CellKind::Mine(1));
+                }
+            }
+
+            if self.cleared > 0 {
+                self.submit_score();
+            }
+        }
+
+        self.set_info.update(|info| {
+            info.cleared = self.cleared;
+            info.status = self.status;
+        });
+    }
+
+    // 以回放提交成绩, 验证失败或服务器拒绝时在游戏信息中显示原因
+    fn submit_score(&self) {
+        let username = (expect_context::<ReadSignal<Username>>())().name;
+        let set_info = self.set_info;
+        let seed = self.seed;
+
+        // 无尽模式按清除的单元格数量排名, 只验证用户名
+        submit_score(
+            validate_username(&username),
+            post_score(
+                username,
+                self.difficulty,
+                Size::default(),
+                Mode::Endless,
+                self.replay.to_string(),
+            ),
+            // 只显示在提交成绩的这一局上 (页面销毁后忽略)
+            move |message| {
+                let _ = set_info.try_update(|info| {
+                    if info.seed == seed {
+                        info.submit_error = Some(message);
+                    }
+                });
+            },
+        );
+    }
+
+    // 重置游戏状态, 使用新的种子并只保留初始区块
+    pub fn reset(&mut self) {
+        self.seed = rand::random();
+        self.replay = EndlessReplay {
+            seed: self.seed,
+            moves: Vec::new(),
+        };
+        self.safe_center = None;
+        self.mines.clear();
+        self.cleared = Default::default();
+        self.status = Default::default();
+
+        let initial_chunks = Self::initial_chunks();
+        self.cells.retain(|chunk, _| initial_chunks.contains(chunk));
+
+        for cell in self.cells.values_mut().flatten() {
+            cell.interaction = Default::default();
+
+            if let Some(set_cell_state) = cell.signal {
+                set_cell_state((Default::default(), Default::default()));
+            }
+        }
+
+        (self.set_chunks)(initial_chunks);
+        (self.set_info)(EndlessInfo {
+            seed: self.seed,
+            ..Default::default()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: u64 = 7;
+    const PROBABILITY: f64 = 0.15;
+
+    #[test]
+    fn negative_cells_map_to_their_own_chunks() {
+        assert_eq!(chunk_of(0, 0), (0, 0));
+        assert_eq!(chunk_of(7, 8), (0, 1));
+        assert_eq!(chunk_of(-1, -1), (-1, -1));
+        assert_eq!(chunk_of(-8, -9), (-1, -2));
+
+        assert_eq!(index_in_chunk(0, 0), 0);
+        assert_eq!(index_in_chunk(8, 9), 1);
+        assert_eq!(
+            index_in_chunk(-1, -1),
+            (CHUNK_SIZE * CHUNK_SIZE - 1) as usize
+        );
+        assert_eq!(index_in_chunk(-8, -7), 1);
+    }
+
+    #[test]
+    fn chunk_mines_depend_only_on_the_seed_and_position() {
+        let mines = chunk_mines(SEED, (2, -3), PROBABILITY);
+
+        assert_eq!(mines.len(), (CHUNK_SIZE * CHUNK_SIZE) as usize);
+        assert_eq!(chunk_mines(SEED, (2, -3), PROBABILITY), mines);
+        assert_ne!(chunk_mines(SEED, (-3, 2), PROBABILITY), mines);
+        assert_ne!(chunk_mines(SEED + 1, (2, -3), PROBABILITY), mines);
+    }
+
+    #[test]
+    fn chunk_mines_follow_the_mine_probability() {
+        let chunks = (-5..5).flat_map(|row| (-5..5).map(move |column| (row, column)));
+        let (mines, cells) = chunks
+            .map(|chunk| chunk_mines(SEED, chunk, PROBABILITY))
+            .fold((0, 0), |(mines, cells), chunk| {
+                (
+                    mines + chunk.iter().filter(|&&mine| mine).count(),
+                    cells + chunk.len(),
+                )
+            });
+
+        let density = mines as f64 / cells as f64;
+        assert!((density - PROBABILITY).abs() < 0.02, "density {density}");
+    }
+
+    #[test]
+    fn initial_chunks_surround_the_origin() {
+        let chunks = EndlessState::initial_chunks();
+
+        assert_eq!(chunks.len(), 9);
+        assert!(chunks.contains(&(0, 0)));
+        assert!(chunks.contains(&(-1, 1)));
+        assert!(!chunks.contains(&(2, 0)));
+    }
+}
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+
+// 夺旗模式的玩家数量
+pub const FLAGS_PLAYERS: usize = 2;
+
+// 夺旗模式的比分: 玩家轮流挖掘, 找到地雷得分并继续挖掘, 挖开安全的单元格则轮到对方
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FlagsScore {
+    pub turn: usize,                  // 当前轮到的玩家 (从0开始)
+    pub scores: [u32; FLAGS_PLAYERS], // 各玩家找到的地雷数量
+}
+
+impl FlagsScore {
+    // 当前玩家找到地雷, 继续由该玩家挖掘
+    pub fn found_mine(&mut self, mines: u32) {
+        self.scores[self.turn] += mines;
+    }
+
+    // 轮到下一位玩家
+    pub fn pass_turn(&mut self) {
+        self.turn = (self.turn + 1) % FLAGS_PLAYERS;
+    }
+
+    // 已找到的地雷总数
+    pub fn found(&self) -> u32 {
+        self.scores.iter().sum()
+    }
+
+    // 找到过半地雷的玩家
+    pub fn winner(&self, total_mines: u32) -> Option<usize> {
+        self.scores
+            .iter()
+            .position(|&score| score * 2 > total_mines)
+    }
+
+    // 有玩家获胜或所有地雷都已找到 (地雷数量为偶数时可能平局)
+    pub fn is_over(&self, total_mines: u32) -> bool {
+        self.winner(total_mines).is_some() || self.found() >= total_mines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 找到地雷后继续由当前玩家挖掘, 挖开安全单元格后轮到对方
+    #[test]
+    fn finding_a_mine_keeps_the_turn() {
+        let mut score = FlagsScore::default();
+        score.found_mine(2);
+        assert_eq!(score.turn, 0);
+        assert_eq!(score.scores, [2, 0]);
+
+        score.pass_turn();
+        score.found_mine(1);
+        assert_eq!(score.turn, 1);
+        assert_eq!(score.scores, [2, 1]);
+
+        score.pass_turn();
+        assert_eq!(score.turn, 0);
+        assert_eq!(score.found(), 3);
+    }
+
+    // 找到过半地雷的玩家直接获胜, 剩余地雷无需再找
+    #[test]
+    fn more_than_half_the_mines_wins() {
+        let score = FlagsScore {
+            turn: 1,
+            scores: [5, 2],
+        };
+        assert_eq!(score.winner(10), None);
+        assert!(!score.is_over(10));
+
+        let score = FlagsScore {
+            turn: 1,
+            scores: [6, 2],
+        };
+        assert_eq!(score.winner(10), Some(0));
+        assert!(score.is_over(10));
+    }
+
+    // 地雷数量为偶数且双方各找到一半时平局
+    #[test]
+    fn an_even_split_is_a_draw() {
+        let score = FlagsScore {
+            turn: 0,
+            scores: [5, 5],
+        };
+        assert_eq!(score.winner(10), None);
+        assert!(score.is_over(10));
+    }
+}
@@ -1,19 +1,37 @@
+use std::cmp::Ordering;
 use std::fmt::Display;
+use std::future::Future;
+use std::ops::RangeInclusive;
 
 use gloo_timers::future::TimeoutFuture;
 use leptos::*;
 use leptos_router::*;
-use rand::{seq::SliceRandom, Rng};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
-    game_settings::{Difficulty, ParseDifficultyError, ParseSizeError, Size, Username},
-    pages::scores::PostScore,
-    utils::to_time,
+    board_mask::BoardMask,
+    flags_logic::FlagsScore,
+    game_settings::{
+        Difficulty, Mode, ParseDifficultyError, ParseModeError, ParseShapeError, ParseSizeError,
+        Shape, Size, Username,
+    },
+    multi_board_logic::Session,
+    pages::adaptive::{AdaptiveParams, PostAdaptiveGame},
+    pages::daily::{post_daily_move, DailyBoard},
+    pages::ghost::PostReplay,
+    pages::scores::{post_score, post_series_score},
+    pages::series::SeriesRun,
+    replay::{MoveKind, ReplayMove, ScoreReplay},
+    solver::{Layout, Puzzle, Rating},
+    utils::{now_millis, to_time, to_title},
+    validation::{error_message, validate_score, ValidationError},
+    verify_logic::{ReplayPosition, REPLAY_MODES},
 };
 
 // 定义相邻单元格的坐标偏移
-const ADJACENTS: [(isize, isize); 8] = [
+pub(crate) const ADJACENTS: [(isize, isize); 8] = [
     (-1, -1),
     (-1, 0),
     (-1, 1),
@@ -29,6 +47,8 @@ const ADJACENTS: [(isize, isize); 8] = [
 pub enum GameParamsError {
     InvalidSize(ParseSizeError),
     InvalidDifficulty(ParseDifficultyError),
+    InvalidMode(ParseModeError),
+    InvalidShape(ParseShapeError),
 }
 
 impl Display for GameParamsError {
@@ -36,41 +56,214 @@ impl Display for GameParamsError {
         match self {
             GameParamsError::InvalidSize(err) => err.fmt(f),
             GameParamsError::InvalidDifficulty(err) => err.fmt(f),
+            GameParamsError::InvalidMode(err) => err.fmt(f),
+            GameParamsError::InvalidShape(err) => err.fmt(f),
         }
     }
 }
 
-// 定义游戏参数结构体，包括难度和大小
+// 定义游戏参数结构体，包括难度、大小、模式和形状
 #[derive(Copy, Clone, PartialEq, Params)]
 pub struct GameParams {
     pub difficulty: Difficulty,
     pub size: Size,
+    pub mode: Option<Mode>,   // 缺省时为经典模式
+    pub shape: Option<Shape>, // 缺省时为矩形
+}
+
+impl GameParams {
+    // 每日挑战使用的固定参数
+    pub const DAILY: Self = Self {
+        difficulty: Difficulty::Normal,
+        size: Size::Medium,
+        mode: None,
+        shape: None,
+    };
+
+    // 获取游戏模式
+    pub fn mode(&self) -> Mode {
+        self.mode.unwrap_or_default()
+    }
+
+    // 获取棋盘形状
+    pub fn shape(&self) -> Shape {
+        self.shape.unwrap_or_default()
+    }
 }
 
 // 定义游戏状态枚举类型
-#[derive(Default, Copy, Clone)]
+#[derive(Default, Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum GameStatus {
     #[default]
     Idle, // 空闲状态
     Started,  // 游戏开始
     GameOver, // 游戏结束
     Victory,  // 胜利
+    TimedOut, // 超时 (仅限时模式)
+}
+
+impl GameStatus {
+    // 判断游戏是否已经结束
+    pub fn is_finished(self) -> bool {
+        matches!(self, Self::GameOver | Self::Victory | Self::TimedOut)
+    }
 }
 
 // 定义游戏信息结构体
 #[derive(Default)]
 pub struct GameInfo {
-    elapsed_seconds: i64, // 游戏开始后经过的秒数
-    cleared: isize,       // 已清除的单元格数量
-    clear_total: isize,   // 总共需要清除的单元格数量
-    status: GameStatus,   // 游戏状态
+    elapsed_seconds: i64,             // 游戏开始后经过的秒数
+    cleared: isize,                   // 已清除的单元格数量
+    clear_total: isize,               // 总共需要清除的单元格数量
+    status: GameStatus,               // 游戏状态
+    lives_left: Option<u32>,          // 剩余生命数量 (仅生命模式)
+    lives_used: u32,                  // 已消耗的生命数量
+    time_limit: Option<i64>,          // 时间限制 (仅限时模式)
+    bonus_seconds: i64,               // 清除空白区域获得的奖励时间
+    series_board: Option<u32>,        // 当前是第几块棋盘 (仅连续挑战模式)
+    series_seconds: i64,              // 之前各块棋盘的累计用时 (仅连续挑战模式)
+    round: u32,                       // 当前局的编号, 用于停止上一局遗留的计时器
+    puzzle_steps: Option<u32>,        // 解出谜题需要的推理轮数 (仅谜题模式)
+    errors: u32,                      // 插错旗的次数 (仅谜题模式)
+    rating: Option<Rating>,           // 布局的难度评估 (生成地雷后才有)
+    adaptive: Option<AdaptiveParams>, // 本局选择的参数 (仅自适应模式)
+    peeks: Option<u32>,               // 重新查看数字的次数 (仅记忆模式)
+    retried: bool,                    // 本局是否为重试 (不计入排行榜)
+    flags: Option<FlagsScore>,        // 双方的比分和当前轮到的玩家 (仅夺旗模式)
+    submit_error: Option<String>,     // 成绩没有被记录的原因 (验证失败或服务器拒绝)
+    race_replay: Option<ScoreReplay>, // 竞速结束后提交给服务器验证的回放
+}
+
+impl GameInfo {
+    // 限时模式下的剩余秒数
+    fn remaining_seconds(&self) -> Option<i64> {
+        self.time_limit
+            .map(|limit| (limit + self.bonus_seconds - self.elapsed_seconds).max(0))
+    }
+
+    // 记忆模式下因重新查看数字而增加的秒数
+    fn penalty_seconds(&self) -> i64 {
+        self.peeks.unwrap_or(0) as i64 * GameState::MEMORY_PEEK_PENALTY
+    }
+
+    // 计入排行榜的秒数 (限时模式为剩余时间, 连续挑战模式为总用时, 记忆模式包含查看数字的加时, 其他模式为用时)
+    fn score_seconds(&self) -> i64 {
+        self.remaining_seconds()
+            .unwrap_or(self.series_seconds + self.elapsed_seconds + self.penalty_seconds())
+    }
+
+    // 判断是否已超时
+    pub fn timed_out(&self) -> bool {
+        matches!(self.status, GameStatus::TimedOut)
+    }
+
+    // 获取游戏状态
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+
+    // 已清除的单元格数量和总共需要清除的单元格数量
+    pub fn progress(&self) -> (isize, isize) {
+        (self.cleared, self.clear_total)
+    }
+
+    // 竞速结束后的回放 (仅竞速模式)
+    pub fn race_replay(&self) -> Option<&ScoreReplay> {
+        self.race_replay.as_ref()
+    }
 }
 
 // 将游戏信息转换为视图
 impl GameInfo {
     pub fn to_view(&self) -> impl IntoView {
         let get_username = move || (expect_context::<ReadSignal<Username>>())().name; // 获取用户名
-        let time = to_time(self.elapsed_seconds); // 转换时间为字符串
+
+        // 转换时间为字符串, 限时模式下显示剩余时间, 谜题模式不计时, 显示需要的推理轮数
+        let time = match self.puzzle_steps {
+            Some(steps) => format!("{steps} deduction steps"),
+            None => to_time(self.score_seconds()),
+        };
+        let time_label = match (self.time_limit, self.puzzle_steps) {
+            (Some(_), _) => "Time left - ",
+            (_, Some(_)) => "",
+            _ => "Time - ",
+        };
+        // 连续挑战模式显示当前棋盘, 谜题模式显示错误次数, 自适应模式显示本局的地雷密度和棋盘大小,
+        // 记忆模式显示查看数字的次数
+        let progress = self
+            .series_board
+            .map(|board| format!("Board {} of {}", board, GameState::SERIES_BOARDS))
+            .or_else(|| {
+                self.puzzle_steps
+                    .map(|_| format!("Errors - {}", self.errors))
+            })
+            .or_else(|| {
+                self.adaptive.map(|adaptive| {
+                    format!(
+                        "{:.0}% mines · {}",
+                        adaptive.density * 100.0,
+                        to_title(&adaptive.size)
+                    )
+                })
+            })
+            .or_else(|| {
+                self.peeks.map(|peeks| {
+                    format!(
+                        "Peeks - {} (hold Space, +{}s each)",
+                        peeks,
+                        GameState::MEMORY_PEEK_PENALTY
+                    )
+                })
+            })
+            .or_else(|| {
+                self.flags.map(|flags| {
+                    format!(
+                        "Player 1 - {} · Player 2 - {}",
+                        flags.scores[0], flags.scores[1]
+                    )
+                })
+            })
+            .unwrap_or_default();
+        // 游戏结束后显示布局的难度评估, 重试的成绩标记为不计入排行榜
+        let rating = self
+            .rating
+            .map(|rating| format!(" · Rating {} (3BV {})", rating.score(), rating.bbbv))
+            .unwrap_or_default()
+            + if self.retried {
+                " · Retry, not ranked"
+            } else {
+                ""
+            }
+            + &self
+                .submit_error
+                .as_ref()
+                .map(|error| format!(" · Not ranked: {error}"))
+                .unwrap_or_default();
+        let lives_used = self
+            .lives_left
+            .map(|_| format!("Lives used - {}", self.lives_used))
+            .unwrap_or(progress.clone());
+
+        // 夺旗模式是双人对战, 显示轮到的玩家和比分, 结束后比分高者获胜
+        if let Some(flags) = self.flags {
+            let headline = match self.status {
+                GameStatus::Victory => match flags.scores[0].cmp(&flags.scores[1]) {
+                    Ordering::Greater => "Player 1 wins! 🥳".to_string(),
+                    Ordering::Less => "Player 2 wins! 🥳".to_string(),
+                    Ordering::Equal => "It's a draw 🤝".to_string(),
+                },
+                _ => format!("Player {}'s turn", flags.turn + 1),
+            };
+
+            return view! {
+                {headline}
+                <br />
+                {time_label} {time}
+                <br />
+                {progress}
+                <br />
+            };
+        }
 
         match self.status {
             GameStatus::Started => {
@@ -79,7 +272,7 @@ impl GameInfo {
                     <br />
                     {time}
                     <br />
-                    ""
+                    {self.lives_left.map(|lives_left| format!("{} ❤️", lives_left)).unwrap_or(progress)}
                     <br />
                 }
             }
@@ -87,9 +280,9 @@ impl GameInfo {
                 view! {
                     {move || format!("Game over, {} 😭", get_username())}
                     <br />
-                    "Time - " {time}
+                    {time_label} {time} {rating}
                     <br />
-                    ""
+                    {lives_used}
                     <br />
                 }
             }
@@ -97,9 +290,19 @@ impl GameInfo {
                 view! {
                     {move || format!("You won, {}! 🥳", get_username())}
                     <br />
-                    "Time - " {time}
+                    {time_label} {time} {rating}
                     <br />
-                    ""
+                    {lives_used}
+                    <br />
+                }
+            }
+            GameStatus::TimedOut => {
+                view! {
+                    {move || format!("Time's up, {} ⏰", get_username())}
+                    <br />
+                    {format!("{} cleared out of {}", self.cleared, self.clear_total)}
+                    <br />
+                    {rating}
                     <br />
                 }
             }
@@ -109,7 +312,7 @@ impl GameInfo {
                     <br />
                     ""
                     <br />
-                    ""
+                    {progress}
                     <br />
                 }
             }
@@ -118,18 +321,19 @@ impl GameInfo {
 }
 
 // 定义单元格交互状态
-#[derive(Copy, Clone, Default)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
 pub enum CellInteraction {
     #[default]
     Untouched, // 未触及
-    Cleared, // 已清除
-    Flagged, // 已标记
+    Cleared,      // 已清除
+    Flagged(u32), // 已标记, 记录插旗数量
+    Exploded,     // 已引爆的地雷 (仅生命模式)
 }
 
 // 定义单元格类型
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum CellKind {
-    Mine,       // 地雷
+    Mine(u32),  // 地雷, 记录该单元格中的地雷数量
     Clear(u32), // 非地雷, 显示数字
 }
 
@@ -139,6 +343,96 @@ impl Default for CellKind {
     }
 }
 
+// 随机生成地雷并计算每个单元格的数字 (按行存储, 遮罩之外的空位为空白)
+// 起点及其相邻区域不放置地雷, 多雷模式下地雷总数会分散到各个单元格中
+// 客户端开始游戏和服务器验证回放都使用这个函数, 同一个种子生成的布局相同
+pub(crate) fn generate_kinds(
+    mask: &BoardMask,
+    mines: isize,
+    max_mines_per_cell: u32,
+    (row, column): (isize, isize),
+    rng: &mut impl Rng,
+) -> Vec<CellKind> {
+    let (rows, columns) = mask.dimensions();
+    let index = |row: isize, column: isize| {
+        mask.contains(row, column)
+            .then_some((row * columns + column) as usize)
+    };
+
+    // 确保起点及其相邻区域不包含地雷
+    let exclude =
+        Vec::from_iter(std::iter::once((0, 0)).chain(ADJACENTS).filter_map(
+            |(row_offset, column_offset)| index(row + row_offset, column + column_offset),
+        ));
+
+    // 可放置地雷的位置 (跳过遮罩之外的空位, 排除起点及其相邻区域)
+    let candidates = Vec::from_iter(
+        (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (row, column)))
+            .filter_map(|(row, column)| index(row, column))
+            .filter(|index| !exclude.contains(index)),
+    );
+
+    let mut counts = vec![0; (rows * columns) as usize];
+    for _ in 0..mines {
+        loop {
+            let index = *candidates.choose(rng).expect("board has room for mines");
+            if counts[index] < max_mines_per_cell {
+                counts[index] += 1;
+                break;
+            }
+        }
+    }
+
+    // 计算每个单元格周围的地雷数量
+    (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (row, column)))
+        .map(
+            |(row, column)| match index(row, column).map(|index| counts[index]) {
+                Some(0) => CellKind::Clear(
+                    ADJACENTS
+                        .iter()
+                        .filter_map(|(row_offset, column_offset)| {
+                            index(row + row_offset, column + column_offset)
+                        })
+                        .map(|neighbor| counts[neighbor])
+                        .sum(),
+                ),
+                Some(mines) => CellKind::Mine(mines),
+                None => CellKind::Clear(0),
+            },
+        )
+        .collect()
+}
+
+// 提交成绩: 先在客户端进行与服务器相同的验证, 验证失败或服务器拒绝时通过 show_error 显示原因
+// 普通模式和无尽模式都使用这个函数
+pub(crate) fn submit_score(
+    validation: Result<(), ValidationError>,
+    submit: impl Future<Output = Result<(), ServerFnError>> + 'static,
+    show_error: impl Fn(String) + 'static,
+) {
+    if let Err(error) = validation {
+        show_error(error.to_string());
+        return;
+    }
+
+    spawn_local(async move {
+        if let Err(error) = submit.await {
+            show_error(
+                error_message(&error).unwrap_or_else(|| "Could not submit the score".into()),
+            );
+        }
+    });
+}
+
+// 每日挑战的排名尝试中交给服务器处理的操作
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum ServerMove {
+    Sent,                       // 等待服务器的回复, 期间忽略其他操作
+    Confirmed(MoveKind, usize), // 服务器已经处理, 接下来在本地进行这一操作
+}
+
 // 定义单元格状态结构体
 #[derive(Default, Clone)]
 struct CellState {
@@ -150,7 +444,24 @@ struct CellState {
 impl CellState {
     // 判断是否为地雷
     fn is_mine(&self) -> bool {
-        matches!(self.kind, CellKind::Mine)
+        matches!(self.kind, CellKind::Mine(_))
+    }
+
+    // 获取单元格中的地雷数量
+    fn mines(&self) -> u32 {
+        match self.kind {
+            CellKind::Mine(mines) => mines,
+            CellKind::Clear(_) => 0,
+        }
+    }
+
+    // 获取单元格上的旗帜数量 (已引爆的地雷视为已标记)
+    fn flags(&self) -> u32 {
+        match self.interaction {
+            CellInteraction::Flagged(flags) => flags,
+            CellInteraction::Exploded => self.mines(),
+            _ => 0,
+        }
     }
 
     // 判断是否为非地雷
@@ -162,19 +473,29 @@ impl CellState {
     fn is_untouched(&self) -> bool {
         matches!(self.interaction, CellInteraction::Untouched)
     }
-
-    // 判断是否已标记
-    fn is_flagged(&self) -> bool {
-        matches!(self.interaction, CellInteraction::Flagged)
-    }
 }
 
 // 定义游戏状态结构体
 pub struct GameState {
     params: GameParams,
-    rows: isize,    // 行数
-    columns: isize, // 列数
+    rows: isize,     // 行数
+    columns: isize,  // 列数
+    mask: BoardMask, // 棋盘形状遮罩, 遮罩之外的位置不存在单元格
     mines: isize,
+    max_mines_per_cell: u32,          // 每个单元格最多容纳的地雷数量
+    mine_cells: isize,                // 含有地雷的单元格数量
+    lives: Option<u32>,               // 生命数量 (仅生命模式)
+    lives_used: u32,                  // 已消耗的生命数量
+    opened: bool,                     // 本次挖掘是否清除了空白区域
+    seed: Option<u64>,                // 地雷布局种子, 设置后布局固定且与第一次点击的位置无关
+    daily: Option<DailyBoard>,        // 每日挑战信息 (仅每日挑战)
+    round: u32,                       // 当前局的编号
+    errors: u32,                      // 插错旗的次数 (仅谜题模式)
+    rating: Option<Rating>,           // 布局的难度评估
+    adaptive: Option<AdaptiveParams>, // 本局选择的参数 (仅自适应模式)
+    race: bool,                       // 是否为竞速房间或对战电脑中的一局 (不计入排行榜)
+    replayed: bool,                   // 是否通过链接重玩之前的布局 (不计入排行榜)
+    flags: Option<FlagsScore>,        // 双方的比分和当前轮到的玩家 (仅夺旗模式)
     cleared: isize,
     cell_states: Vec<CellState>,
     status: GameStatus,
@@ -182,7 +503,17 @@ pub struct GameState {
     set_info: WriteSignal<GameInfo>,         // 更新游戏信息信号
     new_game_enabled: ReadSignal<bool>,      // 新游戏按钮是否启用信号
     set_new_game_enabled: WriteSignal<bool>, // // 更新新游戏按钮是否启用信号
-    timer: Action<(), ()>,                   // 计时器
+    session: Option<Session>,                // 共享的计时器和生命 (仅多棋盘模式)
+    score_replay: ScoreReplay,               // 本局的布局种子和所有操作, 提交成绩时由服务器重新验证
+    series_replays: Vec<ScoreReplay>,        // 之前各块棋盘的回放 (仅连续挑战模式)
+    series_run: Option<SeriesRun>,           // 服务器分配的挑战编号和种子 (仅连续挑战模式)
+    server_move: Option<ServerMove>,         // 交给服务器处理的操作 (仅每日挑战的排名尝试)
+    retried: bool,                           // 本局是否为重试 (沿用上一局的地雷布局, 不计入排行榜)
+    started_at: i64,                         // 第一次点击的时间(毫秒)
+    revealed: Vec<usize>,                    // 本次挖掘翻开的单元格
+    peeking: ReadSignal<bool>,               // 是否显示已隐藏的数字信号 (仅记忆模式)
+    set_peeking: WriteSignal<bool>,          // 更新是否显示已隐藏的数字信号
+    timer: Action<u32, ()>,                  // 计时器
 }
 
 impl GameState {
@@ -196,33 +527,71 @@ impl GameState {
     const MEDIUM_SIZE: (isize, isize) = (10, 15);
     const LARGE_SIZE: (isize, isize) = (12, 18);
 
-    // 初始化游戏状态
+    // 多雷模式下每个单元格最多容纳的地雷数量
+    pub(crate) const MULTI_MINE_MAX: u32 = 3;
+
+    // 生命模式下的生命数量
+    pub(crate) const LIVES: u32 = 3;
+
+    // 限时模式下各大小模式的时间限制(秒), 以及每清除一片空白区域奖励的秒数
+    const SMALL_TIME_LIMIT: i64 = 120;
+    const MEDIUM_TIME_LIMIT: i64 = 240;
+    const LARGE_TIME_LIMIT: i64 = 360;
+    pub(crate) const OPENING_BONUS: i64 = 5;
+
+    // 连续挑战模式下需要连续清除的棋盘数量
+    pub const SERIES_BOARDS: u32 = 5;
+
+    // 记忆模式下数字翻开后保持显示的毫秒数, 以及每次重新查看增加的秒数
+    pub const MEMORY_FADE_MS: u32 = 3_000;
+    pub(crate) const MEMORY_PEEK_PENALTY: i64 = 5;
+
+    // 初始化游戏状态 (使用内置形状)
     pub fn new(params: GameParams) -> Self {
-        let (rows, columns) = match params.size {
-            Size::Small => Self::SMALL_SIZE,
-            Size::Medium => Self::MEDIUM_SIZE,
-            Size::Large => Self::LARGE_SIZE,
+        let (rows, columns) = Self::size_dimensions(params.size);
+
+        Self::with_mask(params, BoardMask::from_shape(params.shape(), rows, columns))
+    }
+
+    // 使用指定的形状遮罩初始化游戏状态
+    pub fn with_mask(params: GameParams, mask: BoardMask) -> Self {
+        let (rows, columns) = mask.dimensions();
+        let total = mask.count();
+        let mines = (total as f64 * Self::mine_probability(params.difficulty)) as isize;
+        let max_mines_per_cell = match params.mode() {
+            Mode::MultiMine => Self::MULTI_MINE_MAX,
+            _ => 1,
         };
-        let total = rows * columns;
-        let mines = (total as f64
-            * match params.difficulty {
-                Difficulty::Easy => Self::EASY_PROB,
-                Difficulty::Normal => Self::NORMAL_PROB,
-                Difficulty::Hard => Self::HARD_PROB,
-            }) as isize;
-
-        let (info, set_info) = create_signal(GameInfo::default());
-        set_info.update(|info| info.clear_total = total - mines);
-
-        // 创建计时器
-        let timer = create_action(move |&()| async move {
+        let lives = matches!(params.mode(), Mode::Lives).then_some(Self::LIVES);
+        let time_limit =
+            matches!(params.mode(), Mode::TimeAttack).then_some(Self::time_limit(params.size));
+
+        let series_board = matches!(params.mode(), Mode::Series).then_some(1);
+        let peeks = matches!(params.mode(), Mode::Memory).then_some(0);
+        let flags = matches!(params.mode(), Mode::Flags).then(FlagsScore::default);
+
+        let (info, set_info) = create_signal(GameInfo {
+            time_limit,
+            series_board,
+            peeks,
+            flags,
+            ..Default::default()
+        });
+
+        // 创建计时器, 限时模式下剩余时间归零时游戏超时; 开始新的一局后上一局的计时器停止
+        let timer = create_action(move |&round: &u32| async move {
             for second in 0..i64::MAX {
                 let mut stop = false;
 
                 let disposed = set_info
                     .try_update(|info| {
-                        if matches!(info.status, GameStatus::Started) {
+                        if info.round == round && matches!(info.status, GameStatus::Started) {
                             info.elapsed_seconds = second;
+
+                            if info.remaining_seconds() == Some(0) {
+                                info.status = GameStatus::TimedOut;
+                                stop = true;
+                            }
                         } else {
                             stop = true;
                         }
@@ -238,20 +607,204 @@ impl GameState {
         });
 
         let (new_game_enabled, set_new_game_enabled) = create_signal(true);
+        let (peeking, set_peeking) = create_signal(false);
 
-        Self {
+        let mut game_state = Self {
             params,
             rows,
             columns,
-            cell_states: vec![Default::default(); total as usize],
+            cell_states: vec![Default::default(); (rows * columns) as usize],
+            mask,
             mines,
+            max_mines_per_cell,
+            mine_cells: 0,
+            lives,
+            lives_used: 0,
+            opened: false,
+            seed: None,
+            daily: None,
+            round: 0,
+            errors: 0,
+            rating: None,
+            adaptive: None,
+            race: false,
+            replayed: false,
+            flags,
+            session: None,
+            score_replay: Default::default(),
+            series_replays: Vec::new(),
+            series_run: None,
+            server_move: None,
+            retried: false,
+            started_at: 0,
+            revealed: Vec::new(),
             cleared: 0,
             status: Default::default(),
             info,
             set_info,
             new_game_enabled,
             set_new_game_enabled,
+            peeking,
+            set_peeking,
             timer,
+        };
+
+        if params.mode() == Mode::Puzzle {
+            game_state.setup_puzzle();
+        }
+
+        game_state
+    }
+
+    // 初始化每日挑战的排名尝试, 同一天所有玩家的地雷布局相同, 布局由服务器保存
+    pub fn daily(board: DailyBoard) -> Self {
+        let mut game_state = Self::new(GameParams::DAILY);
+        game_state.daily = Some(board);
+        game_state
+    }
+
+    // 初始化竞速房间或对战电脑中的一局, 所有参赛者使用同一个种子
+    pub fn race(params: GameParams, seed: u64) -> Self {
+        let mut game_state = Self::new(params);
+        game_state.seed = Some(seed);
+        game_state.race = true;
+        game_state
+    }
+
+    // 通过链接重玩之前的布局, 可以与这块棋盘上的幽灵比赛
+    pub fn replay_board(params: GameParams, seed: u64) -> Self {
+        let mut game_state = Self::new(params);
+        game_state.seed = Some(seed);
+        game_state.replayed = true;
+        game_state
+    }
+
+    // 初始化连续挑战, 每块棋盘使用由服务器分配的种子推出的布局
+    pub fn series(params: GameParams, series_run: SeriesRun) -> Self {
+        let mut game_state = Self::new(params);
+        game_state.seed = Some(Self::series_board_seed(series_run.seed, 1));
+        game_state.series_run = Some(series_run);
+        game_state
+    }
+
+    // 连续挑战中第几块棋盘的种子 (SplitMix64 混合, 不同序号的种子互不相同)
+    pub fn series_board_seed(seed: u64, board: u32) -> u64 {
+        let mut z = seed.wrapping_add((board as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // 初始化自适应模式, 使用服务器根据最近的成绩选择的地雷密度和棋盘大小
+    pub fn adaptive(adaptive: AdaptiveParams) -> Self {
+        let mut game_state = Self::new(GameParams {
+            difficulty: Default::default(),
+            size: adaptive.size,
+            mode: Some(Mode::Adaptive),
+            shape: None,
+        });
+        game_state.mines = (game_state.mask.count() as f64 * adaptive.density) as isize;
+        game_state.adaptive = Some(adaptive);
+        game_state
+            .set_info
+            .update(|info| info.adaptive = Some(adaptive));
+        game_state
+    }
+
+    // 初始化多棋盘模式中的一局游戏, 计时器和生命由会话共享
+    pub(crate) fn in_session(params: GameParams, session: Session) -> Self {
+        let mut game_state = Self::new(params);
+        game_state.lives = Some(session.lives());
+        game_state.session = Some(session);
+        game_state
+    }
+
+    // 生成谜题: 使用能完全靠推理解出, 推理轮数符合难度的布局, 并翻开初始提示 (谜题模式不计时)
+    fn setup_puzzle(&mut self) {
+        let (rows, columns) = self.mask.dimensions();
+        let puzzle = Puzzle::generate(
+            &self.mask,
+            self.mines as usize,
+            Self::puzzle_steps(self.params.difficulty, rows, columns),
+            &mut StdRng::from_entropy(),
+        );
+
+        for index in puzzle.layout.indices() {
+            self.cell_states[index].kind = puzzle.layout.kind(index);
+        }
+        for &index in &puzzle.revealed {
+            self.cell_states[index].interaction = CellInteraction::Cleared;
+        }
+        for cell_state in &self.cell_states {
+            if let Some(set_cell_state) = cell_state.signal {
+                set_cell_state((cell_state.interaction, cell_state.kind));
+            }
+        }
+
+        self.mine_cells = self.mines;
+        self.cleared = puzzle.revealed.len() as isize;
+        self.status = GameStatus::Started;
+        self.set_info
+            .update(|info| info.puzzle_steps = Some(puzzle.steps));
+        self.update_score();
+    }
+
+    // 判断是否获胜 (夺旗模式下有玩家找到过半地雷或所有地雷都已找到时结束)
+    fn is_won(&self) -> bool {
+        match self.flags {
+            Some(flags) => flags.is_over(self.mine_cells as u32),
+            None => self.cleared == self.clear_total() || self.all_mines_flagged(),
+        }
+    }
+
+    // 判断是否所有地雷都已标记 (谜题模式下标记全部地雷也算胜利)
+    fn all_mines_flagged(&self) -> bool {
+        self.params.mode() == Mode::Puzzle
+            && self
+                .cell_states
+                .iter()
+                .filter(|cell_state| cell_state.is_mine())
+                .all(|cell_state| cell_state.flags() == cell_state.mines())
+    }
+
+    // 获取各大小模式下的行列数
+    pub fn size_dimensions(size: Size) -> (isize, isize) {
+        match size {
+            Size::Small => Self::SMALL_SIZE,
+            Size::Medium => Self::MEDIUM_SIZE,
+            Size::Large => Self::LARGE_SIZE,
+        }
+    }
+
+    // 获取限时模式下各大小模式的时间限制(秒)
+    pub(crate) fn time_limit(size: Size) -> i64 {
+        match size {
+            Size::Small => Self::SMALL_TIME_LIMIT,
+            Size::Medium => Self::MEDIUM_TIME_LIMIT,
+            Size::Large => Self::LARGE_TIME_LIMIT,
+        }
+    }
+
+    // 获取各难度模式下的地雷概率
+    pub fn mine_probability(difficulty: Difficulty) -> f64 {
+        match difficulty {
+            Difficulty::Easy => Self::EASY_PROB,
+            Difficulty::Normal => Self::NORMAL_PROB,
+            Difficulty::Hard => Self::HARD_PROB,
+        }
+    }
+
+    // 获取各难度下谜题需要的推理轮数范围, 按棋盘的行数与列数之和缩放
+    pub(crate) fn puzzle_steps(
+        difficulty: Difficulty,
+        rows: isize,
+        columns: isize,
+    ) -> RangeInclusive<u32> {
+        let span = (rows + columns) as u32;
+        match difficulty {
+            Difficulty::Easy => 0..=span * 7 / 10,
+            Difficulty::Normal => span * 7 / 10 + 1..=span * 14 / 10,
+            Difficulty::Hard => span * 14 / 10 + 1..=u32::MAX,
         }
     }
 
@@ -260,6 +813,54 @@ impl GameState {
         (self.rows, self.columns)
     }
 
+    // 获取棋盘形状遮罩
+    pub fn mask(&self) -> &BoardMask {
+        &self.mask
+    }
+
+    // 获取游戏参数
+    pub fn params(&self) -> GameParams {
+        self.params
+    }
+
+    // 地雷布局种子 (仅固定布局)
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    // 是否可以与幽灵比赛 (每日挑战的种子不发送给客户端, 连续挑战的布局不会重复)
+    pub fn has_ghosts(&self) -> bool {
+        self.seed.is_some() && self.series_run.is_none()
+    }
+
+    // 结束后重玩当前布局的链接, 每日挑战, 竞速和服务器无法重新验证的对局不公开布局
+    pub fn replay_link(&self) -> Option<String> {
+        let GameParams {
+            difficulty, size, ..
+        } = self.params;
+        let mode = self.params.mode();
+        let shareable = self.status.is_finished()
+            && self.daily.is_none()
+            && !self.race
+            && self.session.is_none()
+            && self.flags.is_none()
+            && self.adaptive.is_none()
+            && self.params.shape() == Shape::Rectangle
+            && REPLAY_MODES.contains(&mode);
+
+        shareable.then(|| {
+            format!(
+                "/game?difficulty={difficulty}&size={size}&mode={mode}&seed={}",
+                self.score_replay.seed
+            )
+        })
+    }
+
+    // 固定布局的安全起点 (棋盘中央), 其周围区域保证没有地雷
+    pub fn start_cell(&self) -> Option<(isize, isize)> {
+        (self.seed.is_some() || self.daily.is_some()).then_some((self.rows / 2, self.columns / 2))
+    }
+
     // 获取游戏信息信号
     pub fn info_signal(&self) -> ReadSignal<GameInfo> {
         self.info
@@ -270,65 +871,108 @@ impl GameState {
         self.new_game_enabled
     }
 
-    // 开始游戏 (确保第一次点击的单元格及其周围区域不会包含地雷)
-    fn start(&mut self, row: isize, column: isize) {
-        self.timer.dispatch(());
+    // 获取共享会话 (仅多棋盘模式)
+    pub fn session(&self) -> Option<Session> {
+        self.session
+    }
 
-        let mut rng = rand::thread_rng();
+    // 获取是否显示已隐藏的数字信号
+    pub fn peeking_signal(&self) -> ReadSignal<bool> {
+        self.peeking
+    }
 
-        // 确保第一次点击及其相邻区域不包含地雷
-        let exclude = Vec::from_iter(std::iter::once((0, 0)).chain(ADJACENTS).filter_map(
-            |(row_offset, column_offset)| self.index(row + row_offset, column + column_offset),
-        ));
+    // 记忆模式下按下或松开查看键, 游戏进行中每次按下都会增加用时
+    pub fn peek(&mut self, peeking: bool) {
+        if self.params.mode() != Mode::Memory || !matches!(self.status, GameStatus::Started) {
+            return;
+        }
 
-        // 随机生成地雷
-        for _ in 0..self.mines {
-            let cell_state = loop {
-                let index = rng.gen_range(0..self.rows * self.columns) as usize;
+        if peeking && !self.peeking.get_untracked() {
+            self.record_move(MoveKind::Peek, 0);
+            self.set_info.update(|info| {
+                info.peeks = info.peeks.map(|peeks| peeks + 1);
+            });
+        }
+        (self.set_peeking)(peeking);
+    }
 
-                if exclude.contains(&index) {
-                    continue; // 排除第一次点击及其相邻区域
-                }
+    // 开始游戏 (确保第一次点击的单元格及其周围区域不会包含地雷, 固定布局时改为确保安全起点周围没有地雷)
+    fn start(&mut self, row: isize, column: isize) {
+        self.started_at = now_millis();
 
-                let cell_state = self.cell_states.get_mut(index).expect("within bounds");
+        // 多棋盘模式使用会话共享的计时器
+        match self.session {
+            Some(session) => session.start(),
+            None => self.timer.dispatch(self.round),
+        }
 
-                if !cell_state.is_mine() {
-                    break cell_state;
-                }
-            };
+        // 重试时沿用上一局的地雷布局和种子, 每日挑战的布局由服务器逐步发送
+        if !self.retried && self.daily.is_none() {
+            self.generate_layout(row, column);
+        }
+
+        self.status = GameStatus::Started;
+    }
 
-            cell_state.kind = CellKind::Mine;
+    // 生成地雷布局 (没有固定布局时随机选择种子, 提交成绩时服务器用同一个种子重新生成布局)
+    fn generate_layout(&mut self, row: isize, column: isize) {
+        let (row, column) = self.start_cell().unwrap_or((row, column));
+        let seed = self.seed.unwrap_or_else(rand::random);
+        self.score_replay.seed = seed;
+
+        let kinds = generate_kinds(
+            &self.mask,
+            self.mines,
+            self.max_mines_per_cell,
+            (row, column),
+            &mut StdRng::seed_from_u64(seed),
+        );
+        for (cell_state, kind) in self.cell_states.iter_mut().zip(kinds) {
+            cell_state.kind = kind;
         }
 
-        // 计算每个单元格周围的地雷数量
-        for row in 0..self.rows {
-            for column in 0..self.columns {
-                if self
-                    .get_cell_state(row, column)
-                    .expect("within bounds")
-                    .is_clear()
-                {
-                    let mines = ADJACENTS
-                        .iter()
-                        .filter(|(row_offset, column_offset)| {
-                            self.get_cell_state(row + row_offset, column + column_offset)
-                                .map_or(false, |cell_state| cell_state.is_mine())
-                        })
-                        .count();
+        self.mine_cells = self
+            .cell_states
+            .iter()
+            .filter(|cell_state| cell_state.is_mine())
+            .count() as isize;
 
-                    self.get_cell_state_mut(row, column)
-                        .expect("within bounds")
-                        .kind = CellKind::Clear(mines as u32);
-                }
-            }
+        // 评估布局难度 (推理规则只适用于每个单元格最多一个地雷的情况)
+        if let Some(layout) = self.mine_layout() {
+            let rating = self
+                .index(row, column)
+                .map(|start| Rating::of(&layout, start));
+
+            self.rating = rating;
+            self.set_info.update(|info| info.rating = rating);
         }
+    }
 
-        self.status = GameStatus::Started;
+    // 当前的地雷布局 (仅每个单元格最多一个地雷的模式), 不检查地雷是否已生成
+    fn mine_layout(&self) -> Option<Layout> {
+        (self.max_mines_per_cell == 1).then(|| {
+            Layout::new(
+                self.mask.clone(),
+                self.cell_states
+                    .iter()
+                    .map(|cell_state| cell_state.is_mine())
+                    .collect(),
+            )
+        })
+    }
+
+    // 已生成的地雷布局 (第一次点击之后才有), 供电脑对手在同一棋盘上比赛
+    pub fn layout(&self) -> Option<Layout> {
+        if matches!(self.status, GameStatus::Idle) {
+            return None;
+        }
+        self.mine_layout()
     }
 
-    // 获取指定位置的索引
+    // 获取指定位置的索引 (遮罩之外的空位没有索引)
     fn index(&self, row: isize, column: isize) -> Option<usize> {
-        (row >= 0 && column >= 0 && row < self.rows && column < self.columns)
+        self.mask
+            .contains(row, column)
             .then_some((row * self.columns + column) as usize)
     }
 
@@ -351,44 +995,124 @@ impl GameState {
         column: isize,
         set_cell_state: WriteSignal<(CellInteraction, CellKind)>,
     ) {
-        self.get_cell_state_mut(row, column)
-            .expect("row and column within bounds")
-            .signal = Some(set_cell_state);
+        let cell_state = self
+            .get_cell_state_mut(row, column)
+            .expect("row and column within bounds");
+        cell_state.signal = Some(set_cell_state);
+
+        // 谜题模式下注册时棋盘已经生成, 需要同步当前状态
+        set_cell_state((cell_state.interaction, cell_state.kind));
     }
 
     // 更新得分
     fn update_score(&mut self) {
         match self.status {
-            GameStatus::Started if self.cleared == self.rows * self.columns - self.mines => {
+            GameStatus::Started if self.is_won() => {
                 self.status = GameStatus::Victory;
-
-                for cell_state in &mut self.cell_states {
-                    if cell_state.is_untouched() {
-                        cell_state.signal.expect("signal registered")((
-                            CellInteraction::Flagged,
-                            CellKind::Mine,
-                        ));
+                (self.set_peeking)(true); // 游戏结束后重新显示所有数字
+
+                for cell_state in &self.cell_states {
+                    // 遮罩之外的空位没有注册信号, 会被跳过
+                    if let Some(set_cell_state) =
+                        cell_state.signal.filter(|_| cell_state.is_untouched())
+                    {
+                        // 标记剩余的地雷, 谜题模式下标记全部地雷获胜时翻开剩余的安全单元格
+                        let interaction = if cell_state.is_mine() {
+                            CellInteraction::Flagged(cell_state.mines())
+                        } else {
+                            CellInteraction::Cleared
+                        };
+                        set_cell_state((interaction, cell_state.kind));
                     }
                 }
 
-                let post_score = create_server_action::<PostScore>();
+                // 可以重玩的布局保存获胜的回放, 供之后在同一布局上与幽灵比赛
+                if !self.retried && self.replay_link().is_some() {
+                    self.post_replay();
+                }
 
-                post_score.dispatch(PostScore {
-                    username: (expect_context::<ReadSignal<Username>>())().name,
-                    time_in_seconds: self.info.with(|info| info.elapsed_seconds),
-                    difficulty: self.params.difficulty,
-                    size: self.params.size,
-                });
+                match self.info.with(|info| info.series_board) {
+                    // 多棋盘模式由会话判断整体结果
+                    _ if self.session.is_some() => self.report_to_session(true),
+                    // 重试, 竞速和重玩的成绩不计入排行榜
+                    _ if self.retried || self.race || self.replayed => {}
+                    // 夺旗模式是双人对战, 不记录成绩
+                    _ if self.flags.is_some() => {}
+                    // 每日挑战的结果由服务器在最后一步时记录
+                    _ if self.daily.is_some() => {}
+                    // 自适应模式只记录结果, 用于选择下一局的参数
+                    _ if self.adaptive.is_some() => self.post_adaptive_game(true),
+                    // 谜题模式不计时, 不记录成绩
+                    _ if self.params.mode() == Mode::Puzzle => {}
+                    // 连续挑战模式下立即进入下一块棋盘
+                    Some(board) if board < Self::SERIES_BOARDS => self.next_series_board(),
+                    // 排行榜只记录矩形棋盘的成绩, 其他形状的单元格数量不同, 无法公平比较
+                    _ if self.params.shape() != Shape::Rectangle => {}
+                    // 连续挑战模式提交每块棋盘的回放, 由服务器逐一验证并按整个系列的总用时记录成绩
+                    Some(_) => {
+                        let Some(series_run) = self.series_run else {
+                            return;
+                        };
+                        let username = (expect_context::<ReadSignal<Username>>())().name;
+                        let time_in_seconds = self.info.with(|info| info.score_seconds());
+                        let replays = self
+                            .series_replays
+                            .iter()
+                            .chain([&self.score_replay])
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>()
+                            .join(" ");
+
+                        self.submit_score(
+                            validate_score(&username, time_in_seconds),
+                            post_series_score(
+                                username,
+                                self.params.difficulty,
+                                self.params.size,
+                                series_run.id,
+                                replays,
+                            ),
+                        );
+                    }
+                    // 普通的成绩以回放提交, 由服务器重新进行一局并判定用时
+                    None => {
+                        let username = (expect_context::<ReadSignal<Username>>())().name;
+                        let time_in_seconds = self.info.with(|info| info.score_seconds());
+
+                        self.submit_score(
+                            validate_score(&username, time_in_seconds),
+                            post_score(
+                                username,
+                                self.params.difficulty,
+                                self.params.size,
+                                self.params.mode(),
+                                self.score_replay.to_string(),
+                            ),
+                        );
+                    }
+                }
             }
 
-            GameStatus::GameOver => {
+            GameStatus::GameOver | GameStatus::TimedOut => {
                 (self.set_new_game_enabled)(false);
+                (self.set_peeking)(true); // 游戏结束后重新显示所有数字
+
+                if self.adaptive.is_some() && !self.retried {
+                    self.post_adaptive_game(false);
+                }
+                // 多棋盘模式下任意一块棋盘失败都会结束整个会话
+                self.report_to_session(false);
 
                 let mut mine_signals = self
                     .cell_states
                     .iter()
                     .filter(|cell_state| cell_state.is_untouched() && cell_state.is_mine())
-                    .map(|cell_state| cell_state.signal.expect("signal registered"))
+                    .map(|cell_state| {
+                        (
+                            cell_state.signal.expect("signal registered"),
+                            cell_state.kind,
+                        )
+                    })
                     .collect::<Vec<_>>();
                 mine_signals.shuffle(&mut rand::thread_rng());
 
@@ -398,8 +1122,8 @@ impl GameState {
                     async move {
                         TimeoutFuture::new(400).await;
 
-                        for set_cell_state in mine_signals {
-                            set_cell_state((CellInteraction::Cleared, CellKind::Mine));
+                        for (set_cell_state, kind) in mine_signals {
+                            set_cell_state((CellInteraction::Cleared, kind));
                             TimeoutFuture::new(20).await;
                         }
 
@@ -411,30 +1135,256 @@ impl GameState {
             _ => {}
         }
 
+        let clear_total = self.clear_total();
         self.set_info.update(|info| {
             info.cleared = self.cleared;
+            info.clear_total = clear_total;
             info.status = self.status;
+            info.lives_left = self.lives_left();
+            info.lives_used = self.lives_used;
+            info.errors = self.errors;
+            info.flags = self.flags;
+            if self.race && self.status.is_finished() {
+                info.race_replay = Some(self.score_replay.clone());
+            }
+        });
+    }
+
+    // 每日挑战的排名尝试由服务器保存布局: 操作先发送给服务器, 收到已翻开的单元格后再在本地进行,
+    // 等待回复期间忽略其他操作. 返回 true 表示这一操作已交给服务器处理
+    fn send_to_server(&mut self, kind: MoveKind, row: isize, column: isize) -> bool {
+        let Some(daily) = self
+            .daily
+            .as_ref()
+            .filter(|_| !self.retried && !self.status.is_finished())
+        else {
+            return false;
+        };
+        let Some(index) = self.index(row, column) else {
+            return true;
+        };
+        match self.server_move {
+            Some(ServerMove::Confirmed(confirmed_kind, confirmed)) => {
+                return (confirmed_kind, confirmed) != (kind, index);
+            }
+            Some(ServerMove::Sent) => return true,
+            None => {}
+        }
+        self.server_move = Some(ServerMove::Sent);
+
+        let username = (expect_context::<ReadSignal<Username>>())().name;
+        let date = daily.date.clone();
+        let set_game_state = expect_context::<WriteSignal<GameState>>();
+        spawn_local(async move {
+            let position = post_daily_move(username, date, kind, index).await;
+            // 页面销毁后忽略
+            let _ = set_game_state
+                .try_update(|game_state| game_state.receive_position(kind, row, column, position));
+        });
+        true
+    }
+
+    // 收到服务器的回复: 记下已翻开的单元格的类型, 然后在本地进行这一操作
+    fn receive_position(
+        &mut self,
+        kind: MoveKind,
+        row: isize,
+        column: isize,
+        position: Result<ReplayPosition, ServerFnError>,
+    ) {
+        self.server_move = None;
+        let position = match position {
+            Ok(position) => position,
+            Err(error) => {
+                let message =
+                    error_message(&error).unwrap_or_else(|| "Could not reach the server".into());
+                self.set_info
+                    .update(|info| info.submit_error = Some(message));
+                return;
+            }
+        };
+
+        self.mine_cells = position.mine_cells;
+        for (index, kind) in position.cells {
+            if let Some(cell_state) = self.cell_states.get_mut(index) {
+                cell_state.kind = kind;
+            }
+        }
+
+        self.server_move = self
+            .index(row, column)
+            .map(|index| ServerMove::Confirmed(kind, index));
+        match kind {
+            MoveKind::Dig => self.dig(row, column),
+            MoveKind::Flag => self.flag(row, column),
+            MoveKind::Peek => {}
+        }
+        self.server_move = None;
+    }
+
+    // 提交成绩, 验证失败或服务器拒绝时在游戏信息中显示原因
+    fn submit_score(
+        &self,
+        validation: Result<(), ValidationError>,
+        submit: impl Future<Output = Result<(), ServerFnError>> + 'static,
+    ) {
+        let set_info = self.set_info;
+        let round = self.round;
+        // 只显示在提交成绩的这一局上 (页面销毁后忽略)
+        submit_score(validation, submit, move |message| {
+            let _ = set_info.try_update(|info| {
+                if info.round == round {
+                    info.submit_error = Some(message);
+                }
+            });
+        });
+    }
+
+    // 提交获胜的回放, 服务器验证后在同一布局上可以作为幽灵比赛
+    fn post_replay(&self) {
+        let post_replay = create_server_action::<PostReplay>();
+
+        post_replay.dispatch(PostReplay {
+            username: (expect_context::<ReadSignal<Username>>())().name,
+            difficulty: self.params.difficulty,
+            size: self.params.size,
+            mode: self.params.mode(),
+            replay: self.score_replay.to_string(),
         });
     }
 
+    // 记录自适应模式的结果, 服务器据此选择下一局的参数
+    fn post_adaptive_game(&self, won: bool) {
+        let Some(adaptive) = self.adaptive else {
+            return;
+        };
+        let post_adaptive_game = create_server_action::<PostAdaptiveGame>();
+
+        post_adaptive_game.dispatch(PostAdaptiveGame {
+            username: (expect_context::<ReadSignal<Username>>())().name,
+            density: adaptive.density,
+            size: adaptive.size,
+            won,
+            bbbv: self.rating.map_or(0, |rating| rating.bbbv),
+            time_in_seconds: self.info.with(|info| info.elapsed_seconds),
+        });
+    }
+
+    // 多棋盘模式下向会话报告本块棋盘的结果
+    fn report_to_session(&self, won: bool) {
+        if let Some(session) = self.session {
+            session.board_finished(won);
+        }
+    }
+
+    // 剩余生命数量 (仅生命模式和多棋盘模式, 多棋盘模式下所有棋盘共享生命)
+    fn lives_left(&self) -> Option<u32> {
+        let lives_used = self
+            .session
+            .map_or(self.lives_used, |session| session.lives_used());
+        self.lives.map(|lives| lives.saturating_sub(lives_used))
+    }
+
+    // 需要清除的单元格总数 (生成地雷前按地雷数量估算, 重试时布局已经确定)
+    fn clear_total(&self) -> isize {
+        let mine_cells = match self.status {
+            GameStatus::Idle if !self.retried => self.mines,
+            _ => self.mine_cells,
+        };
+
+        self.mask.count() as isize - mine_cells
+    }
+
     // 挖地雷(挖掘指定位置的单元格)
     pub fn dig(&mut self, row: isize, column: isize) {
-        match self.status {
-            GameStatus::GameOver | GameStatus::Victory => {
+        // 固定布局只保证安全起点周围没有地雷, 因此第一次挖掘必须在安全起点上, 挖其他位置不会开始游戏
+        if self.status == GameStatus::Idle
+            && self
+                .start_cell()
+                .is_some_and(|start_cell| start_cell != (row, column))
+        {
+            return;
+        }
+
+        if self.send_to_server(MoveKind::Dig, row, column) {
+            return;
+        }
+
+        let started = match self.status {
+            status if status.is_finished() => {
                 return;
             }
             GameStatus::Idle => {
                 self.start(row, column);
+                false
             }
-            _ => {}
+            _ => true,
+        };
+        if let Some(index) = self.index(row, column) {
+            self.record_move(MoveKind::Dig, index);
         }
 
+        self.opened = false;
+        self.revealed.clear();
+        let flags_before = self.flags;
         self.dig_inner(row, column);
+
+        // 夺旗模式下挖开安全的单元格后轮到对方, 找到地雷则继续挖掘
+        if let (Some(before), Some(flags)) = (flags_before, &mut self.flags) {
+            if flags.scores == before.scores && !self.revealed.is_empty() {
+                flags.pass_turn();
+            }
+        }
+
+        // 限时模式下, 游戏开始后每清除一片空白区域奖励额外的时间
+        if started && self.opened {
+            self.set_info.update(|info| {
+                if info.time_limit.is_some() {
+                    info.bonus_seconds += Self::OPENING_BONUS;
+                }
+            });
+        }
+
+        self.update_score();
+    }
+
+    // 记录一次操作, 游戏开始前的操作 (插旗) 记为第0毫秒
+    fn record_move(&mut self, kind: MoveKind, index: usize) {
+        let millis = match self.status {
+            GameStatus::Idle => 0,
+            _ => now_millis() - self.started_at,
+        };
+        self.score_replay.moves.push(ReplayMove {
+            millis,
+            kind,
+            index,
+        });
+    }
+
+    // 超时后结束游戏 (由计时器更新游戏信息后触发)
+    pub fn time_out(&mut self) {
+        if self.status.is_finished() {
+            return;
+        }
+
+        self.status = GameStatus::TimedOut;
+        self.update_score();
+    }
+
+    // 会话中的其他棋盘失败后结束本局 (仅多棋盘模式)
+    pub fn end_session(&mut self) {
+        if self.status.is_finished() {
+            return;
+        }
+
+        self.status = GameStatus::GameOver;
         self.update_score();
     }
 
     // 挖地雷内部逻辑(扫雷算法的核心)
     fn dig_inner(&mut self, row: isize, column: isize) {
+        let lives_mode = self.lives.is_some();
+        let flags_mode = self.flags.is_some();
         let Some(cell_state) = self.get_cell_state_mut(row, column) else {
             return;
         };
@@ -442,7 +1392,12 @@ impl GameState {
         // 根据单元格的交互状态进行不同的处理
         match cell_state.interaction {
             CellInteraction::Untouched => {
-                cell_state.interaction = CellInteraction::Cleared;
+                // 生命模式下踩到的地雷标记为已引爆, 夺旗模式下找到的地雷插上旗
+                cell_state.interaction = match cell_state.kind {
+                    CellKind::Mine(_) if lives_mode => CellInteraction::Exploded,
+                    CellKind::Mine(mines) if flags_mode => CellInteraction::Flagged(mines),
+                    _ => CellInteraction::Cleared,
+                };
 
                 cell_state.signal.expect("signal registered")((
                     cell_state.interaction,
@@ -450,13 +1405,33 @@ impl GameState {
                 ));
 
                 match cell_state.kind {
+                    // 生命模式下踩到地雷会消耗一条生命, 生命耗尽时游戏结束
+                    CellKind::Mine(_) if lives_mode => {
+                        self.lives_used += 1;
+                        if let Some(session) = self.session {
+                            session.lose_life();
+                        }
+                        if self.lives_left() == Some(0) {
+                            self.status = GameStatus::GameOver;
+                        }
+                        return;
+                    }
+                    // 夺旗模式下找到地雷为当前玩家得分
+                    CellKind::Mine(mines) if flags_mode => {
+                        if let Some(flags) = &mut self.flags {
+                            flags.found_mine(mines);
+                        }
+                        return;
+                    }
                     // 如果是地雷，游戏结束
-                    CellKind::Mine => {
+                    CellKind::Mine(_) => {
                         self.status = GameStatus::GameOver;
                         return;
                     }
                     // 如果是非地雷, 递归挖开相邻单元格
                     CellKind::Clear(0) => {
+                        self.opened = true;
+
                         // 清除0的单元格时(当前单元格周围没有雷且被挖到)，递归清除相邻单元格
                         for (row_offset, column_offset) in ADJACENTS {
                             self.dig_inner(row + row_offset, column + column_offset);
@@ -475,16 +1450,16 @@ impl GameState {
                     .expect("within bounds")
                     .kind
                 {
-                    let flags = ADJACENTS
+                    let flags: u32 = ADJACENTS
                         .iter()
-                        .filter(|(row_offset, column_offset)| {
+                        .filter_map(|(row_offset, column_offset)| {
                             self.get_cell_state(row + row_offset, column + column_offset)
-                                .map_or(false, |cell_state| cell_state.is_flagged())
                         })
-                        .count();
+                        .map(|cell_state| cell_state.flags())
+                        .sum();
 
                     // 比较地雷数量和旗子数量
-                    if mines == flags as u32 {
+                    if mines == flags {
                         for (row_offset, column_offset) in ADJACENTS {
                             if let Some(cell_state) =
                                 self.get_cell_state(row + row_offset, column + column_offset)
@@ -500,44 +1475,141 @@ impl GameState {
                 return;
             }
 
-            // 已标记状态下不允许挖开单元格
-            CellInteraction::Flagged => {
+            // 已标记或已引爆状态下不允许挖开单元格
+            CellInteraction::Flagged(_) | CellInteraction::Exploded => {
                 return;
             }
         }
 
+        self.revealed.extend(self.index(row, column));
         self.cleared += 1;
     }
 
-    // 标记或取消标记指定位置的单元格(插旗或拔旗)
+    // 标记或取消标记指定位置的单元格(插旗或拔旗), 多雷模式下循环切换旗帜数量
     pub fn flag(&mut self, row: isize, column: isize) {
-        if matches!(self.status, GameStatus::GameOver | GameStatus::Victory) {
+        // 夺旗模式下旗子只能通过挖到地雷获得
+        if self.status.is_finished() || self.flags.is_some() {
+            return;
+        }
+        if self.send_to_server(MoveKind::Flag, row, column) {
             return;
         }
+        if let Some(index) = self.index(row, column) {
+            self.record_move(MoveKind::Flag, index);
+        }
 
+        let max_flags = self.max_mines_per_cell;
+        let puzzle_mode = self.params.mode() == Mode::Puzzle;
         let Some(cell_state) = self.get_cell_state_mut(row, column) else {
             return;
         };
 
         match cell_state.interaction {
+            // 谜题模式下不允许在安全的单元格上插旗, 插错旗计为一次错误
+            CellInteraction::Untouched if puzzle_mode && cell_state.is_clear() => {
+                self.errors += 1;
+                self.update_score();
+                return;
+            }
             CellInteraction::Untouched => {
-                cell_state.interaction = CellInteraction::Flagged;
+                cell_state.interaction = CellInteraction::Flagged(1);
             }
-            CellInteraction::Cleared => {
+            CellInteraction::Cleared | CellInteraction::Exploded => {
                 return;
             }
-            CellInteraction::Flagged => {
+            CellInteraction::Flagged(flags) if flags < max_flags => {
+                cell_state.interaction = CellInteraction::Flagged(flags + 1);
+            }
+            CellInteraction::Flagged(_) => {
                 cell_state.interaction = CellInteraction::Untouched;
             }
         }
 
         cell_state.signal.expect("signal registered")((cell_state.interaction, cell_state.kind));
+
+        if puzzle_mode {
+            self.update_score();
+        }
     }
 
-    // 重置游戏状态
+    // 重置游戏状态 (连续挑战模式下从第一块棋盘重新开始)
     pub fn reset(&mut self) {
+        let series_board = self
+            .info
+            .with_untracked(|info| info.series_board.map(|_| 1));
+        self.series_replays.clear();
+        self.reset_board(series_board, 0);
+
+        if let Some(series_run) = self.series_run {
+            self.seed = Some(Self::series_board_seed(series_run.seed, 1));
+        }
+    }
+
+    // 判断是否可以在同一布局上重试 (失败之后, 谜题模式和多棋盘模式除外)
+    fn can_retry(&self) -> bool {
+        matches!(self.status, GameStatus::GameOver)
+            && self.params.mode() != Mode::Puzzle
+            && self.session.is_none()
+    }
+
+    // 在同一布局上重试: 保留地雷和数字, 只清空交互和本块棋盘的用时, 重试的成绩不计入排行榜
+    pub fn retry(&mut self) {
+        if !self.can_retry() {
+            return;
+        }
+
+        let kinds = Vec::from_iter(self.cell_states.iter().map(|cell_state| cell_state.kind));
+        let (mine_cells, rating, seed) = (self.mine_cells, self.rating, self.score_replay.seed);
+        let (series_board, series_seconds) = self
+            .info
+            .with_untracked(|info| (info.series_board, info.series_seconds));
+        self.reset_board(series_board, series_seconds);
+
+        for (cell_state, kind) in self.cell_states.iter_mut().zip(kinds) {
+            cell_state.kind = kind;
+        }
+        self.mine_cells = mine_cells;
+        self.rating = rating;
+        self.score_replay.seed = seed;
+        self.retried = true;
+
+        let clear_total = self.clear_total();
+        self.set_info.update(|info| {
+            info.retried = true;
+            info.clear_total = clear_total;
+            info.rating = rating;
+        });
+    }
+
+    // 连续挑战模式下进入下一块棋盘, 累计之前各块棋盘的用时
+    fn next_series_board(&mut self) {
+        let (series_board, series_seconds) = self.info.with_untracked(|info| {
+            (
+                info.series_board.map(|board| board + 1),
+                info.series_seconds + info.elapsed_seconds,
+            )
+        });
+        self.series_replays
+            .push(std::mem::take(&mut self.score_replay));
+        self.reset_board(series_board, series_seconds);
+
+        if let (Some(series_run), Some(board)) = (self.series_run, series_board) {
+            self.seed = Some(Self::series_board_seed(series_run.seed, board));
+        }
+    }
+
+    // 清空棋盘, 开始新的一局
+    fn reset_board(&mut self, series_board: Option<u32>, series_seconds: i64) {
+        self.round += 1;
+        self.errors = Default::default();
+        self.rating = Default::default();
+        self.score_replay = Default::default();
+        self.retried = Default::default();
         self.status = Default::default();
         self.cleared = Default::default();
+        self.mine_cells = Default::default();
+        self.lives_used = Default::default();
+        self.flags = self.flags.map(|_| Default::default());
 
         for cell_state in &mut self.cell_states {
             cell_state.interaction = Default::default();
@@ -548,9 +1620,114 @@ impl GameState {
             }
         }
 
+        (self.set_peeking)(false);
+
+        let (time_limit, peeks) = self
+            .info
+            .with_untracked(|info| (info.time_limit, info.peeks.map(|_| 0)));
         (self.set_info)(GameInfo {
-            clear_total: self.rows * self.columns - self.mines,
+            clear_total: self.clear_total(),
+            lives_left: self.lives_left(),
+            time_limit,
+            series_board,
+            series_seconds,
+            round: self.round,
+            adaptive: self.adaptive,
+            peeks,
+            flags: self.flags,
             ..Default::default()
         });
+
+        if self.params.mode() == Mode::Puzzle {
+            self.setup_puzzle();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARAMS: GameParams = GameParams {
+        difficulty: Difficulty::Easy,
+        size: Size::Small,
+        mode: None,
+        shape: None,
+    };
+
+    // 按行列顺序找出所有地雷或安全单元格的位置
+    fn cells(game_state: &GameState, mines: bool) -> Vec<(isize, isize)> {
+        (0..game_state.rows)
+            .flat_map(|row| (0..game_state.columns).map(move |column| (row, column)))
+            .filter(|&(row, column)| {
+                game_state
+                    .get_cell_state(row, column)
+                    .is_some_and(|cell_state| cell_state.is_mine() == mines)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn series_boards_have_distinct_seeds() {
+        let seeds = Vec::from_iter(
+            (1..=GameState::SERIES_BOARDS).map(|board| GameState::series_board_seed(7, board)),
+        );
+
+        for (i, seed) in seeds.iter().enumerate() {
+            assert!(!seeds[i + 1..].contains(seed));
+        }
+        assert_ne!(
+            seeds,
+            Vec::from_iter(
+                (1..=GameState::SERIES_BOARDS).map(|board| GameState::series_board_seed(8, board)),
+            )
+        );
+    }
+
+    #[test]
+    fn retried_board_can_be_won() {
+        let runtime = create_runtime();
+
+        // 竞速对局使用固定种子, 并且不会提交成绩
+        let mut game_state = GameState::race(PARAMS, 7);
+        let start = game_state
+            .start_cell()
+            .expect("seeded boards have a start cell");
+        game_state.dig(start.0, start.1);
+        let seed = game_state.score_replay.seed;
+        let kinds = Vec::from_iter(
+            game_state
+                .cell_states
+                .iter()
+                .map(|cell_state| cell_state.kind),
+        );
+
+        let (row, column) = cells(&game_state, true)[0];
+        game_state.dig(row, column);
+        assert_eq!(game_state.status, GameStatus::GameOver);
+
+        game_state.retry();
+        assert!(game_state.retried);
+        assert_eq!(game_state.status, GameStatus::Idle);
+        assert_eq!(
+            game_state.info.with_untracked(|info| info.clear_total),
+            game_state.mask.count() as isize - game_state.mine_cells
+        );
+
+        game_state.dig(start.0, start.1);
+        assert_eq!(game_state.status, GameStatus::Started);
+        for (row, column) in cells(&game_state, false) {
+            game_state.dig(row, column);
+        }
+
+        assert_eq!(game_state.status, GameStatus::Victory);
+        assert_eq!(game_state.score_replay.seed, seed);
+        assert!(game_state
+            .cell_states
+            .iter()
+            .map(|cell_state| cell_state.kind)
+            .eq(kinds));
+
+        runtime.dispose();
     }
 }
@@ -168,6 +168,88 @@ impl Display for Size {
     }
 }
 
+// 解析游戏模式错误类型
+#[derive(Error, Debug)]
+pub struct ParseModeError;
+
+impl Display for ParseModeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Error parsing game mode")
+    }
+}
+
+// 游戏模式枚举
+#[derive(PartialEq, Copy, Clone, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    #[default]
+    Classic, // 经典模式, 每个单元格最多一个地雷
+    MultiMine,  // 多雷模式, 每个单元格可以容纳多个地雷
+    Lives,      // 生命模式, 踩到地雷只消耗一条生命
+    TimeAttack, // 限时模式, 倒计时结束即失败
+    Endless,    // 无尽模式, 棋盘按区块无限延伸
+    Series,     // 连续挑战模式, 连续清除多块棋盘, 按总用时排名
+    Puzzle,     // 谜题模式, 部分单元格已翻开, 无需猜测即可解出
+    Adaptive,   // 自适应模式, 根据最近的成绩选择地雷密度和棋盘大小
+    Memory,     // 记忆模式, 数字翻开几秒后隐藏, 按住空格键可重新查看, 每次查看加时
+    MultiBoard, // 多棋盘模式, 同时进行多块小棋盘, 共享计时器和生命
+    Flags,      // 夺旗模式, 两名玩家轮流挖掘, 找到地雷得分, 先找到过半地雷者获胜
+}
+
+// 实现从字符串解析Mode
+impl FromStr for Mode {
+    type Err = serde::de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(s.into_deserializer())
+    }
+}
+
+// 实现Display trait用于格式化输出模式Mode
+impl Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.serialize(f)
+    }
+}
+
+// 解析棋盘形状错误类型
+#[derive(Error, Debug)]
+pub struct ParseShapeError;
+
+impl Display for ParseShapeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Error parsing gameboard shape")
+    }
+}
+
+// 棋盘形状枚举
+#[derive(PartialEq, Copy, Clone, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Shape {
+    #[default]
+    Rectangle,
+    Circle,
+    Heart,
+    Donut,
+    Custom, // 自定义形状, 遮罩文本保存在设置中
+}
+
+// 实现从字符串解析Shape
+impl FromStr for Shape {
+    type Err = serde::de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(s.into_deserializer())
+    }
+}
+
+// 实现Display trait用于格式化输出形状Shape
+impl Display for Shape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.serialize(f)
+    }
+}
+
 // 使用cfg_if宏，根据不同的编译环境选择不同的代码路径
 cfg_if! {
     if #[cfg(feature = "ssr")] {
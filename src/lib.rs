@@ -1,10 +1,33 @@
 use cfg_if::cfg_if;
 pub mod app;
 pub mod app_error;
+pub mod board_mask;
+pub mod bot_logic;
+pub mod endless_logic;
+pub mod flags_logic;
 pub mod game_logic;
 pub mod game_settings;
+pub mod multi_board_logic;
 pub mod pages;
+pub mod race;
+#[cfg(feature = "ssr")]
+pub mod race_server;
+pub mod replay;
+pub mod review_logic;
+pub mod room;
+#[cfg(feature = "ssr")]
+pub mod room_server;
+pub mod solver;
+pub mod trainer_logic;
 pub mod utils;
+pub mod validation;
+pub mod verified;
+#[cfg(feature = "ssr")]
+pub mod verified_server;
+pub mod verify_logic;
+pub mod watch;
+#[cfg(feature = "ssr")]
+pub mod watch_server;
 
 cfg_if! {
     if #[cfg(feature = "hydrate")] {
@@ -18,12 +18,20 @@ cfg_if::cfg_if! {
         use tower_http::services::ServeDir;
 
         use quan_minesweeper::app::App;
+        use quan_minesweeper::race_server::{race_handler, RaceRoom};
+        use quan_minesweeper::room_server::{room_handler, spawn_cleanup, Rooms};
+        use quan_minesweeper::verified_server::VerifiedGames;
+        use quan_minesweeper::watch_server::{watch_handler, Broadcasts};
 
-        // 定义应用状态结构体，包含Leptos选项和数据库连接池
-        #[derive(FromRef, Debug, Clone)]
+        // 定义应用状态结构体，包含Leptos选项, 数据库连接池和多人游戏房间
+        #[derive(FromRef, Clone)]
         struct AppState {
             leptos_options: LeptosOptions,
             db_pool: SqlitePool,
+            rooms: Rooms,
+            races: Rooms<RaceRoom>,
+            broadcasts: Broadcasts,
+            verified_games: VerifiedGames,
         }
 
         // 主函数，启动异步执行环境
@@ -51,22 +59,41 @@ cfg_if::cfg_if! {
                 .expect("database migrated");
 
             // 创建应用状态
-            let state = {
-                let db_pool = db_pool.clone();
-                AppState {
-                    leptos_options,
-                    db_pool,
-                }
+            // 房间, 直播和验证游戏的共享状态, 同时提供给 WebSocket 入口和服务器函数
+            let rooms = Rooms::default();
+            let races = Rooms::<RaceRoom>::default();
+            let broadcasts = Broadcasts::default();
+            let verified_games = VerifiedGames::default();
+            let state = AppState {
+                leptos_options,
+                db_pool: db_pool.clone(),
+                rooms: rooms.clone(),
+                races: races.clone(),
+                broadcasts: broadcasts.clone(),
+                verified_games: verified_games.clone(),
             };
 
+            // 定期删除空闲的房间
+            spawn_cleanup(rooms.clone());
+            spawn_cleanup(races.clone());
+
             // 设置路由和处理器
             let app = Router::new()
                 .route(
                     "/api/*fn_name",
                     get(server_fn_handler).post(server_fn_handler),
                 )
+                // 多人游戏房间的 WebSocket 连接
+                .route("/ws/:room", get(room_handler))
+                .route("/ws/race/:room", get(race_handler))
+                // 直播的服务器推送事件
+                .route("/sse/watch/:id", get(watch_handler))
                 .leptos_routes_with_context(&state, routes, move || {
                     provide_context(db_pool.clone());
+                    provide_context(rooms.clone());
+                    provide_context(races.clone());
+                    provide_context(broadcasts.clone());
+                    provide_context(verified_games.clone());
                 }, App)
                 .fallback(file_and_error_handler)
                 .with_state(state);
@@ -81,6 +108,10 @@ cfg_if::cfg_if! {
         // 处理服务器端函数调用的路由处理器
         async fn server_fn_handler(
             State(db_pool): State<SqlitePool>,
+            State(rooms): State<Rooms>,
+            State(races): State<Rooms<RaceRoom>>,
+            State(broadcasts): State<Broadcasts>,
+            State(verified_games): State<VerifiedGames>,
             path: Path<String>,
             headers: HeaderMap,
             raw_query: RawQuery,
@@ -92,6 +123,10 @@ cfg_if::cfg_if! {
                 raw_query,
                 move || {
                     provide_context(db_pool.clone());
+                    provide_context(rooms.clone());
+                    provide_context(races.clone());
+                    provide_context(broadcasts.clone());
+                    provide_context(verified_games.clone());
                 },
                 request,
             )
@@ -0,0 +1,148 @@
+use gloo_timers::future::TimeoutFuture;
+use leptos::*;
+
+use crate::{
+    game_logic::{GameParams, GameState, GameStatus},
+    game_settings::{Difficulty, Mode, Size, Username},
+    utils::to_time,
+};
+
+// 多棋盘模式下同时进行的棋盘数量范围
+pub const MIN_BOARDS: usize = 2;
+pub const MAX_BOARDS: usize = 4;
+
+// 多棋盘模式下所有棋盘共享的生命数量
+const LIVES: u32 = 3;
+
+// 多棋盘模式下各棋盘共享的计时器, 生命和结果
+#[derive(Copy, Clone)]
+pub struct Session {
+    boards: usize,                   // 棋盘数量
+    boards_cleared: RwSignal<usize>, // 已清除的棋盘数量
+    lives_used: RwSignal<u32>,       // 已消耗的生命数量 (所有棋盘合计)
+    elapsed_seconds: RwSignal<i64>,  // 第一次点击后经过的秒数
+    status: RwSignal<GameStatus>,    // 整个会话的状态
+}
+
+impl Session {
+    // 创建会话及其中的各局游戏, 每块棋盘都是小尺寸
+    pub fn new(boards: usize, difficulty: Difficulty) -> (Self, Vec<GameState>) {
+        let session = Self {
+            boards: boards.clamp(MIN_BOARDS, MAX_BOARDS),
+            boards_cleared: create_rw_signal(0),
+            lives_used: create_rw_signal(0),
+            elapsed_seconds: create_rw_signal(0),
+            status: create_rw_signal(GameStatus::Idle),
+        };
+        let params = GameParams {
+            difficulty,
+            size: Size::Small,
+            mode: Some(Mode::MultiBoard),
+            shape: None,
+        };
+        let game_states = (0..session.boards)
+            .map(|_| GameState::in_session(params, session))
+            .collect();
+
+        (session, game_states)
+    }
+
+    // 第一次点击任意棋盘时开始计时
+    pub(crate) fn start(&self) {
+        if !matches!(self.status.get_untracked(), GameStatus::Idle) {
+            return;
+        }
+        self.status.set(GameStatus::Started);
+
+        let session = *self;
+        spawn_local(async move {
+            for second in 0..i64::MAX {
+                // 会话结束或页面销毁后停止计时
+                let running = session
+                    .status
+                    .try_with_untracked(|status| matches!(status, GameStatus::Started));
+                if running != Some(true) {
+                    break;
+                }
+
+                session.elapsed_seconds.set(second);
+                TimeoutFuture::new(1_000).await;
+            }
+        });
+    }
+
+    // 共享的生命数量
+    pub(crate) fn lives(&self) -> u32 {
+        LIVES
+    }
+
+    // 所有棋盘合计消耗的生命数量
+    pub(crate) fn lives_used(&self) -> u32 {
+        self.lives_used.get_untracked()
+    }
+
+    // 消耗一条共享的生命
+    pub(crate) fn lose_life(&self) {
+        self.lives_used.update(|lives_used| *lives_used += 1);
+    }
+
+    // 报告一块棋盘的结果, 任意一块失败即结束会话, 全部清除时获胜
+    pub(crate) fn board_finished(&self, won: bool) {
+        if self.status.get_untracked().is_finished() {
+            return;
+        }
+
+        if won {
+            self.boards_cleared.update(|cleared| *cleared += 1);
+            if self.boards_cleared.get_untracked() == self.boards {
+                self.status.set(GameStatus::Victory);
+            }
+        } else {
+            self.status.set(GameStatus::GameOver);
+        }
+    }
+
+    // 判断会话是否因某块棋盘失败而结束
+    pub fn is_lost(&self) -> bool {
+        matches!(self.status.get(), GameStatus::GameOver)
+    }
+
+    // 将整个会话的信息转换为视图
+    pub fn to_view(&self) -> impl IntoView {
+        let get_username = move || (expect_context::<ReadSignal<Username>>())().name; // 获取用户名
+        let time = to_time(self.elapsed_seconds.get());
+        let boards = format!(
+            "{} boards cleared out of {}",
+            self.boards_cleared.get(),
+            self.boards
+        );
+        let lives_left = format!("{} ❤️", LIVES.saturating_sub(self.lives_used.get()));
+
+        match self.status.get() {
+            GameStatus::GameOver => view! {
+                {move || format!("Game over, {} 😭", get_username())}
+                <br />
+                {boards}
+                <br />
+                {format!("Time - {time}")}
+                <br />
+            },
+            GameStatus::Victory => view! {
+                {move || format!("You won, {}! 🥳", get_username())}
+                <br />
+                {format!("Time - {time}")}
+                <br />
+                {format!("Lives used - {}", self.lives_used.get())}
+                <br />
+            },
+            _ => view! {
+                {boards}
+                <br />
+                {time}
+                <br />
+                {lives_left}
+                <br />
+            },
+        }
+    }
+}
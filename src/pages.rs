@@ -4,6 +4,47 @@ pub use homepage::HomePage;
 pub mod game;
 pub use game::Game;
 
+pub mod adaptive;
+pub use adaptive::Adaptive;
+
+pub mod series;
+pub use series::Series;
+
+pub mod multi_board;
+pub use multi_board::MultiBoard;
+
+pub mod ghost;
+
+pub mod bot;
+pub use bot::Versus;
+
+pub mod room;
+pub use room::Room;
+
+pub mod race;
+pub use race::Race;
+
+pub mod lobby;
+pub use lobby::Lobby;
+
+pub mod endless;
+pub use endless::Endless;
+
+pub mod daily;
+pub use daily::Daily;
+
+pub mod trainer;
+pub use trainer::Trainer;
+
+pub mod watch;
+pub use watch::Watch;
+
+pub mod verified;
+pub use verified::Verified;
+
+pub mod review;
+pub use review::Review;
+
 pub mod scores;
 pub use scores::Scores;
 
@@ -0,0 +1,162 @@
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_error::AppError,
+    game_logic::GameState,
+    game_settings::{Size, Username},
+    pages::{game::GameView, Error},
+};
+
+// 自适应模式参考最近几局的结果
+const RECENT_GAMES: i64 = 10;
+
+// 自适应模式的地雷密度范围, 初始密度, 以及胜率每偏离50%一个百分点时的密度调整量
+const MIN_DENSITY: f64 = 0.08;
+const MAX_DENSITY: f64 = 0.30;
+const START_DENSITY: f64 = 0.15;
+const DENSITY_STEP: f64 = 0.0008;
+
+// 根据 3BV/s 选择棋盘大小的阈值
+const MEDIUM_BBBV_PER_SECOND: f64 = 0.8;
+const LARGE_BBBV_PER_SECOND: f64 = 1.5;
+
+// 自适应模式为下一局选择的参数
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AdaptiveParams {
+    pub density: f64, // 地雷密度
+    pub size: Size,   // 棋盘大小
+}
+
+impl Default for AdaptiveParams {
+    fn default() -> Self {
+        Self {
+            density: START_DENSITY,
+            size: Size::Small,
+        }
+    }
+}
+
+// 最近一局自适应模式的结果
+#[cfg(feature = "ssr")]
+struct AdaptiveGame {
+    density: f64,
+    won: bool,
+    bbbv: i64,
+    time_in_seconds: i64,
+}
+
+// 根据用户最近的胜率和 3BV/s 选择下一局的参数, 目标胜率为50%
+#[server(GetAdaptiveParams)]
+async fn get_adaptive_params(username: String) -> Result<AdaptiveParams, ServerFnError> {
+    let pool = expect_context::<sqlx::SqlitePool>(); // 获取数据库连接池上下文
+
+    // 查询数据库
+    let games = sqlx::query_as!(
+        AdaptiveGame,
+        "
+            SELECT density, won, bbbv, time_in_seconds
+            FROM adaptive_games
+            WHERE username=?
+            ORDER BY id DESC
+            LIMIT ?
+        ",
+        username,
+        RECENT_GAMES
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let Some(last_game) = games.first() else {
+        return Ok(AdaptiveParams::default());
+    };
+
+    // 胜率高于50%时增加地雷密度, 低于50%时降低
+    let wins = games.iter().filter(|game| game.won).count();
+    let win_rate = wins as f64 / games.len() as f64;
+    let density = (last_game.density + DENSITY_STEP * (win_rate - 0.5) * 100.0)
+        .clamp(MIN_DENSITY, MAX_DENSITY);
+
+    // 获胜局的 3BV/s 越高, 棋盘越大
+    let (bbbv, seconds) = games
+        .iter()
+        .filter(|game| game.won)
+        .fold((0, 0), |(bbbv, seconds), game| {
+            (bbbv + game.bbbv, seconds + game.time_in_seconds)
+        });
+    let bbbv_per_second = bbbv as f64 / seconds.max(1) as f64;
+    let size = if bbbv_per_second >= LARGE_BBBV_PER_SECOND {
+        Size::Large
+    } else if bbbv_per_second >= MEDIUM_BBBV_PER_SECOND {
+        Size::Medium
+    } else {
+        Size::Small
+    };
+
+    Ok(AdaptiveParams { density, size })
+}
+
+// 记录一局自适应模式的结果
+#[server(PostAdaptiveGame)]
+pub async fn post_adaptive_game(
+    username: String,
+    density: f64,
+    size: Size,
+    won: bool,
+    bbbv: u32,
+    time_in_seconds: i64,
+) -> Result<(), ServerFnError> {
+    let pool = expect_context::<sqlx::SqlitePool>(); // 获取数据库连接池上下文
+    let size = size.to_string();
+
+    // 向数据库中插入数据
+    sqlx::query!(
+        "
+            INSERT INTO adaptive_games(username, density, size, won, bbbv, time_in_seconds)
+            VALUES (?, ?, ?, ?, ?, ?)
+        ",
+        username,
+        density,
+        size,
+        won,
+        bbbv,
+        time_in_seconds,
+    )
+    .execute(&pool)
+    .await
+    .map(|_| ())
+    .map_err(Into::into)
+}
+
+// 渲染自适应模式的游戏, 每开始新的一局都重新选择参数
+#[component]
+pub fn Adaptive() -> impl IntoView {
+    let username = expect_context::<ReadSignal<Username>>();
+    let (round, set_round) = create_signal(0);
+    let adaptive_params = create_resource(round, move |_| async move {
+        get_adaptive_params(username.get_untracked().name).await
+    });
+
+    view! {
+        <Suspense fallback=|| ()>
+            {move || adaptive_params().map(|adaptive_params| match adaptive_params {
+                Ok(adaptive_params) => view! {
+                    <GameView
+                        game_state=GameState::adaptive(adaptive_params)
+                        on_new_game=move |_| set_round.update(|round| *round += 1)
+                    />
+                }
+                .into_view(),
+                Err(_) => {
+                    let mut outside_errors = Errors::default();
+                    outside_errors.insert_with_default_key(AppError::AdaptiveUnavailable);
+
+                    view! {
+                        <Error outside_errors /> // 显示错误信息
+                    }
+                    .into_view()
+                }
+            })}
+        </Suspense>
+    }
+}
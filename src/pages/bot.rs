@@ -0,0 +1,202 @@
+use gloo_timers::future::TimeoutFuture;
+use leptos::*;
+use leptos_router::*;
+
+use crate::{
+    bot_logic::{Bot, BotStrength},
+    game_logic::{GameInfo, GameParams, GameState, GameStatus},
+    game_settings::{apply_setting, fetch_setting, Difficulty, Size},
+    pages::{game::GameView, lobby::option_views},
+};
+
+// 对战电脑页面的地址, 指定棋盘参数和地雷布局种子
+fn versus_path(difficulty: Difficulty, size: Size, seed: u64) -> String {
+    format!("/versus?difficulty={difficulty}&size={size}&seed={seed}")
+}
+
+// 渲染对战电脑页面, 没有种子时随机生成一个
+#[component]
+pub fn Versus() -> impl IntoView {
+    window_event_listener(ev::contextmenu, |ev| ev.prevent_default()); // 禁用右键菜单
+
+    let query = use_query_map();
+    let params = move || {
+        query.with(|query| {
+            (
+                query.get("seed").and_then(|seed| seed.parse::<u64>().ok()),
+                query
+                    .get("difficulty")
+                    .and_then(|difficulty| difficulty.parse::<Difficulty>().ok())
+                    .unwrap_or_default(),
+                query
+                    .get("size")
+                    .and_then(|size| size.parse::<Size>().ok())
+                    .unwrap_or_default(),
+            )
+        })
+    };
+
+    // 随机的种子只在浏览器中生成, 并替换当前地址, 方便分享同一块棋盘
+    let navigate = use_navigate();
+    create_effect(move |_| {
+        if let (None, difficulty, size) = params() {
+            navigate(
+                &versus_path(difficulty, size, rand::random()),
+                NavigateOptions {
+                    replace: true,
+                    ..Default::default()
+                },
+            );
+        }
+    });
+
+    let navigate = use_navigate();
+    move || {
+        let (seed, difficulty, size) = params();
+        let navigate = navigate.clone();
+        seed.map(|seed| {
+            let game_state = GameState::race(
+                GameParams {
+                    difficulty,
+                    size,
+                    mode: None,
+                    shape: None,
+                },
+                seed,
+            );
+            // 新游戏换一块新的棋盘
+            let on_new_game = move |_: ()| {
+                navigate(
+                    &versus_path(difficulty, size, rand::random()),
+                    Default::default(),
+                )
+            };
+
+            view! { <GameView game_state on_new_game bot=true /> }
+        })
+    }
+}
+
+// 电脑对手: 玩家第一次点击时从同一个安全起点出发, 用进度条比较双方的进度
+#[component]
+pub fn BotRace(
+    start: usize,               // 安全起点的索引
+    info: ReadSignal<GameInfo>, // 玩家的游戏信息信号
+) -> impl IntoView {
+    let game_state = expect_context::<ReadSignal<GameState>>();
+    let (strength, set_strength) =
+        create_signal(fetch_setting::<BotStrength>("bot_strength").unwrap_or_default());
+    let (progress, set_progress) = create_signal((0, 1)); // 电脑对手已清除和需要清除的单元格数量
+    let (bot_status, set_bot_status) = create_signal(GameStatus::Idle);
+    let (winner, set_winner) = create_signal(None::<bool>); // 先清除棋盘的一方, true 表示玩家
+    let run = store_value(0u32); // 对局的编号, 用于停止上一局的电脑对手
+
+    // 电脑对手按强度思考后走一步, 开始新的一局或离开页面后停止
+    let play = move |mut bot: Bot| {
+        let current = run.get_value();
+        set_progress(bot.progress());
+        set_bot_status(bot.status());
+
+        spawn_local(async move {
+            let mut rng = rand::thread_rng();
+            while bot.status() == GameStatus::Started {
+                TimeoutFuture::new(bot.strength().think_ms()).await;
+                if run.try_get_value() != Some(current) {
+                    return;
+                }
+
+                bot.step(&mut rng);
+                set_progress(bot.progress());
+                set_bot_status(bot.status());
+            }
+        });
+    };
+
+    // 玩家第一次点击后地雷才生成, 延迟到当前更新完成后再读取布局, 避免在更新游戏状态时读取
+    create_effect(move |was_idle: Option<bool>| {
+        let status = info.with(GameInfo::status);
+        match status {
+            GameStatus::Idle => {
+                run.update_value(|run| *run += 1);
+                set_progress((0, 1));
+                set_bot_status(GameStatus::Idle);
+                set_winner(None);
+            }
+            GameStatus::Started if was_idle == Some(true) => queue_microtask(move || {
+                if let Some(layout) = game_state.try_with_untracked(GameState::layout).flatten() {
+                    play(Bot::new(layout, strength.get_untracked(), start));
+                }
+            }),
+            _ => {}
+        }
+        matches!(status, GameStatus::Idle)
+    });
+
+    // 记录先清除棋盘的一方
+    create_effect(move |_| {
+        let player_won = info.with(|info| info.status() == GameStatus::Victory);
+        let bot_won = bot_status() == GameStatus::Victory;
+        if winner.get_untracked().is_none() && (player_won || bot_won) {
+            set_winner(Some(player_won));
+        }
+    });
+
+    let result = move || {
+        let status = info.with(GameInfo::status);
+        match (winner(), status, bot_status()) {
+            (Some(true), _, _) => "You beat the bot! 🥳",
+            (Some(false), _, _) => "The bot cleared the board first 🤖",
+            (None, GameStatus::GameOver | GameStatus::TimedOut, GameStatus::GameOver) => {
+                "Both of you hit a mine"
+            }
+            (None, _, GameStatus::GameOver) => "The bot hit a mine, keep going!",
+            (None, GameStatus::GameOver | GameStatus::TimedOut, _) => {
+                "You hit a mine, the bot keeps going"
+            }
+            _ => "",
+        }
+    };
+
+    // 进度的百分比
+    let percent = |(cleared, total): (usize, usize)| {
+        format!("{:.0}%", cleared as f64 * 100.0 / total.max(1) as f64)
+    };
+
+    view! {
+        <div class="btns">
+            // 强度在下一局生效
+            <select
+                class="inline-select"
+                on:change=move |ev| {
+                    if let Ok(value) = event_target_value(&ev).parse::<BotStrength>() {
+                        apply_setting("bot_strength", &value);
+                        set_strength(value);
+                    }
+                }
+            >
+                {option_views(&BotStrength::ALL, strength)}
+            </select>
+        </div>
+
+        <div class="daily-note">
+            {result}
+        </div>
+
+        <div class="ghost-race">
+            <div class="ghost-race-label">"You"</div>
+            <div class="ghost-race-track">
+                <div
+                    class="ghost-race-bar player"
+                    style:width=move || percent(info.with(|info| {
+                        let (cleared, total) = info.progress();
+                        (cleared.max(0) as usize, total.max(0) as usize)
+                    }))
+                />
+            </div>
+            <div class="ghost-race-label">"Bot"</div>
+            <div class="ghost-race-track">
+                <div class="ghost-race-bar ghost" style:width=move || percent(progress()) />
+            </div>
+        </div>
+    }
+}
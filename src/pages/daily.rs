@@ -0,0 +1,422 @@
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_error::AppError,
+    game_logic::{GameParams, GameState},
+    game_settings::{Mode, Username},
+    pages::{
+        game::GameView,
+        scores::{Score, ScoreRows},
+        Error,
+    },
+    replay::MoveKind,
+    verify_logic::ReplayPosition,
+};
+
+// 每日排行榜只显示前10名, 存档只显示最近30天
+const MAX_SCORES: usize = 10;
+const MAX_ARCHIVE_DAYS: usize = 30;
+
+// 每日挑战信息, 当天的布局由服务器根据日期和密钥生成并保存, 不发送给客户端
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DailyBoard {
+    pub date: String,    // 发出棋盘的日期, 格式为 YYYY-MM-DD
+    pub attempted: bool, // 当前用户是否已用掉当天的排名机会 (之后只能在随机的棋盘上练习)
+}
+
+// 由日期和服务器密钥计算当天的种子 (FNV-1a 哈希, 结果不随编译器版本变化)
+#[cfg(feature = "ssr")]
+fn daily_seed(secret: &str, date: &str) -> u64 {
+    format!("{secret}:{date}")
+        .bytes()
+        .fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3)
+        })
+}
+
+// 获取服务器当天的日期
+#[cfg(feature = "ssr")]
+fn today() -> String {
+    chrono::Utc::now()
+        .date_naive()
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+// 获取服务器昨天的日期 (跨过午夜的尝试仍然可以继续)
+#[cfg(feature = "ssr")]
+fn yesterday() -> String {
+    (chrono::Utc::now().date_naive() - chrono::Duration::days(1))
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+// 判断种子是否为今天的每日挑战 (当天结束之前不提供这块棋盘的幽灵)
+#[cfg(feature = "ssr")]
+pub(crate) fn is_todays_board(seed: u64) -> bool {
+    dotenvy::var("DAILY_SECRET").is_ok_and(|secret| daily_seed(&secret, &today()) == seed)
+}
+
+// 获取今天的每日挑战, 第一次获取时就记录排名尝试 (放弃的尝试记为失败)
+#[server(GetDailyBoard)]
+async fn get_daily_board(username: String) -> Result<DailyBoard, ServerFnError> {
+    use crate::validation::{validate_username, ValidationError};
+
+    validate_username(&username).map_err(ValidationError::into_server_error)?;
+    dotenvy::var("DAILY_SECRET")
+        .map_err(|_| ServerFnError::ServerError("DAILY_SECRET is not set".into()))?;
+
+    let pool = expect_context::<sqlx::SqlitePool>(); // 获取数据库连接池上下文
+    let date = today();
+
+    // 已有记录时忽略, 说明当前用户今天已经开始过排名尝试
+    let recorded = sqlx::query!(
+        "
+            INSERT OR IGNORE INTO daily_scores(username, date, time_in_seconds, won, verified, finished)
+            VALUES (?, ?, 0, 0, 1, 0)
+        ",
+        username,
+        date,
+    )
+    .execute(&pool)
+    .await?
+    .rows_affected()
+        > 0;
+
+    // 中途重新获取棋盘 (例如刷新页面) 时结束之前的尝试
+    if !recorded {
+        sqlx::query!(
+            "UPDATE daily_scores SET finished=1 WHERE username=? AND date=?",
+            username,
+            date,
+        )
+        .execute(&pool)
+        .await?;
+    }
+
+    Ok(DailyBoard {
+        date,
+        attempted: !recorded,
+    })
+}
+
+// 每日挑战的排名尝试中的一次操作 (挖掘或插旗): 服务器在当天的布局上进行到这一步,
+// 只返回已翻开的单元格, 尝试结束时记录结果, 用时和分析
+#[server(PostDailyMove)]
+pub async fn post_daily_move(
+    username: String,
+    date: String,
+    kind: MoveKind,
+    index: usize,
+) -> Result<ReplayPosition, ServerFnError> {
+    use crate::pages::scores::{post_coin_flips, review_run};
+    use crate::replay::{ReplayMove, ScoreReplay};
+    use crate::utils::now_millis;
+    use crate::validation::{validate_score, ValidationError};
+    use crate::verify_logic::{analyze_loss, replay_position, verify_replay, ReplayError};
+
+    let pool = expect_context::<sqlx::SqlitePool>(); // 获取数据库连接池上下文
+    let secret = dotenvy::var("DAILY_SECRET")
+        .map_err(|_| ServerFnError::ServerError("DAILY_SECRET is not set".into()))?;
+
+    // 使用发出棋盘的日期, 只接受今天和昨天的棋盘
+    if date != today() && date != yesterday() {
+        return Err(ServerFnError::ServerError(
+            "daily challenge has ended".into(),
+        ));
+    }
+
+    let attempt = sqlx::query!(
+        "SELECT replay, started_at FROM daily_scores WHERE username=? AND date=? AND finished=0",
+        username,
+        date,
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| ServerFnError::ServerError("no ranked attempt in progress".into()))?;
+
+    let mut replay = match attempt.replay.as_str() {
+        "" => ScoreReplay {
+            seed: daily_seed(&secret, &date),
+            moves: Vec::new(),
+        },
+        replay => replay
+            .parse::<ScoreReplay>()
+            .map_err(|error| ServerFnError::ServerError(ReplayError::from(error).to_string()))?,
+    };
+
+    // 由服务器计时: 第一次挖掘开始计时, 之前的插旗记为第0毫秒
+    let now = now_millis();
+    let started_at = match attempt.started_at {
+        0 if kind == MoveKind::Dig => now,
+        started_at => started_at,
+    };
+    let millis = match started_at {
+        0 => 0,
+        started_at => now - started_at,
+    };
+    replay.moves.push(ReplayMove {
+        millis,
+        kind,
+        index,
+    });
+
+    // 第一次挖掘必须在棋盘中央的安全起点上, 所有玩家的布局才会相同
+    let params = GameParams::DAILY;
+    let (rows, columns) = GameState::size_dimensions(params.size);
+    let start = (rows / 2 * columns + columns / 2) as usize;
+    let position = replay_position(
+        params.difficulty,
+        params.size,
+        params.mode(),
+        start,
+        &replay,
+    )
+    .map_err(|error| ServerFnError::ServerError(error.to_string()))?;
+
+    // 获胜时由服务器的计时得出用时
+    let finished = position.status.is_finished();
+    let run = verify_replay(params.difficulty, params.size, params.mode(), &replay).ok();
+    if let Some(run) = &run {
+        validate_score(&username, run.time_in_seconds)
+            .map_err(ValidationError::into_server_error)?;
+    }
+    // 尝试结束时分析这一局: 服务器保存了每一步, 失败的尝试同样统计五五开, 获胜的成绩可疑时等待审核
+    let analysis = match &run {
+        Some(run) => Some(run.analysis.clone()),
+        None if finished => {
+            analyze_loss(params.difficulty, params.size, params.mode(), &replay).ok()
+        }
+        None => None,
+    };
+    let (suspicions, approved) = match &run {
+        Some(run) => review_run(&pool, &username, &run.analysis).await?,
+        None => (String::new(), true),
+    };
+    let (won, time_in_seconds) = (run.is_some(), run.map_or(0, |run| run.time_in_seconds));
+    let replay = replay.to_string();
+
+    // 更新数据库中的尝试, 同时到达的操作只接受一个
+    let updated = sqlx::query!(
+        "
+            UPDATE daily_scores
+            SET replay=?, started_at=?, finished=?, won=?, time_in_seconds=?, suspicions=?,
+                approved=?
+            WHERE username=? AND date=? AND finished=0 AND replay=?
+        ",
+        replay,
+        started_at,
+        finished,
+        won,
+        time_in_seconds,
+        suspicions,
+        approved,
+        username,
+        date,
+        attempt.replay,
+    )
+    .execute(&pool)
+    .await?
+    .rows_affected();
+    if updated == 0 {
+        return Err(ServerFnError::ServerError(
+            "no ranked attempt in progress".into(),
+        ));
+    }
+    if let Some(analysis) = analysis {
+        post_coin_flips(&pool, &username, analysis.coin_flips).await?;
+    }
+
+    Ok(position)
+}
+
+// 获取指定日期的每日排行榜 (只包含服务器验证过且没有等待审核的获胜尝试)
+#[server(GetDailyScores)]
+async fn get_daily_scores(date: String) -> Result<Vec<Score>, ServerFnError> {
+    let pool = expect_context::<sqlx::SqlitePool>(); // 获取数据库连接池上下文
+
+    // 查询数据库
+    sqlx::query_as!(
+        Score,
+        r#"
+            SELECT username, time_in_seconds, 0 AS "lives_used!: i64", 0 AS "cells_cleared!: i64",
+                NULL AS "rating: f64"
+            FROM daily_scores
+            WHERE date=?
+                AND won
+                AND verified=1
+                AND approved=1
+            ORDER BY time_in_seconds
+            LIMIT ?
+        "#,
+        date,
+        MAX_SCORES as i64
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(Into::into)
+}
+
+// 获取存档中有成绩的日期 (不含今天), 按日期倒序
+#[server(GetDailyDates)]
+async fn get_daily_dates() -> Result<Vec<String>, ServerFnError> {
+    let pool = expect_context::<sqlx::SqlitePool>(); // 获取数据库连接池上下文
+    let date = today();
+
+    // 查询数据库
+    sqlx::query_scalar!(
+        "
+            SELECT DISTINCT date
+            FROM daily_scores
+            WHERE date<?
+                AND verified=1
+            ORDER BY date DESC
+            LIMIT ?
+        ",
+        date,
+        MAX_ARCHIVE_DAYS as i64
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(Into::into)
+}
+
+// 渲染每日挑战页面
+#[component]
+pub fn Daily() -> impl IntoView {
+    window_event_listener(ev::contextmenu, |ev| ev.prevent_default()); // 禁用右键菜单
+
+    let username = expect_context::<ReadSignal<Username>>();
+    let (round, set_round) = create_signal(0);
+    let daily_board = create_resource(round, move |_| async move {
+        get_daily_board(username.get_untracked().name).await
+    });
+
+    view! {
+        <div class="text-2xl font-semibold">"Daily Challenge"</div>
+
+        <Suspense fallback=|| ()>
+            {move || daily_board().map(|daily_board| match daily_board {
+                Ok(daily_board) => view! {
+                    <DailyGame
+                        daily_board
+                        on_new_game=move |_| set_round.update(|round| *round += 1)
+                    />
+                }
+                .into_view(),
+                Err(_) => {
+                    let mut outside_errors = Errors::default();
+                    outside_errors.insert_with_default_key(AppError::DailyUnavailable);
+
+                    view! {
+                        <Error outside_errors /> // 显示错误信息
+                    }
+                    .into_view()
+                }
+            })}
+        </Suspense>
+    }
+}
+
+// 渲染当天的棋盘和排行榜, 用掉排名机会之后改为在随机的棋盘上练习
+#[component]
+fn DailyGame(
+    daily_board: DailyBoard,
+    #[prop(into)] on_new_game: Callback<()>, // 开始新的一局时重新获取棋盘
+) -> impl IntoView {
+    let date = daily_board.date.clone();
+    let attempted = daily_board.attempted;
+    let game_state = if attempted {
+        GameState::new(GameParams::DAILY)
+    } else {
+        GameState::daily(daily_board)
+    };
+
+    view! {
+        <div class="daily-note">
+            {date.clone()}
+            " - "
+            {if attempted {
+                "Your ranked attempt is used, this board is for practice"
+            } else {
+                "One ranked attempt, start from the marked cell. Leaving the page ends it"
+            }}
+        </div>
+
+        <GameView game_state on_new_game />
+
+        <div class="panel-label mt-8">"Today's Leaderboard"</div>
+        <DailyScoreboard date />
+
+        <DailyArchive />
+    }
+}
+
+// 渲染过去几天的排行榜存档
+#[component]
+fn DailyArchive() -> impl IntoView {
+    let dates = create_resource(
+        || (),
+        |_| async move { get_daily_dates().await.unwrap_or_default() },
+    );
+    let (selected, set_selected) = create_signal(None::<String>);
+
+    view! {
+        <div class="panel">
+            <div class="panel-label">
+                "Archive"
+            </div>
+            <table class="panel-table">
+                <tr class="panel-row">
+                    <td>
+                        <select on:change=move |ev| set_selected(Some(event_target_value(&ev)))>
+                            <option value="" selected=move || selected().is_none() disabled=true>
+                                "Choose a day"
+                            </option>
+                            {move || dates().unwrap_or_default().into_iter().map(|date| {
+                                view! {
+                                    <option value=date.clone()>
+                                        {date}
+                                    </option>
+                                }
+                            }).collect_view()}
+                        </select>
+                    </td>
+                </tr>
+            </table>
+        </div>
+
+        {move || selected().map(|date| view! { <DailyScoreboard date /> })}
+    }
+}
+
+// 每日排行榜组件
+#[component]
+fn DailyScoreboard(date: String) -> impl IntoView {
+    let score_getter = create_resource(
+        move || date.clone(),
+        |date| async move { get_daily_scores(date).await.unwrap_or_default() },
+    );
+
+    view! {
+        <div>
+            <table class="scoreboard">
+                <tr class="header">
+                    <th class="n">
+                        "#"
+                    </th>
+                    <th class="name">
+                        "Name"
+                    </th>
+                    <th class="time">
+                        "Time"
+                    </th>
+                </tr>
+                <Transition fallback=move || view! { <ScoreRows scores=vec![] mode=Mode::Classic /> }>
+                    {move || view! { <ScoreRows scores=score_getter().unwrap_or_default() mode=Mode::Classic /> }}
+                </Transition>
+            </table>
+        </div>
+    }
+}
@@ -0,0 +1,208 @@
+use leptos::*;
+use leptos_router::*;
+
+use crate::endless_logic::{ChunkPos, EndlessState, CHUNK_SIZE};
+use crate::game_logic::{CellInteraction, CellKind};
+use crate::game_settings::Difficulty;
+use crate::pages::game::CellView;
+
+// 单元格的边长(像素)
+const CELL_PIXELS: isize = 40;
+
+// 渲染无尽模式的游戏
+#[component]
+pub fn Endless(difficulty: Difficulty) -> impl IntoView {
+    let endless_state = EndlessState::new(difficulty);
+    let chunks = endless_state.chunks_signal();
+    let info = endless_state.info_signal();
+
+    let (_, endless_state_write) = create_signal(endless_state);
+    provide_context(endless_state_write);
+
+    view! {
+        <div class="btns">
+            <div class="btn">
+                <A
+                    href=""
+                    on:click=move |ev| {
+                        ev.prevent_default();
+                        endless_state_write.update(|endless_state| endless_state.reset());
+                    }
+                >
+                    "New Game"
+                </A>
+            </div>
+            <div class="btn">
+                <A href="/">
+                    "Return"
+                </A>
+            </div>
+        </div>
+
+        <h2 class="info">
+            { move || info.with(|info| info.to_view()) }
+        </h2>
+
+        <EndlessBoard chunks />
+    }
+}
+
+// 渲染可滚动的无尽棋盘, 按下鼠标中键拖动可以平移视图
+#[component]
+fn EndlessBoard(chunks: ReadSignal<Vec<ChunkPos>>) -> impl IntoView {
+    let viewport_ref = create_node_ref::<html::Div>();
+    let (drag_origin, set_drag_origin) = create_signal(None::<(i32, i32, i32, i32)>);
+
+    // 已显示区块的边界 (最小行, 最小列, 最大行, 最大列)
+    let bounds = create_memo(move |_| {
+        chunks.with(|chunks| {
+            chunks.iter().fold(
+                (isize::MAX, isize::MAX, isize::MIN, isize::MIN),
+                |(min_row, min_column, max_row, max_column), &(row, column)| {
+                    (
+                        min_row.min(row),
+                        min_column.min(column),
+                        max_row.max(row),
+                        max_column.max(column),
+                    )
+                },
+            )
+        })
+    });
+    let chunk_pixels = CHUNK_SIZE * CELL_PIXELS;
+
+    // 棋盘向上或向左扩展时调整滚动位置, 保持当前视图不跳动; 首次渲染时将原点区块滚动到中央
+    create_effect(
+        move |previous: Option<Option<(isize, isize, isize, isize)>>| {
+            let current = bounds();
+            let viewport = viewport_ref.get()?;
+
+            match previous.flatten() {
+                Some((min_row, min_column, _, _)) => {
+                    viewport.set_scroll_top(
+                        viewport.scroll_top() + ((min_row - current.0) * chunk_pixels) as i32,
+                    );
+                    viewport.set_scroll_left(
+                        viewport.scroll_left() + ((min_column - current.1) * chunk_pixels) as i32,
+                    );
+                }
+                None => {
+                    viewport.set_scroll_top(
+                        (-current.0 * chunk_pixels) as i32
+                            - (viewport.client_height() - chunk_pixels as i32) / 2,
+                    );
+                    viewport.set_scroll_left(
+                        (-current.1 * chunk_pixels) as i32
+                            - (viewport.client_width() - chunk_pixels as i32) / 2,
+                    );
+                }
+            }
+
+            Some(current)
+        },
+    );
+
+    view! {
+        <div
+            class="game-board endless"
+            node_ref=viewport_ref
+
+            on:mousedown=move |event| {
+                // 鼠标中键按下时开始拖动
+                if event.button() == 1 {
+                    event.prevent_default();
+                    let viewport = viewport_ref.get().expect("noderef assigned");
+                    set_drag_origin(Some((
+                        event.client_x(),
+                        event.client_y(),
+                        viewport.scroll_left(),
+                        viewport.scroll_top(),
+                    )));
+                }
+            }
+
+            on:mousemove=move |event| {
+                if let Some((x, y, scroll_left, scroll_top)) = drag_origin() {
+                    let viewport = viewport_ref.get().expect("noderef assigned");
+                    viewport.set_scroll_left(scroll_left - (event.client_x() - x));
+                    viewport.set_scroll_top(scroll_top - (event.client_y() - y));
+                }
+            }
+
+            on:mouseup=move |_| set_drag_origin(None)
+            on:mouseleave=move |_| set_drag_origin(None)
+        >
+            <div
+                class="endless-plane"
+                style:width=move || {
+                    let (_, min_column, _, max_column) = bounds();
+                    format!("{}px", (max_column - min_column + 1) * chunk_pixels)
+                }
+                style:height=move || {
+                    let (min_row, _, max_row, _) = bounds();
+                    format!("{}px", (max_row - min_row + 1) * chunk_pixels)
+                }
+            >
+                <For
+                    each=chunks
+                    key=|chunk| *chunk
+                    children=move |chunk| {
+                        view! { <Chunk chunk bounds /> }
+                    }
+                />
+            </div>
+        </div>
+    }
+}
+
+// 渲染一个区块, 区块内部使用网格布局
+#[component]
+fn Chunk(chunk: ChunkPos, bounds: Memo<(isize, isize, isize, isize)>) -> impl IntoView {
+    let (chunk_row, chunk_column) = chunk;
+    let chunk_pixels = CHUNK_SIZE * CELL_PIXELS;
+
+    view! {
+        <div
+            class="endless-chunk"
+            style:top=move || format!("{}px", (chunk_row - bounds().0) * chunk_pixels)
+            style:left=move || format!("{}px", (chunk_column - bounds().1) * chunk_pixels)
+            style:width=format!("{chunk_pixels}px")
+            style:height=format!("{chunk_pixels}px")
+        >
+            {
+                (0..CHUNK_SIZE)
+                    .flat_map(|row| (0..CHUNK_SIZE).map(move |column| (row, column)))
+                    .map(|(row, column)| {
+                        view! {
+                            <EndlessCell
+                                row=chunk_row * CHUNK_SIZE + row
+                                column=chunk_column * CHUNK_SIZE + column
+                            />
+                        }
+                    })
+                    .collect_view()
+            }
+        </div>
+    }
+}
+
+// 渲染无尽棋盘的单元格
+#[component]
+fn EndlessCell(row: isize, column: isize) -> impl IntoView {
+    let (cell_state, set_cell_state) =
+        create_signal((CellInteraction::Untouched, CellKind::Clear(0)));
+    let endless_state_write = expect_context::<WriteSignal<EndlessState>>();
+
+    endless_state_write
+        .update(|endless_state| endless_state.register_cell(row, column, set_cell_state));
+
+    view! {
+        <CellView
+            row=row.rem_euclid(CHUNK_SIZE)
+            column=column.rem_euclid(CHUNK_SIZE)
+            cell_state
+            on_dig=move |_| endless_state_write.update(|endless_state| endless_state.dig(row, column))
+            on_flag=move |_| endless_state_write.update(|endless_state| endless_state.flag(row, column))
+        />
+    }
+}
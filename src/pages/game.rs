@@ -1,10 +1,20 @@
+use gloo_timers::future::TimeoutFuture;
 use leptos::*;
 use leptos_router::*;
 
 use crate::app_error::AppError;
-use crate::game_logic::{CellInteraction, CellKind, GameParams, GameState};
-use crate::game_settings::Size;
-use crate::pages::Error;
+use crate::board_mask::{BoardMask, ParseMaskError};
+use crate::game_logic::{CellInteraction, CellKind, GameParams, GameState, GameStatus};
+use crate::game_settings::{fetch_setting, Mode, Shape, Size};
+use crate::pages::{
+    bot::BotRace,
+    ghost::GhostRace,
+    watch::{GoLive, Publisher},
+    Adaptive, Endless, Error, MultiBoard, Room, Series,
+};
+use crate::replay::Ghost;
+use crate::room::valid_room_name;
+use crate::verify_logic::REPLAY_MODES;
 
 // 定义用于显示数字的SVG图标
 const NUM_SVGS: [&str; 9] = [
@@ -27,47 +37,74 @@ const FLAG_SVG: &str = include_str!("../../svgs/flag.svg"); // 旗帜图标
 pub fn Game() -> impl IntoView {
     window_event_listener(ev::contextmenu, |ev| ev.prevent_default()); // 禁用右键菜单
 
+    // 指定房间名称时加入多人合作房间
+    let room = use_query_map()
+        .with_untracked(|query| query.get("room").cloned())
+        .filter(|room| valid_room_name(room));
+    // 指定种子时重玩之前的布局 (只支持服务器可以重新验证的模式)
+    let seed = use_query_map()
+        .with_untracked(|query| query.get("seed").and_then(|seed| seed.parse::<u64>().ok()));
+
     use_query::<GameParams>().with_untracked(|params| match params {
-        Ok(params) => {
-            let game_state = GameState::new(*params);
-            let (rows, columns) = game_state.dimensions();
-            let new_game_enabled = game_state.new_game_enabled_signal();
+        // 多人房间的棋盘由服务器维护, 参数只在创建房间时使用 (支持经典模式和夺旗模式)
+        Ok(params) if room.is_some() => view! {
+            <Room
+                room=room.clone().expect("room is set")
+                difficulty=params.difficulty
+                size=params.size
+                mode=params.mode()
+            />
+        }
+        .into_view(),
 
-            let (game_state_read, game_state_write) = create_signal(game_state);
-            provide_context(game_state_read);
-            provide_context(game_state_write);
+        // 无尽模式使用单独的棋盘
+        Ok(params) if params.mode() == Mode::Endless => view! {
+            <Endless difficulty=params.difficulty />
+        }
+        .into_view(),
 
-            view! {
-                <div class="btns">
-                    <div class=move || { format!("btn {}", if new_game_enabled() { "" } else { "disabled" }) }>
-                        <A
-                            href=""
+        // 多棋盘模式在同一页面上同时进行多局游戏
+        Ok(params) if params.mode() == Mode::MultiBoard => view! {
+            <MultiBoard difficulty=params.difficulty />
+        }
+        .into_view(),
 
-                            on:click=move |ev| {
-                                ev.prevent_default();
+        // 连续挑战的种子由服务器分配 (自定义形状不计入排行榜, 仍使用随机布局)
+        Ok(params) if params.mode() == Mode::Series && params.shape() != Shape::Custom => view! {
+            <Series params=*params />
+        }
+        .into_view(),
 
-                                if new_game_enabled() {
-                                    game_state_write.update(|game_state| game_state.reset());
-                                }
-                            }
+        // 自适应模式的参数由服务器根据最近的成绩选择
+        Ok(params) if params.mode() == Mode::Adaptive => view! {
+            <Adaptive />
+        }
+        .into_view(),
 
-                            class=move || { if new_game_enabled() { "" } else { "disabled" } }
-                        >
-                            "New Game"
-                        </A>
-                    </div>
-                    <div class="btn">
-                        <A href="/">
-                            "Return"
-                        </A>
-                    </div>
-                </div>
+        Ok(params) => {
+            // 自定义形状的遮罩保存在设置中
+            let game_state = match params.shape() {
+                Shape::Custom => match fetch_setting::<BoardMask>("mask") {
+                    Some(mask) => GameState::with_mask(*params, mask),
+                    None => {
+                        let mut outside_errors = Errors::default();
+                        outside_errors
+                            .insert_with_default_key(AppError::MaskError(ParseMaskError::Empty));
 
-                <Info /> // 显示游戏信息
+                        return view! {
+                            <Error outside_errors /> // 显示错误信息
+                        }
+                        .into_view();
+                    }
+                },
+                Shape::Rectangle => match seed.filter(|_| REPLAY_MODES.contains(&params.mode())) {
+                    Some(seed) => GameState::replay_board(*params, seed),
+                    None => GameState::new(*params),
+                },
+                _ => GameState::new(*params),
+            };
 
-                <Board rows columns size=params.size /> // 渲染扫雷游戏的棋盘(面板)
-            }
-            .into_view()
+            view! { <GameView game_state /> }.into_view()
         }
 
         Err(error) => {
@@ -82,6 +119,168 @@ pub fn Game() -> impl IntoView {
     })
 }
 
+// 渲染一局游戏 (按钮, 游戏信息和棋盘), 供其他页面复用
+#[component]
+pub fn GameView(
+    game_state: GameState,
+    #[prop(optional, into)] on_new_game: Option<Callback<()>>, // 新游戏回调, 缺省时在原棋盘上重新开始
+    #[prop(optional)] bot: bool,                               // 是否与电脑对手比赛 (代替幽灵比赛)
+) -> impl IntoView {
+    let (rows, columns) = game_state.dimensions();
+    let mask = game_state.mask().clone();
+    let size = game_state.params().size;
+    let start = game_state.start_cell();
+    let memory = game_state.params().mode() == Mode::Memory;
+    let seed = game_state.seed();
+    let ghosts = game_state.has_ghosts();
+    let retry_allowed = game_state.params().mode() != Mode::Puzzle;
+    let new_game_enabled = game_state.new_game_enabled_signal();
+    let info = game_state.info_signal();
+    let params = game_state.params();
+
+    let (game_state_read, game_state_write) = create_signal(game_state);
+    provide_context(game_state_read);
+    provide_context(game_state_write);
+
+    // 单元格的变化记录在直播中, 开始直播后发送给观众
+    let publisher = Publisher::new(columns);
+    provide_context(publisher);
+
+    // 固定布局下可以与之前的回放 (幽灵) 比赛
+    let ghost = seed
+        .filter(|_| !bot && ghosts)
+        .map(|_| Ghost::new(rows, columns));
+    if let Some(ghost) = ghost {
+        provide_context(ghost);
+    }
+
+    // 限时模式下计时器归零后结束游戏
+    create_effect(move |_| {
+        if info.with(|info| info.timed_out()) {
+            game_state_write.update(|game_state| game_state.time_out());
+        }
+    });
+
+    // 记忆模式下按住空格键重新显示已隐藏的数字
+    if memory {
+        window_event_listener(ev::keydown, move |ev| {
+            if ev.key() == " " {
+                ev.prevent_default(); // 避免页面滚动
+                if !ev.repeat() {
+                    game_state_write.update(|game_state| game_state.peek(true));
+                }
+            }
+        });
+        window_event_listener(ev::keyup, move |ev| {
+            if ev.key() == " " {
+                game_state_write.update(|game_state| game_state.peek(false));
+            }
+        });
+    }
+
+    view! {
+        <div class="btns">
+            <div class=move || { format!("btn {}", if new_game_enabled() { "" } else { "disabled" }) }>
+                <A
+                    href=""
+
+                    on:click=move |ev| {
+                        ev.prevent_default();
+
+                        if new_game_enabled() {
+                            match on_new_game {
+                                Some(on_new_game) => on_new_game(()),
+                                None => game_state_write.update(|game_state| game_state.reset()),
+                            }
+                        }
+                    }
+
+                    class=move || { if new_game_enabled() { "" } else { "disabled" } }
+                >
+                    "New Game"
+                </A>
+            </div>
+            // 失败后可以在同一布局上重试
+            {move || {
+                let can_retry = retry_allowed
+                    && new_game_enabled()
+                    && info.with(|info| matches!(info.status(), GameStatus::GameOver));
+
+                can_retry.then(|| view! {
+                    <div class="btn">
+                        <A
+                            href=""
+                            on:click=move |ev| {
+                                ev.prevent_default();
+                                game_state_write.update(|game_state| game_state.retry());
+                            }
+                        >
+                            "Retry This Board"
+                        </A>
+                    </div>
+                })
+            }}
+            // 结束后可以通过链接重玩这块棋盘并与幽灵比赛 (重新加载页面以使用链接中的种子)
+            {move || {
+                info.with(|info| info.status().is_finished())
+                    .then(|| game_state_read.with(|game_state| game_state.replay_link()))
+                    .flatten()
+                    .map(|link| view! {
+                        <div class="btn">
+                            <a href=link rel="external">
+                                "Replay This Board"
+                            </a>
+                        </div>
+                    })
+            }}
+            <div class="btn">
+                <A href="/">
+                    "Return"
+                </A>
+            </div>
+        </div>
+
+        <Info /> // 显示游戏信息
+
+        <GoLive publisher params rows columns mask=mask.clone() info /> // 直播当前游戏
+
+        {seed.zip(ghost).map(|(seed, ghost)| view! { <GhostRace seed params ghost info /> })}
+
+        {start.filter(|_| bot).map(|(row, column)| view! {
+            <BotRace start=(row * columns + column) as usize info />
+        })}
+
+        <Board rows columns mask size start /> // 渲染扫雷游戏的棋盘(面板)
+    }
+}
+
+// 渲染一块独立的棋盘 (不含按钮和游戏信息), 每块棋盘在自己的作用域中提供游戏状态, 供同一页面上的多局游戏使用
+#[component]
+pub fn GameBoard(game_state: GameState) -> impl IntoView {
+    let (rows, columns) = game_state.dimensions();
+    let mask = game_state.mask().clone();
+    let size = game_state.params().size;
+    let start = game_state.start_cell();
+    let session = game_state.session();
+
+    let (game_state_read, game_state_write) = create_signal(game_state);
+    provide_context(game_state_read);
+    provide_context(game_state_write);
+
+    // 多棋盘模式下其他棋盘失败后结束本局, 延迟到当前更新完成后执行, 避免在更新游戏状态时再次更新
+    if let Some(session) = session {
+        create_effect(move |_| {
+            if session.is_lost() {
+                queue_microtask(move || {
+                    game_state_write.update(|game_state| game_state.end_session());
+                });
+            }
+        });
+    }
+
+    view! { <Board rows columns mask size start /> }
+}
+
 // 显示计时器和当前游戏进度
 #[component]
 fn Info() -> impl IntoView {
@@ -96,42 +295,129 @@ fn Info() -> impl IntoView {
     }
 }
 
-// 渲染游戏棋盘
+// 渲染游戏棋盘, 显式设置网格的行列数, 使遮罩之外的空位保持占位
 #[component]
-fn Board(rows: isize, columns: isize, size: Size) -> impl IntoView {
+fn Board(
+    rows: isize,
+    columns: isize,
+    mask: BoardMask,
+    size: Size,
+    start: Option<(isize, isize)>, // 固定布局的安全起点
+) -> impl IntoView {
+    let masked = mask.count() < (rows * columns) as usize;
+
     view! {
-        <div class={ format!("game-board {size}") }>
-            { (0..rows).map(|row| view!{ <Row row columns /> }).collect_view() }
+        <div
+            class={ format!("game-board {size}") }
+            class:masked=masked
+            style:grid-template-rows=format!("repeat({rows}, minmax(0, 1fr))")
+            style:grid-template-columns=format!("repeat({columns}, minmax(0, 1fr))")
+        >
+            { (0..rows).map(|row| view!{ <Row row columns mask=mask.clone() start /> }).collect_view() }
         </div>
     }
 }
 
-// 渲染游戏棋盘的行
+// 渲染游戏棋盘的行 (跳过遮罩之外的空位)
 #[component]
-fn Row(row: isize, columns: isize) -> impl IntoView {
+fn Row(
+    row: isize,
+    columns: isize,
+    mask: BoardMask,
+    start: Option<(isize, isize)>,
+) -> impl IntoView {
     (0..columns)
-        .map(|column| view! { <Cell row column /> })
+        .filter(|&column| mask.contains(row, column))
+        .map(|column| view! { <Cell row column start=start == Some((row, column)) /> })
         .collect_view()
 }
 
 // 渲染游戏棋盘的单元格
 #[component]
-fn Cell(row: isize, column: isize) -> impl IntoView {
+fn Cell(row: isize, column: isize, start: bool) -> impl IntoView {
     let (cell_state, set_cell_state) =
         create_signal((CellInteraction::Untouched, CellKind::Clear(0)));
     let game_state_write = use_context::<WriteSignal<GameState>>().expect("game state exists");
+    let (memory, peeking) = use_context::<ReadSignal<GameState>>()
+        .expect("game state exists")
+        .with_untracked(|game_state| {
+            (
+                game_state.params().mode() == Mode::Memory,
+                game_state.peeking_signal(),
+            )
+        });
+
+    let ghost = use_context::<Ghost>();
+
+    // 直播时记录单元格的变化
+    if let Some(publisher) = use_context::<Publisher>() {
+        create_effect(move |_| publisher.record(row, column, cell_state()));
+    }
 
     game_state_write.update(|game_state| game_state.register_cell(row, column, set_cell_state));
 
+    // 记忆模式下数字翻开一段时间后隐藏, 棋盘重置时恢复
+    let (faded, set_faded) = create_signal(false);
+    if memory {
+        create_effect(move |_| match cell_state() {
+            (CellInteraction::Cleared, CellKind::Clear(mines)) if mines > 0 => {
+                spawn_local(async move {
+                    TimeoutFuture::new(GameState::MEMORY_FADE_MS).await;
+                    // 等待期间单元格可能已被销毁
+                    if matches!(
+                        cell_state.try_get_untracked(),
+                        Some((CellInteraction::Cleared, _))
+                    ) {
+                        let _ = set_faded.try_set(true);
+                    }
+                });
+            }
+            _ => set_faded(false),
+        });
+    }
+
+    view! {
+        <CellView
+            row
+            column
+            cell_state
+            highlight=start
+            hidden=Signal::derive(move || faded() && !peeking())
+            ghost=Signal::derive(move || ghost.map_or(false, |ghost| ghost.is_revealed(row, column)))
+            on_dig=move |_| game_state_write.update(|game_state| game_state.dig(row, column))
+            on_flag=move |_| game_state_write.update(|game_state| game_state.flag(row, column))
+        />
+    }
+}
+
+// 渲染单元格的外观并处理点击, 不依赖具体的游戏状态, 供其他模式复用
+#[component]
+pub fn CellView(
+    row: isize,                                             // 在网格中的行
+    column: isize,                                          // 在网格中的列
+    cell_state: ReadSignal<(CellInteraction, CellKind)>,    // 单元格状态
+    #[prop(optional)] highlight: bool,                      // 未翻开时高亮显示 (安全起点, 训练题)
+    #[prop(optional, into)] hidden: Signal<bool>,           // 隐藏已翻开的数字 (记忆模式)
+    #[prop(optional, into)] ghost: Signal<bool>,            // 幽灵已翻开该单元格 (幽灵比赛)
+    #[prop(into)] on_dig: Callback<()>,                     // 左键点击回调
+    #[prop(into)] on_flag: Callback<()>,                    // 右键点击回调
+    #[prop(optional, into)] on_hover: Option<Callback<()>>, // 鼠标移入回调 (多人房间)
+) -> impl IntoView {
     view! {
         <div
+            on:mouseenter=move |_| {
+                if let Some(on_hover) = on_hover {
+                    on_hover(());
+                }
+            }
+
             on:mouseup=move |event| {
                 match event.button() {
                     0 => { // 左键点击, 挖掘
-                        game_state_write.update(|game_state| game_state.dig(row, column));
+                        on_dig(());
                     }
                     2 => { // 右键点击, 插旗
-                        game_state_write.update(|game_state| game_state.flag(row, column));
+                        on_flag(());
                     }
                     _ => {}
                 }
@@ -139,16 +425,23 @@ fn Cell(row: isize, column: isize) -> impl IntoView {
 
             class=move || {
                 match cell_state() {
-                    (CellInteraction::Flagged, _) => "cell flagged".into(),
-                    (_, CellKind::Mine) => "cell mine".into(),
+                    (CellInteraction::Flagged(_), _) => "cell flagged".into(),
+                    (CellInteraction::Exploded, _) => "cell mine exploded".into(),
+                    (_, CellKind::Mine(_)) => "cell mine".into(),
                     (_, CellKind::Clear(num)) => format!("cell num-{num}"),
                 }
             }
 
+            class:highlight=move || highlight && matches!(cell_state().0, CellInteraction::Untouched)
+
             class:cleared=move || {
-                matches!(cell_state().0, CellInteraction::Cleared)
+                matches!(cell_state().0, CellInteraction::Cleared | CellInteraction::Exploded)
             }
 
+            class:faded=hidden
+
+            class:ghost=move || ghost() && matches!(cell_state().0, CellInteraction::Untouched)
+
             style:grid-row-start={row+1}
             style:grid-column-start={column+1}
 
@@ -157,23 +450,39 @@ fn Cell(row: isize, column: isize) -> impl IntoView {
 
                 match interaction {
                     CellInteraction::Untouched => {
-                        ""
+                        String::new()
                     }
-                    CellInteraction::Cleared => {
+                    CellInteraction::Cleared | CellInteraction::Exploded => {
                         match cell_kind {
-                            CellKind::Mine => {
-                                BOMB_SVG
+                            CellKind::Mine(mines) => {
+                                with_count(BOMB_SVG, mines)
+                            }
+                            CellKind::Clear(_) if hidden() => {
+                                String::new()
                             }
                             CellKind::Clear(mines) => {
-                                NUM_SVGS[mines as usize]
+                                // 多雷模式下数字可能超过8, 超出图标范围时直接显示数字
+                                NUM_SVGS.get(mines as usize).map_or_else(
+                                    || format!("<span class=\"big-num\">{mines}</span>"),
+                                    |svg| svg.to_string(),
+                                )
                             },
                         }
                     }
-                    CellInteraction::Flagged => {
-                        FLAG_SVG
+                    CellInteraction::Flagged(flags) => {
+                        with_count(FLAG_SVG, flags)
                     }
                 }
             }
         />
     }
 }
+
+// 在图标旁显示数量(仅当数量大于1时, 用于多雷模式)
+fn with_count(svg: &str, count: u32) -> String {
+    if count > 1 {
+        format!("{svg}<span class=\"count\">{count}</span>")
+    } else {
+        svg.to_string()
+    }
+}
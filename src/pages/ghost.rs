@@ -0,0 +1,202 @@
+use leptos::*;
+use leptos_router::*;
+
+use crate::{
+    game_logic::{GameInfo, GameParams, GameStatus},
+    game_settings::{Difficulty, Mode, Size, Username},
+    replay::{Ghost, Replay},
+};
+
+// 获取指定布局和设置下最快的获胜回放, 指定用户名时只查找该用户的回放
+#[server(GetGhostReplay)]
+async fn get_ghost_replay(
+    seed: String,
+    difficulty: Difficulty,
+    size: Size,
+    mode: Mode,
+    username: Option<String>,
+) -> Result<Option<Replay>, ServerFnError> {
+    use crate::pages::daily::is_todays_board;
+
+    // 今天的每日挑战结束之前不提供幽灵, 否则排名尝试可以照着最快的回放进行
+    if seed.parse().is_ok_and(is_todays_board) {
+        return Ok(None);
+    }
+
+    let pool = expect_context::<sqlx::SqlitePool>(); // 获取数据库连接池上下文
+    let (difficulty, size, mode) = (difficulty.to_string(), size.to_string(), mode.to_string());
+
+    // 查询数据库
+    let replay = sqlx::query_scalar!(
+        "
+            SELECT replay
+            FROM replays
+            WHERE seed=?
+                AND difficulty=?
+                AND size=?
+                AND mode=?
+                AND (? IS NULL OR username=?)
+            ORDER BY time_millis
+            LIMIT 1
+        ",
+        seed,
+        difficulty,
+        size,
+        mode,
+        username,
+        username,
+    )
+    .fetch_optional(&pool)
+    .await?;
+
+    Ok(replay.and_then(|replay| replay.parse().ok()))
+}
+
+// 保存一局获胜的回放: 服务器用回放的种子重新进行这一局, 由翻开的单元格生成幽灵
+#[server(PostReplay)]
+pub async fn post_replay(
+    username: String,
+    difficulty: Difficulty,
+    size: Size,
+    mode: Mode,
+    replay: String,
+) -> Result<(), ServerFnError> {
+    use crate::replay::ScoreReplay;
+    use crate::validation::{validate_username, ValidationError};
+    use crate::verify_logic::{check_replay_len, verify_replay, ReplayError, MAX_REPLAY_MOVES};
+
+    // 无效的用户名, 过长或不可能的回放, 不可能的用时返回具体的错误
+    validate_username(&username).map_err(ValidationError::into_server_error)?;
+    let replay = check_replay_len(&replay, MAX_REPLAY_MOVES)
+        .and_then(|()| replay.parse::<ScoreReplay>().map_err(ReplayError::from))
+        .map_err(|error| ServerFnError::ServerError(error.to_string()))?;
+    let run = verify_replay(difficulty, size, mode, &replay)
+        .map_err(|error| ServerFnError::ServerError(error.to_string()))?;
+    // 用时以获胜的一步为准
+    let time_millis = run.ghost.millis();
+    if time_millis <= 0 {
+        return Err(ValidationError::Time.into_server_error());
+    }
+
+    let pool = expect_context::<sqlx::SqlitePool>(); // 获取数据库连接池上下文
+    let seed = replay.seed.to_string();
+    let (difficulty, size, mode) = (difficulty.to_string(), size.to_string(), mode.to_string());
+    let ghost = run.ghost.to_string();
+
+    // 向数据库中插入数据
+    sqlx::query!(
+        "
+            INSERT INTO replays(username, seed, difficulty, size, mode, time_millis, replay)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+        ",
+        username,
+        seed,
+        difficulty,
+        size,
+        mode,
+        time_millis,
+        ghost,
+    )
+    .execute(&pool)
+    .await
+    .map(|_| ())
+    .map_err(Into::into)
+}
+
+// 幽灵比赛: 选择自己的最佳回放或排行榜第一的回放, 并用进度条比较双方的进度
+#[component]
+pub fn GhostRace(
+    seed: u64,
+    params: GameParams, // 布局的难度, 尺寸和模式
+    ghost: Ghost,
+    info: ReadSignal<GameInfo>, // 玩家的游戏信息信号
+) -> impl IntoView {
+    let username = expect_context::<ReadSignal<Username>>();
+    let (message, set_message) = create_signal(String::new());
+
+    let load_ghost = create_action(move |personal: &bool| {
+        let username = personal.then(|| username.get_untracked().name);
+        async move {
+            match get_ghost_replay(
+                seed.to_string(),
+                params.difficulty,
+                params.size,
+                params.mode(),
+                username,
+            )
+            .await
+            {
+                Ok(Some(replay)) => {
+                    ghost.load(replay);
+                    set_message(String::new());
+                }
+                Ok(None) => set_message("No recorded run on this board yet".into()),
+                Err(_) => set_message("Could not load the ghost".into()),
+            }
+        }
+    });
+
+    // 玩家第一次点击时幽灵同时出发, 开始新的一局时幽灵回到起点
+    create_effect(move |was_idle: Option<bool>| {
+        let status = info.with(GameInfo::status);
+        match status {
+            GameStatus::Idle => ghost.rewind(),
+            GameStatus::Started if was_idle == Some(true) => ghost.play(),
+            _ => {}
+        }
+        matches!(status, GameStatus::Idle)
+    });
+
+    // 进度的百分比
+    let percent = move |cleared: usize| {
+        let total = info.with(|info| info.progress().1).max(1);
+        format!("{:.0}%", cleared as f64 * 100.0 / total as f64)
+    };
+
+    view! {
+        <div class="btns">
+            <div class="btn">
+                <A
+                    href=""
+                    on:click=move |ev| {
+                        ev.prevent_default();
+                        load_ghost.dispatch(true);
+                    }
+                >
+                    "Race My Best"
+                </A>
+            </div>
+            <div class="btn">
+                <A
+                    href=""
+                    on:click=move |ev| {
+                        ev.prevent_default();
+                        load_ghost.dispatch(false);
+                    }
+                >
+                    "Race Top Run"
+                </A>
+            </div>
+        </div>
+
+        <div class="daily-note">
+            {message}
+        </div>
+
+        {move || ghost.is_loaded().then(|| view! {
+            <div class="ghost-race">
+                <div class="ghost-race-label">"You"</div>
+                <div class="ghost-race-track">
+                    <div
+                        class="ghost-race-bar player"
+                        style:width=move || percent(info.with(|info| info.progress().0.max(0) as usize))
+                    />
+                </div>
+                <div class="ghost-race-label">"Ghost"</div>
+                <div class="ghost-race-track">
+                    <div class="ghost-race-bar ghost" style:width=move || percent(ghost.cleared()) />
+                </div>
+            </div>
+        })}
+    }
+}
@@ -1,4 +1,4 @@
-use std::{ops::RangeInclusive, rc::Rc};
+use std::rc::Rc;
 
 use gloo_timers::future::TimeoutFuture;
 use leptos::*;
@@ -7,20 +7,16 @@ use wasm_bindgen::JsCast;
 use web_sys::HtmlFormElement;
 
 use crate::{
-    game_settings::{apply_setting, fetch_setting, Difficulty, Size, Username},
+    board_mask::{BoardMask, ParseMaskError},
+    game_settings::{apply_setting, fetch_setting, Difficulty, Mode, Shape, Size, Username},
+    pages::watch::LiveGames,
+    room::ROOM_NAME_MAX,
     utils::to_title,
+    validation::{valid_chars, validate_username, ValidationError, USERNAME_BOUNDS},
 };
 
-const USERNAME_BOUNDS: RangeInclusive<usize> = 3..=10; // 用户名长度范围
 const DICE_SVG: &str = include_str!("../../svgs/dice.svg"); // 骰子SVG图标
 
-// 验证用户名字符是否合法
-fn valid_chars(username: &str) -> bool {
-    username
-        .chars()
-        .all(|c| c.is_ascii_alphabetic() || c == '_')
-}
-
 // 渲染主页
 #[component]
 pub fn HomePage() -> impl IntoView {
@@ -34,13 +30,23 @@ pub fn HomePage() -> impl IntoView {
     let (difficulty, set_difficulty) =
         create_signal(fetch_setting::<Difficulty>("difficulty").unwrap_or_default());
     let (size, set_size) = create_signal(fetch_setting::<Size>("size").unwrap_or_default());
+    let (mode, set_mode) = create_signal(fetch_setting::<Mode>("mode").unwrap_or_default());
+    let (shape, set_shape) = create_signal(fetch_setting::<Shape>("shape").unwrap_or_default());
+    // 自定义形状的遮罩文本, 在输入框中每行显示一行棋盘
+    let mask_text = fetch_setting::<BoardMask>("mask")
+        .map(|mask| mask.to_string().replace('-', "\n"))
+        .unwrap_or_default();
+    let (mask_error, set_mask_error) = create_signal(None::<ParseMaskError>);
     let (form_action, set_form_action) = create_signal("/");
+    let (username_error, set_username_error) = create_signal(ValidationError::UsernameChars);
 
     // 创建HTML元素的引用，用于后续访问DOM元素
     let username_ref = create_node_ref::<html::Input>();
     let error_ref = create_node_ref::<html::Span>();
     let difficulty_ref = create_node_ref::<html::Select>();
     let size_ref = create_node_ref::<html::Select>();
+    let mode_ref = create_node_ref::<html::Select>();
+    let mask_ref = create_node_ref::<html::Textarea>();
 
     let username_error_action = create_action(move |&()| async move {
         let username_input = username_ref.get().expect("noderef assigned");
@@ -75,12 +81,17 @@ pub fn HomePage() -> impl IntoView {
     // 用户名输入事件处理函数
     let on_username_input = move |ev| {
         let new_name = event_target_value(&ev);
-        // 验证新用户名的长度和字符是否合法
-        if new_name.len() <= *USERNAME_BOUNDS.end() && valid_chars(&new_name) {
-            set_username(Username::new(new_name));
-        } else {
+        // 验证新用户名的长度和字符是否合法 (输入过程中允许短于最小长度)
+        if new_name.len() > *USERNAME_BOUNDS.end() {
             set_username(username());
+            set_username_error(ValidationError::UsernameLength);
             username_error_action.dispatch(());
+        } else if !valid_chars(&new_name) {
+            set_username(username());
+            set_username_error(ValidationError::UsernameChars);
+            username_error_action.dispatch(());
+        } else {
+            set_username(Username::new(new_name));
         }
     };
 
@@ -88,14 +99,15 @@ pub fn HomePage() -> impl IntoView {
     let on_settings_submit = move |ev: ev::SubmitEvent| {
         let Username { name, stable } = username();
         // 验证用户名的长度和字符是否合法
-        if USERNAME_BOUNDS.contains(&name.len()) && valid_chars(&name) {
-            if stable {
-                apply_setting("username", &name);
+        match validate_username(&name) {
+            Ok(()) if stable => apply_setting("username", &name),
+            Ok(()) => {}
+            Err(error) => {
+                ev.prevent_default();
+                set_username_error(error);
+                username_error_action.dispatch(());
+                return;
             }
-        } else {
-            ev.prevent_default();
-            username_error_action.dispatch(());
-            return;
         }
 
         // 获取并验证难度选择
@@ -121,6 +133,35 @@ pub fn HomePage() -> impl IntoView {
             ev.prevent_default();
             return;
         }
+
+        // 获取并验证模式选择
+        let mode_select = mode_ref.get().expect("noderef assigned");
+        if let Ok(selected_mode) = mode_select.value().parse() {
+            if mode() != selected_mode {
+                apply_setting("mode", &selected_mode);
+                set_mode(selected_mode);
+            }
+        } else {
+            ev.prevent_default();
+            return;
+        }
+
+        // 保存形状选择, 自定义形状需要先验证遮罩文本
+        apply_setting("shape", &shape());
+        if shape() == Shape::Custom {
+            let mask_input = mask_ref.get().expect("noderef assigned");
+            match mask_input.value().parse::<BoardMask>() {
+                Ok(mask) => {
+                    apply_setting("mask", &mask);
+                    set_mask_error(None);
+                }
+                Err(err) => {
+                    ev.prevent_default();
+                    set_mask_error(Some(err));
+                    return;
+                }
+            }
+        }
         ev.target()
             .unwrap()
             .dyn_into::<HtmlFormElement>()
@@ -137,6 +178,12 @@ pub fn HomePage() -> impl IntoView {
             on:submit=on_settings_submit
             on_form_data=Rc::new(move |form_data| {
                 form_data.delete("username"); //don't need this in the query
+                form_data.delete("mask"); // 遮罩文本保存在设置中
+                // 房间名称只用于开始新游戏或竞速, 留空时进行单人游戏或创建新的竞速房间
+                let room = form_data.get("room").as_string().unwrap_or_default();
+                if room.is_empty() || !matches!(form_action(), "/game" | "/race") {
+                    form_data.delete("room");
+                }
             })
         >
             // 设置面板
@@ -167,7 +214,7 @@ pub fn HomePage() -> impl IntoView {
                             // 用户名错误提示容器
                             <div class="username-error-container">
                                 <span class="username-error" node_ref=error_ref>
-                                    "Name must be 3-10 alphanumeric characters and underscores"
+                                    {move || username_error().to_string()}
                                 </span>
                             </div>
                         </td>
@@ -230,6 +277,122 @@ pub fn HomePage() -> impl IntoView {
                             </select>
                         </td>
                     </tr>
+
+                    // 模式选择行
+                    <tr class="panel-row">
+                        <td class="panel-row-label">
+                            <label for="mode">"Mode:"</label>
+                        </td>
+                        <td>
+                            // 模式选择框
+                            <select name="mode" node_ref=mode_ref>
+                            {
+                                // 生成模式选项
+                                [
+                                    Mode::Classic,
+                                    Mode::MultiMine,
+                                    Mode::Lives,
+                                    Mode::TimeAttack,
+                                    Mode::Endless,
+                                    Mode::Series,
+                                    Mode::Puzzle,
+                                    Mode::Adaptive,
+                                    Mode::Memory,
+                                    Mode::MultiBoard,
+                                    Mode::Flags,
+                                ].iter().map(|curr_mode| {
+                                    view! {
+                                        <option
+                                            value=curr_mode.to_string()
+                                            selected=move || mode() == *curr_mode
+                                        >
+                                        {to_title(&curr_mode)}
+                                        </option>
+                                    }
+                                }).collect_view()
+                            }
+                            </select>
+                        </td>
+                    </tr>
+
+                    // 形状选择行
+                    <tr class="panel-row">
+                        <td class="panel-row-label">
+                            <label for="shape">"Shape:"</label>
+                        </td>
+                        <td>
+                            // 形状选择框
+                            <select
+                                name="shape"
+                                on:change=move |ev| {
+                                    if let Ok(selected_shape) = event_target_value(&ev).parse() {
+                                        set_shape(selected_shape);
+                                    }
+                                }
+                            >
+                            {
+                                // 生成形状选项
+                                [
+                                    Shape::Rectangle,
+                                    Shape::Circle,
+                                    Shape::Heart,
+                                    Shape::Donut,
+                                    Shape::Custom,
+                                ].iter().map(|curr_shape| {
+                                    view! {
+                                        <option
+                                            value=curr_shape.to_string()
+                                            selected=move || shape() == *curr_shape
+                                        >
+                                        {to_title(&curr_shape)}
+                                        </option>
+                                    }
+                                }).collect_view()
+                            }
+                            </select>
+                        </td>
+                    </tr>
+
+                    // 自定义形状行, 仅在选择自定义形状时显示
+                    <tr class="panel-row" class:hidden=move || shape() != Shape::Custom>
+                        <td class="panel-row-label">
+                            <label for="mask">"Mask:"</label>
+                        </td>
+                        <td>
+                            // 遮罩输入框, '#' 表示单元格, '.' 表示空位
+                            <textarea
+                                name="mask"
+                                class="mask-input"
+                                rows="6"
+                                placeholder="..####..\n.######.\n########"
+                                node_ref=mask_ref
+                            >
+                                {mask_text}
+                            </textarea>
+                            <div class="mask-error">
+                                {move || mask_error().map(|err| err.to_string())}
+                            </div>
+                        </td>
+                    </tr>
+
+                    // 房间行, 填写相同房间名称的玩家共同在一块棋盘上游戏
+                    <tr class="panel-row">
+                        <td class="panel-row-label">
+                            <label for="room">"Room:"</label>
+                        </td>
+                        <td>
+                            // 房间名称输入框, 可选
+                            <input
+                                type="text"
+                                name="room"
+                                size="12"
+                                maxlength=ROOM_NAME_MAX
+                                pattern="[A-Za-z0-9_\\-]*"
+                                placeholder="optional"
+                                title="Letters, digits, underscores and hyphens"
+                            />
+                        </td>
+                    </tr>
                 </table>
             </div>
 
@@ -243,6 +406,14 @@ pub fn HomePage() -> impl IntoView {
                         on:click=move |_| set_form_action("/game")
                     />
                 </div>
+                // 验证模式按钮, 只有服务器验证的成绩才能进入排行榜
+                <div class="btn">
+                    <input
+                        type="submit"
+                        value="Ranked"
+                        on:click=move |_| set_form_action("/verified")
+                    />
+                </div>
                 // 查看排行榜按钮
                 <div class="btn">
                     <input
@@ -251,7 +422,50 @@ pub fn HomePage() -> impl IntoView {
                         on:click=move |_| set_form_action("/scores")
                     />
                 </div>
+                // 每日挑战按钮
+                <div class="btn">
+                    <input
+                        type="submit"
+                        value="Daily"
+                        on:click=move |_| set_form_action("/daily")
+                    />
+                </div>
+                // 竞速按钮
+                <div class="btn">
+                    <input
+                        type="submit"
+                        value="Race"
+                        on:click=move |_| set_form_action("/race")
+                    />
+                </div>
+                // 大厅按钮
+                <div class="btn">
+                    <input
+                        type="submit"
+                        value="Lobby"
+                        on:click=move |_| set_form_action("/lobby")
+                    />
+                </div>
+                // 对战电脑按钮
+                <div class="btn">
+                    <input
+                        type="submit"
+                        value="Versus Bot"
+                        on:click=move |_| set_form_action("/versus")
+                    />
+                </div>
+                // 定式训练按钮
+                <div class="btn">
+                    <input
+                        type="submit"
+                        value="Trainer"
+                        on:click=move |_| set_form_action("/trainer")
+                    />
+                </div>
             </div>
         </Form>
+
+        // 正在直播的游戏
+        <LiveGames />
     }
 }
@@ -0,0 +1,360 @@
+use std::fmt::Display;
+
+use gloo_timers::future::TimeoutFuture;
+use leptos::*;
+use leptos_router::*;
+
+use crate::{
+    game_settings::{fetch_setting, Difficulty, Mode, Size},
+    room::{
+        RoomInfo, RoomKind, DEFAULT_ROOM_CAPACITY, ROOM_CAPACITY_BOUNDS, ROOM_MODES, ROOM_NAME_MAX,
+    },
+    utils::to_title,
+};
+
+// 大厅刷新房间列表的间隔(毫秒)
+const LOBBY_REFRESH_MS: u32 = 3_000;
+
+// 在大厅中创建房间, 名称留空时随机生成, 返回加入房间的页面地址
+#[server(CreateRoom)]
+async fn create_room(
+    kind: RoomKind,
+    name: String,
+    capacity: usize,
+    difficulty: Difficulty,
+    size: Size,
+    mode: Mode,
+) -> Result<String, ServerFnError> {
+    use crate::race_server::RaceRoom;
+    use crate::room::{random_room_name, LobbyError};
+    use crate::room_server::{self, Room, Rooms};
+
+    let name = if name.is_empty() {
+        random_room_name(&mut rand::thread_rng())
+    } else {
+        name
+    };
+    let result = match kind {
+        RoomKind::Coop if !ROOM_MODES.contains(&mode) => Err(LobbyError::InvalidMode),
+        RoomKind::Coop => room_server::create_room(
+            &expect_context::<Rooms>(),
+            name.clone(),
+            capacity,
+            Room::new(difficulty, size, mode),
+        ),
+        RoomKind::Race => room_server::create_room(
+            &expect_context::<Rooms<RaceRoom>>(),
+            name.clone(),
+            capacity,
+            RaceRoom::new(difficulty, size),
+        ),
+    };
+    result.map_err(|error| ServerFnError::ServerError(error.to_string()))?;
+
+    let room = RoomInfo {
+        name,
+        kind,
+        host: None,
+        players: 0,
+        capacity,
+        difficulty,
+        size,
+        mode,
+        playing: false,
+    };
+    Ok(room.join_path())
+}
+
+// 获取大厅中的所有房间 (合作房间和竞速房间), 按名称排列
+#[server(GetRooms)]
+async fn get_rooms() -> Result<Vec<RoomInfo>, ServerFnError> {
+    use crate::race_server::RaceRoom;
+    use crate::room_server::{room_list, Rooms};
+
+    let mut rooms = room_list(&expect_context::<Rooms>());
+    rooms.extend(room_list(&expect_context::<Rooms<RaceRoom>>()));
+    rooms.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(rooms)
+}
+
+// 生成下拉框的选项
+pub(crate) fn option_views<T: Display + PartialEq + Copy + 'static>(
+    values: &[T],
+    selected: impl Fn() -> T + Copy + 'static,
+) -> View {
+    values
+        .iter()
+        .map(|&value| {
+            view! {
+                <option value=value.to_string() selected=move || selected() == value>
+                    {to_title(&value)}
+                </option>
+            }
+        })
+        .collect_view()
+}
+
+// 从下拉框的值解析选项, 解析失败时保持原值
+fn on_select<T: std::str::FromStr + 'static>(
+    set_value: WriteSignal<T>,
+) -> impl Fn(ev::Event) + 'static {
+    move |ev| {
+        if let Ok(value) = event_target_value(&ev).parse() {
+            set_value(value);
+        }
+    }
+}
+
+// 渲染大厅: 创建新房间, 查看并加入已有的房间
+#[component]
+pub fn Lobby() -> impl IntoView {
+    let (kind, set_kind) = create_signal(RoomKind::Coop);
+    let (name, set_name) = create_signal(String::new());
+    let (capacity, set_capacity) = create_signal(DEFAULT_ROOM_CAPACITY);
+    let (difficulty, set_difficulty) =
+        create_signal(fetch_setting::<Difficulty>("difficulty").unwrap_or_default());
+    let (size, set_size) = create_signal(fetch_setting::<Size>("size").unwrap_or_default());
+    let (mode, set_mode) = create_signal(Mode::Classic);
+    let (error, set_error) = create_signal(None::<String>);
+
+    let navigate = use_navigate();
+    let create = create_action(move |&()| {
+        let navigate = navigate.clone();
+        let settings = (kind(), name(), capacity(), difficulty(), size(), mode());
+        async move {
+            let (kind, name, capacity, difficulty, size, mode) = settings;
+            match create_room(kind, name, capacity, difficulty, size, mode).await {
+                Ok(path) => navigate(&path, Default::default()),
+                Err(ServerFnError::ServerError(message)) => set_error(Some(message)),
+                Err(_) => set_error(Some("Could not create the room".into())),
+            }
+        }
+    });
+
+    // 定期刷新房间列表 (只在浏览器中), 离开页面后停止
+    let (refresh, set_refresh) = create_signal(0);
+    create_effect(move |_| {
+        spawn_local(async move {
+            loop {
+                TimeoutFuture::new(LOBBY_REFRESH_MS).await;
+                if set_refresh.try_update(|refresh| *refresh += 1).is_none() {
+                    break;
+                }
+            }
+        });
+    });
+    let rooms = create_resource(
+        refresh,
+        |_| async move { get_rooms().await.unwrap_or_default() },
+    );
+
+    view! {
+        <div class="panel">
+            <div class="panel-label">"New Room"</div>
+            <table class="panel-table">
+                <tr class="panel-row">
+                    <td class="panel-row-label">
+                        <label for="kind">"Type:"</label>
+                    </td>
+                    <td>
+                        <select name="kind" on:change=on_select(set_kind)>
+                            {option_views(&[RoomKind::Coop, RoomKind::Race], kind)}
+                        </select>
+                    </td>
+                </tr>
+                <tr class="panel-row">
+                    <td class="panel-row-label">
+                        <label for="name">"Name:"</label>
+                    </td>
+                    <td>
+                        <input
+                            type="text"
+                            name="name"
+                            size="12"
+                            maxlength=ROOM_NAME_MAX
+                            placeholder="random"
+                            prop:value=name
+                            on:input=move |ev| set_name(event_target_value(&ev))
+                        />
+                    </td>
+                </tr>
+                <tr class="panel-row">
+                    <td class="panel-row-label">
+                        <label for="capacity">"Players:"</label>
+                    </td>
+                    <td>
+                        <select name="capacity" on:change=on_select(set_capacity)>
+                            {option_views(&ROOM_CAPACITY_BOUNDS.collect::<Vec<_>>(), capacity)}
+                        </select>
+                    </td>
+                </tr>
+                <tr class="panel-row">
+                    <td class="panel-row-label">
+                        <label for="difficulty">"Difficulty:"</label>
+                    </td>
+                    <td>
+                        <select name="difficulty" on:change=on_select(set_difficulty)>
+                            {option_views(&[Difficulty::Easy, Difficulty::Normal, Difficulty::Hard], difficulty)}
+                        </select>
+                    </td>
+                </tr>
+                <tr class="panel-row">
+                    <td class="panel-row-label">
+                        <label for="size">"Board Size:"</label>
+                    </td>
+                    <td>
+                        <select name="size" on:change=on_select(set_size)>
+                            {option_views(&[Size::Small, Size::Medium, Size::Large], size)}
+                        </select>
+                    </td>
+                </tr>
+                // 竞速房间只使用经典模式
+                <tr class="panel-row" class:hidden=move || kind() != RoomKind::Coop>
+                    <td class="panel-row-label">
+                        <label for="mode">"Mode:"</label>
+                    </td>
+                    <td>
+                        <select name="mode" on:change=on_select(set_mode)>
+                            {option_views(&ROOM_MODES, mode)}
+                        </select>
+                    </td>
+                </tr>
+            </table>
+            <div class="mask-error">
+                {error}
+            </div>
+        </div>
+
+        <div class="btns">
+            <div class="btn">
+                <A
+                    href=""
+                    on:click=move |ev| {
+                        ev.prevent_default();
+                        create.dispatch(());
+                    }
+                >
+                    "Create Room"
+                </A>
+            </div>
+            <div class="btn">
+                <A href="/">
+                    "Return"
+                </A>
+            </div>
+        </div>
+
+        <table class="scoreboard">
+            <tr class="header">
+                <th class="name">
+                    "Room"
+                </th>
+                <th class="extra">
+                    "Host"
+                </th>
+                <th class="extra">
+                    "Players"
+                </th>
+                <th class="extra">
+                    "Settings"
+                </th>
+                <th class="time">
+                    "Status"
+                </th>
+            </tr>
+            <Transition fallback=|| ()>
+                {move || rooms().unwrap_or_default().into_iter().zip(1..).map(|(room, n)| view! {
+                    <RoomRow room n />
+                }).collect_view()}
+            </Transition>
+        </table>
+    }
+}
+
+// 大厅中的一个房间, 未满时可以加入
+#[component]
+fn RoomRow(room: RoomInfo, n: usize) -> impl IntoView {
+    let settings = match room.kind {
+        RoomKind::Coop => format!(
+            "{} · {} · {}",
+            to_title(&room.difficulty),
+            to_title(&room.size),
+            to_title(&room.mode)
+        ),
+        RoomKind::Race => format!(
+            "Race · {} · {}",
+            to_title(&room.difficulty),
+            to_title(&room.size)
+        ),
+    };
+    let status = match (room.is_full(), room.playing) {
+        (true, _) => "Full",
+        (false, true) => "Playing",
+        (false, false) => "Open",
+    };
+    let name = if room.is_full() {
+        view! { <span>{room.name.clone()}</span> }.into_view()
+    } else {
+        view! { <A class="watch-link" href=room.join_path()>{room.name.clone()}</A> }.into_view()
+    };
+
+    view! {
+        <tr class={ if n % 2 == 0 { "even" } else { "odd" }}>
+            <td class="name">
+                {name}
+            </td>
+            <td class="extra">
+                {room.host.unwrap_or_default()}
+            </td>
+            <td class="extra">
+                {format!("{}/{}", room.players, room.capacity)}
+            </td>
+            <td class="extra">
+                {settings}
+            </td>
+            <td class="time">
+                {status}
+            </td>
+        </tr>
+    }
+}
+
+// 房主修改房间的棋盘参数, 合作房间还可以修改模式
+#[component]
+pub(crate) fn HostSettings(
+    difficulty: Difficulty,
+    size: Size,
+    #[prop(optional)] mode: Option<Mode>, // 当前模式 (仅合作房间)
+    #[prop(into)] on_apply: Callback<(Difficulty, Size, Mode)>,
+) -> impl IntoView {
+    let (difficulty, set_difficulty) = create_signal(difficulty);
+    let (size, set_size) = create_signal(size);
+    let (selected_mode, set_mode) = create_signal(mode.unwrap_or_default());
+
+    view! {
+        <div class="btns">
+            <select class="inline-select" on:change=on_select(set_difficulty)>
+                {option_views(&[Difficulty::Easy, Difficulty::Normal, Difficulty::Hard], difficulty)}
+            </select>
+            <select class="inline-select" on:change=on_select(set_size)>
+                {option_views(&[Size::Small, Size::Medium, Size::Large], size)}
+            </select>
+            {mode.map(|_| view! {
+                <select class="inline-select" on:change=on_select(set_mode)>
+                    {option_views(&ROOM_MODES, selected_mode)}
+                </select>
+            })}
+            <div class="btn">
+                <A
+                    href=""
+                    on:click=move |ev| {
+                        ev.prevent_default();
+                        on_apply((difficulty(), size(), selected_mode()));
+                    }
+                >
+                    "Apply Settings"
+                </A>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,74 @@
+use leptos::*;
+use leptos_router::*;
+
+use crate::game_settings::{apply_setting, fetch_setting, Difficulty};
+use crate::multi_board_logic::{Session, MAX_BOARDS, MIN_BOARDS};
+use crate::pages::game::GameBoard;
+
+// 渲染多棋盘模式, 可以选择同时进行的棋盘数量
+#[component]
+pub fn MultiBoard(difficulty: Difficulty) -> impl IntoView {
+    let (boards, set_boards) = create_signal(
+        fetch_setting::<usize>("boards")
+            .unwrap_or(MIN_BOARDS)
+            .clamp(MIN_BOARDS, MAX_BOARDS),
+    );
+    let (round, set_round) = create_signal(0); // 每开始新的一局加一, 重新创建所有棋盘
+
+    view! {
+        <div class="btns">
+            <div class="btn">
+                <A
+                    href=""
+                    on:click=move |ev| {
+                        ev.prevent_default();
+                        set_round.update(|round| *round += 1);
+                    }
+                >
+                    "New Game"
+                </A>
+            </div>
+            {(MIN_BOARDS..=MAX_BOARDS).map(|count| view! {
+                <div class=move || format!("btn {}", if boards() == count { "disabled" } else { "" })>
+                    <A
+                        href=""
+                        on:click=move |ev| {
+                            ev.prevent_default();
+                            apply_setting("boards", &count);
+                            set_boards(count);
+                            set_round.update(|round| *round += 1);
+                        }
+                    >
+                        {format!("{count} Boards")}
+                    </A>
+                </div>
+            }).collect_view()}
+            <div class="btn">
+                <A href="/">
+                    "Return"
+                </A>
+            </div>
+        </div>
+
+        {move || {
+            round.track();
+            view! { <MultiBoardSession boards=boards.get_untracked() difficulty /> }
+        }}
+    }
+}
+
+// 渲染一次多棋盘会话: 合并的游戏信息和并排的棋盘
+#[component]
+fn MultiBoardSession(boards: usize, difficulty: Difficulty) -> impl IntoView {
+    let (session, game_states) = Session::new(boards, difficulty);
+
+    view! {
+        <h2 class="info">
+            { move || session.to_view() }
+        </h2>
+
+        <div class="multi-board">
+            {game_states.into_iter().map(|game_state| view! { <GameBoard game_state /> }).collect_view()}
+        </div>
+    }
+}
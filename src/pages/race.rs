@@ -0,0 +1,342 @@
+use gloo_timers::future::TimeoutFuture;
+use leptos::*;
+use leptos_router::*;
+
+use crate::{
+    game_logic::{GameInfo, GameParams, GameState, GameStatus},
+    game_settings::{Difficulty, Mode, Size, Username},
+    pages::{game::GameBoard, lobby::HostSettings, room::Connection},
+    race::{RaceClientMessage, RacePhase, RaceResult, RaceServerMessage, Racer, MIN_RACERS},
+    room::{random_room_name, valid_room_name},
+    utils::to_title,
+};
+
+// 将服务器记录的毫秒数转换为秒
+fn to_seconds(millis: i64) -> String {
+    format!("{:.2}s", millis as f64 / 1000.0)
+}
+
+// 渲染竞速页面, 没有房间名称时创建新房间
+#[component]
+pub fn Race() -> impl IntoView {
+    window_event_listener(ev::contextmenu, |ev| ev.prevent_default()); // 禁用右键菜单
+
+    let query = use_query_map();
+    let params = move || {
+        query.with(|query| {
+            (
+                query
+                    .get("room")
+                    .cloned()
+                    .filter(|room| valid_room_name(room)),
+                query
+                    .get("difficulty")
+                    .and_then(|difficulty| difficulty.parse::<Difficulty>().ok())
+                    .unwrap_or_default(),
+                query
+                    .get("size")
+                    .and_then(|size| size.parse::<Size>().ok())
+                    .unwrap_or_default(),
+            )
+        })
+    };
+
+    // 随机的房间名称只在浏览器中生成, 并替换当前地址, 方便分享
+    let navigate = use_navigate();
+    create_effect(move |_| {
+        if let (None, difficulty, size) = params() {
+            navigate(
+                &format!(
+                    "/race?room={}&difficulty={difficulty}&size={size}",
+                    random_room_name(&mut rand::thread_rng())
+                ),
+                NavigateOptions {
+                    replace: true,
+                    ..Default::default()
+                },
+            );
+        }
+    });
+
+    move || {
+        let (room, difficulty, size) = params();
+        room.map(|room| view! { <RaceRoom room difficulty size /> })
+    }
+}
+
+// 渲染一个竞速房间: 邀请链接, 倒计时, 各玩家的进度和比赛结果
+#[component]
+fn RaceRoom(room: String, difficulty: Difficulty, size: Size) -> impl IntoView {
+    let username = expect_context::<ReadSignal<Username>>();
+    let (player, set_player) = create_signal(None::<u32>); // 自己的玩家编号
+    let (phase, set_phase) = create_signal(RacePhase::Lobby);
+    let (winner, set_winner) = create_signal(None::<u32>);
+    let (racers, set_racers) = create_signal(Vec::<Racer>::new());
+    let (countdown, set_countdown) = create_signal(None::<u32>); // 倒计时剩余秒数
+    let (game, set_game) = create_signal(None::<(u64, GameParams)>); // 当前比赛的种子和参数
+    let (host, set_host) = create_signal(None::<u32>); // 房主的编号
+    let (settings, set_settings) = create_signal((difficulty, size)); // 房间当前的棋盘参数
+    let (full, set_full) = create_signal(false);
+    let (connected, set_connected) = create_signal(true);
+    let (link, set_link) = create_signal(String::new()); // 邀请链接
+
+    // 邀请链接就是当前页面的地址
+    create_effect(move |_| set_link(window().location().href().unwrap_or_default()));
+
+    // 页面销毁后信号不再可用, 因此都使用 try_set
+    let on_message = move |message: RaceServerMessage| match message {
+        RaceServerMessage::Welcome { player } => {
+            let _ = set_player.try_set(Some(player));
+        }
+        RaceServerMessage::Countdown {
+            seed,
+            difficulty,
+            size,
+            seconds,
+        } => {
+            let params = GameParams {
+                difficulty,
+                size,
+                mode: None,
+                shape: None,
+            };
+            let _ = set_game.try_set(Some((seed, params)));
+            let _ = set_countdown.try_set(Some(seconds));
+
+            spawn_local(async move {
+                for remaining in (0..seconds).rev() {
+                    TimeoutFuture::new(1_000).await;
+                    let _ =
+                        set_countdown.try_set(Some(remaining).filter(|&remaining| remaining > 0));
+                }
+            });
+        }
+        RaceServerMessage::Standings {
+            phase,
+            winner,
+            racers,
+            host,
+            difficulty,
+            size,
+        } => {
+            let _ = set_phase.try_set(phase);
+            let _ = set_winner.try_set(winner);
+            let _ = set_racers.try_set(racers);
+            let _ = set_host.try_set(host);
+            let _ = set_settings.try_set((difficulty, size));
+        }
+        RaceServerMessage::Full => {
+            let _ = set_full.try_set(true);
+        }
+    };
+
+    // 棋盘参数只在创建房间时使用
+    let connection = Connection::<RaceClientMessage>::open(
+        format!(
+            "/ws/race/{room}?username={}&difficulty={difficulty}&size={size}",
+            username.get_untracked().name
+        ),
+        on_message,
+        move || {
+            let _ = set_connected.try_set(false);
+        },
+    );
+
+    let can_start =
+        move || phase() != RacePhase::Racing && racers.with(|racers| racers.len() >= MIN_RACERS);
+
+    // 比赛的状态或结果
+    let headline = move || {
+        let name_of = |id: u32| {
+            racers.with(|racers| {
+                racers
+                    .iter()
+                    .find(|racer| racer.id == id)
+                    .map(|racer| racer.name.clone())
+                    .unwrap_or_default()
+            })
+        };
+
+        match (phase(), winner()) {
+            (RacePhase::Lobby, _) => format!(
+                "Waiting for racers - {} joined, {MIN_RACERS} needed",
+                racers.with(Vec::len)
+            ),
+            (RacePhase::Racing, _) => match countdown() {
+                Some(seconds) => format!("Starting in {seconds}..."),
+                None => "Race on!".to_string(),
+            },
+            (RacePhase::Finished, Some(id)) if Some(id) == player() => {
+                "You won the race! 🥳".to_string()
+            }
+            (RacePhase::Finished, Some(id)) => format!("{} won the race", name_of(id)),
+            (RacePhase::Finished, None) => "Nobody cleared the board 😭".to_string(),
+        }
+    };
+
+    view! {
+        <div class="btns">
+            <div class=move || format!("btn {}", if can_start() { "" } else { "disabled" })>
+                <A
+                    href=""
+                    on:click=move |ev| {
+                        ev.prevent_default();
+                        if can_start() {
+                            connection.send(RaceClientMessage::Start);
+                        }
+                    }
+                >
+                    "Start Race"
+                </A>
+            </div>
+            <div class="btn">
+                <A href="/">
+                    "Return"
+                </A>
+            </div>
+        </div>
+
+        <h2 class="info">
+            {headline}
+        </h2>
+
+        <div class="daily-note">
+            "Invite link - "
+            <a class="race-link" href=link>{link}</a>
+            <br />
+            {move || {
+                let (difficulty, size) = settings();
+                format!("{} · {}", to_title(&difficulty), to_title(&size))
+            }}
+            <br />
+            {move || match (full(), connected()) {
+                (true, _) => Some("This race is full"),
+                (false, false) => Some("Disconnected from the race"),
+                (false, true) => None,
+            }}
+        </div>
+
+        // 房主在比赛开始前或结束后可以修改棋盘参数
+        {move || {
+            let is_host = player().is_some() && player() == host();
+            (is_host && phase() != RacePhase::Racing).then(|| {
+                let (difficulty, size) = settings();
+                view! {
+                    <HostSettings
+                        difficulty
+                        size
+                        on_apply=move |(difficulty, size, _): (Difficulty, Size, Mode)| {
+                            connection.send(RaceClientMessage::Configure { difficulty, size })
+                        }
+                    />
+                }
+            })
+        }}
+
+        <RaceStandings racers player host />
+
+        {move || game().map(|(seed, params)| view! {
+            <RaceBoard seed params connection countdown />
+        })}
+    }
+}
+
+// 玩家的名称, 标出自己和房主
+fn racer_name(racer: &Racer, player: Option<u32>, host: Option<u32>) -> String {
+    let mut name = racer.name.clone();
+    if Some(racer.id) == player {
+        name.push_str(" (you)");
+    }
+    if Some(racer.id) == host {
+        name.push_str(" (host)");
+    }
+    name
+}
+
+// 渲染自己的棋盘, 倒计时结束前不能操作, 进度和结果实时发送给服务器
+#[component]
+fn RaceBoard(
+    seed: u64,
+    params: GameParams,
+    connection: Connection<RaceClientMessage>,
+    countdown: ReadSignal<Option<u32>>,
+) -> impl IntoView {
+    let game_state = GameState::race(params, seed);
+    let info = game_state.info_signal();
+
+    // 进度变化时发送给服务器, 游戏结束时发送结果
+    create_effect(move |sent: Option<(isize, bool)>| {
+        let (status, (cleared, clear_total)) = info.with(|info| (info.status(), info.progress()));
+        let current = (cleared, status.is_finished());
+
+        if sent != Some(current) {
+            if let Some(replay) = info.with(|info| info.race_replay().cloned()) {
+                connection.send(RaceClientMessage::Finished { replay });
+            } else if status == GameStatus::Started {
+                connection.send(RaceClientMessage::Progress {
+                    cleared,
+                    clear_total,
+                });
+            }
+        }
+        current
+    });
+
+    view! {
+        <h2 class="info">
+            {move || countdown().is_none().then(|| info.with(GameInfo::to_view))}
+        </h2>
+
+        <div class="race-board" class:waiting=move || countdown().is_some()>
+            <GameBoard game_state />
+        </div>
+    }
+}
+
+// 显示房间中各玩家的进度, 比赛结束后按服务器判定的名次排列
+#[component]
+fn RaceStandings(
+    racers: ReadSignal<Vec<Racer>>,
+    player: ReadSignal<Option<u32>>,
+    host: ReadSignal<Option<u32>>,
+) -> impl IntoView {
+    view! {
+        <table class="scoreboard">
+            <tr class="header">
+                <th class="name">
+                    "Racer"
+                </th>
+                <th class="extra">
+                    "Progress"
+                </th>
+                <th class="time">
+                    "Result"
+                </th>
+            </tr>
+            {move || racers().into_iter().zip(1..).map(|(racer, n)| {
+                let progress = (racer.clear_total > 0)
+                    .then(|| format!("{:.0}%", racer.cleared as f64 * 100.0 / racer.clear_total as f64));
+                let result = match racer.result {
+                    Some(RaceResult::Cleared { millis }) => to_seconds(millis),
+                    Some(RaceResult::Exploded { millis }) => format!("💥 {}", to_seconds(millis)),
+                    None if racer.racing => "Racing".to_string(),
+                    None => "Waiting".to_string(),
+                };
+
+                view! {
+                    <tr class={ if n % 2 == 0 { "even" } else { "odd" }}>
+                        <td class="name">
+                            {racer_name(&racer, player(), host())}
+                        </td>
+                        <td class="extra">
+                            {progress}
+                        </td>
+                        <td class="time">
+                            {result}
+                        </td>
+                    </tr>
+                }
+            }).collect_view()}
+        </table>
+    }
+}
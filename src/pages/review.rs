@@ -0,0 +1,282 @@
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    review_logic::Suspicion,
+    utils::{to_time, to_title},
+};
+
+// 等待审核的成绩
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FlaggedScore {
+    pub id: i64,
+    pub leaderboard: String, // 成绩所在的排行榜: scores, daily 或 series
+    pub username: String,
+    pub time_in_seconds: i64,
+    pub difficulty: String, // 难度 (每日挑战为日期)
+    pub size: String,
+    pub mode: String,
+    pub suspicions: String, // 以','分隔的可疑原因
+}
+
+// 检查管理员令牌 (与环境变量 ADMIN_TOKEN 比较, 未设置时拒绝所有请求)
+#[cfg(feature = "ssr")]
+fn check_admin(token: &str) -> Result<(), ServerFnError> {
+    let admin_token = dotenvy::var("ADMIN_TOKEN")
+        .map_err(|_| ServerFnError::ServerError("ADMIN_TOKEN is not set".into()))?;
+    if admin_token.is_empty() || token != admin_token {
+        return Err(ServerFnError::ServerError("Not authorized".into()));
+    }
+    Ok(())
+}
+
+// 获取审核队列 (普通成绩, 每日挑战和连续挑战成绩), 每个排行榜中最早提交的成绩排在前面
+#[server(GetReviewQueue)]
+async fn get_review_queue(token: String) -> Result<Vec<FlaggedScore>, ServerFnError> {
+    check_admin(&token)?;
+    let pool = expect_context::<sqlx::SqlitePool>(); // 获取数据库连接池上下文
+
+    // 查询数据库
+    sqlx::query_as!(
+        FlaggedScore,
+        r#"
+            SELECT id AS "id!: i64", 'scores' AS "leaderboard!: String", username, time_in_seconds,
+                difficulty, size, mode, suspicions
+            FROM scores
+            WHERE approved=0
+            UNION ALL
+            SELECT id, 'daily', username, time_in_seconds, date, '', 'daily', suspicions
+            FROM daily_scores
+            WHERE approved=0
+                AND won
+            UNION ALL
+            SELECT id, 'series', username, time_in_seconds, difficulty, size, 'series', suspicions
+            FROM series_scores
+            WHERE approved=0
+            ORDER BY 2, 1
+        "#,
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(Into::into)
+}
+
+// 审核一条成绩: 批准后显示在排行榜上, 拒绝则删除
+// 被拒绝的每日挑战尝试只记为失败, 保留记录以免玩家当天再次尝试
+#[server(ReviewScore)]
+async fn review_score(
+    token: String,
+    leaderboard: String,
+    id: i64,
+    approve: bool,
+) -> Result<(), ServerFnError> {
+    check_admin(&token)?;
+    let pool = expect_context::<sqlx::SqlitePool>(); // 获取数据库连接池上下文
+
+    match (leaderboard.as_str(), approve) {
+        ("scores", true) => {
+            sqlx::query!("UPDATE scores SET approved=1 WHERE id=?", id)
+                .execute(&pool)
+                .await?
+        }
+        ("scores", false) => {
+            sqlx::query!("DELETE FROM scores WHERE id=? AND approved=0", id)
+                .execute(&pool)
+                .await?
+        }
+        ("daily", true) => {
+            sqlx::query!("UPDATE daily_scores SET approved=1 WHERE id=?", id)
+                .execute(&pool)
+                .await?
+        }
+        ("daily", false) => {
+            sqlx::query!(
+                "UPDATE daily_scores SET won=0 WHERE id=? AND approved=0",
+                id
+            )
+            .execute(&pool)
+            .await?
+        }
+        ("series", true) => {
+            sqlx::query!("UPDATE series_scores SET approved=1 WHERE id=?", id)
+                .execute(&pool)
+                .await?
+        }
+        ("series", false) => {
+            sqlx::query!("DELETE FROM series_scores WHERE id=? AND approved=0", id)
+                .execute(&pool)
+                .await?
+        }
+        _ => return Err(ServerFnError::ServerError("Unknown leaderboard".into())),
+    };
+    Ok(())
+}
+
+// 渲染审核页面: 输入管理员令牌后显示被标记为可疑的成绩
+#[component]
+pub fn Review() -> impl IntoView {
+    let (token, set_token) = create_signal(String::new());
+    let (error, set_error) = create_signal(None::<String>);
+
+    let review = create_action(move |(leaderboard, id, approve): &(String, i64, bool)| {
+        let (leaderboard, id, approve) = (leaderboard.clone(), *id, *approve);
+        async move {
+            if let Err(ServerFnError::ServerError(message)) =
+                review_score(token.get_untracked(), leaderboard, id, approve).await
+            {
+                set_error(Some(message));
+            }
+        }
+    });
+    let load = create_action(move |&()| async move { token.get_untracked() });
+    // 载入令牌或审核一条成绩后重新获取队列
+    let queue = create_resource(
+        move || (load.value()(), review.version()()),
+        move |(token, _)| async move {
+            let Some(token) = token else {
+                return Vec::new();
+            };
+            match get_review_queue(token).await {
+                Ok(queue) => {
+                    set_error(None);
+                    queue
+                }
+                Err(ServerFnError::ServerError(message)) => {
+                    set_error(Some(message));
+                    Vec::new()
+                }
+                Err(_) => {
+                    set_error(Some("Could not load the review queue".into()));
+                    Vec::new()
+                }
+            }
+        },
+    );
+
+    view! {
+        <div class="panel">
+            <div class="panel-label">"Review Queue"</div>
+            <table class="panel-table">
+                <tr class="panel-row">
+                    <td class="panel-row-label">
+                        <label for="token">"Admin Token:"</label>
+                    </td>
+                    <td>
+                        <input
+                            type="password"
+                            name="token"
+                            size="16"
+                            prop:value=token
+                            on:input=move |ev| set_token(event_target_value(&ev))
+                        />
+                    </td>
+                </tr>
+            </table>
+            <div class="mask-error">
+                {error}
+            </div>
+        </div>
+
+        <div class="btns">
+            <div class="btn">
+                <A
+                    href=""
+                    on:click=move |ev| {
+                        ev.prevent_default();
+                        load.dispatch(());
+                    }
+                >
+                    "Load"
+                </A>
+            </div>
+            <div class="btn">
+                <A href="/">
+                    "Return"
+                </A>
+            </div>
+        </div>
+
+        <table class="scoreboard">
+            <tr class="header">
+                <th class="name">
+                    "Name"
+                </th>
+                <th class="extra">
+                    "Settings"
+                </th>
+                <th class="time">
+                    "Time"
+                </th>
+                <th class="extra">
+                    "Reasons"
+                </th>
+                <th class="extra">
+                    "Review"
+                </th>
+            </tr>
+            <Transition fallback=|| ()>
+                {move || queue().unwrap_or_default().into_iter().zip(1..).map(|(score, n)| {
+                    let (leaderboard, id) = (score.leaderboard.clone(), score.id);
+                    view! {
+                        <FlaggedRow
+                            score
+                            n
+                            on_review=move |approve| review.dispatch((leaderboard.clone(), id, approve))
+                        />
+                    }
+                }).collect_view()}
+            </Transition>
+        </table>
+    }
+}
+
+// 审核队列中的一条成绩, 显示可疑原因以及批准和拒绝按钮
+#[component]
+fn FlaggedRow(
+    score: FlaggedScore,
+    n: usize,
+    #[prop(into)] on_review: Callback<bool>, // 审核回调, 参数为是否批准
+) -> impl IntoView {
+    let settings = match score.leaderboard.as_str() {
+        "daily" => format!("Daily · {}", score.difficulty),
+        _ => format!(
+            "{} · {} · {}",
+            to_title(&score.difficulty),
+            to_title(&score.size),
+            to_title(&score.mode)
+        ),
+    };
+    let reasons = score
+        .suspicions
+        .split(',')
+        .filter_map(|suspicion| suspicion.parse::<Suspicion>().ok())
+        .map(|suspicion| suspicion.description())
+        .collect::<Vec<_>>()
+        .join("; ");
+
+    view! {
+        <tr class={ if n % 2 == 0 { "even" } else { "odd" }}>
+            <td class="name">
+                {score.username}
+            </td>
+            <td class="extra">
+                {settings}
+            </td>
+            <td class="time">
+                {to_time(score.time_in_seconds)}
+            </td>
+            <td class="extra">
+                {reasons}
+            </td>
+            <td class="extra">
+                <button class="review-btn" on:click=move |_| on_review(true)>
+                    "Approve"
+                </button>
+                <button class="review-btn" on:click=move |_| on_review(false)>
+                    "Reject"
+                </button>
+            </td>
+        </tr>
+    }
+}
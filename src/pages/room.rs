@@ -0,0 +1,391 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use futures::{channel::mpsc, SinkExt, StreamExt};
+use gloo_net::websocket::{futures::WebSocket, Message};
+use leptos::*;
+use leptos_router::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    flags_logic::FlagsScore,
+    game_logic::{CellInteraction, CellKind, GameStatus},
+    game_settings::{Difficulty, Mode, Size, Username},
+    pages::{game::CellView, lobby::HostSettings},
+    room::{BoardSnapshot, CellUpdate, ClientMessage, Player, ServerMessage},
+};
+
+// 单元格的写入信号, 按索引 (行 * 列数 + 列) 排列
+pub(crate) type CellSignals = Vec<Option<WriteSignal<(CellInteraction, CellKind)>>>;
+
+// 与服务器的 WebSocket 连接, 合作房间和竞速房间共用, 离开页面时自动关闭
+pub(crate) struct Connection<C: 'static> {
+    sender: StoredValue<Option<mpsc::UnboundedSender<C>>>,
+}
+
+impl<C> Clone for Connection<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for Connection<C> {}
+
+impl<C: Serialize + 'static> Connection<C> {
+    // 打开指定路径 (包含查询参数) 的连接, 收到的消息交给 on_message, 连接关闭后调用 on_close
+    pub(crate) fn open<S: DeserializeOwned + 'static>(
+        path: String,
+        on_message: impl Fn(S) + 'static,
+        on_close: impl FnOnce() + 'static,
+    ) -> Self {
+        let connection = Self {
+            sender: store_value(None),
+        };
+        let mut handlers = Some((on_message, on_close));
+
+        // WebSocket 只在浏览器中可用, 连接后分别处理发送和接收
+        create_effect(move |_| {
+            let Some((on_message, on_close)) = handlers.take() else {
+                return;
+            };
+            let location = window().location();
+            let protocol = match location.protocol().as_deref() {
+                Ok("https:") => "wss",
+                _ => "ws",
+            };
+            let host = location.host().unwrap_or_default();
+
+            let Ok(socket) = WebSocket::open(&format!("{protocol}://{host}{path}")) else {
+                on_close();
+                return;
+            };
+            let (mut write, mut read) = socket.split();
+            let (message_sender, mut messages) = mpsc::unbounded::<C>();
+            connection.sender.set_value(Some(message_sender));
+
+            spawn_local(async move {
+                while let Some(message) = messages.next().await {
+                    let text = serde_json::to_string(&message).expect("message serializes");
+                    if write.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                let _ = write.close().await;
+            });
+
+            spawn_local(async move {
+                while let Some(Ok(Message::Text(text))) = read.next().await {
+                    if let Ok(message) = serde_json::from_str::<S>(&text) {
+                        on_message(message);
+                    }
+                }
+                on_close();
+            });
+        });
+
+        // 离开页面时关闭发送通道, 发送任务随之关闭连接
+        on_cleanup(move || connection.sender.set_value(None));
+
+        connection
+    }
+
+    // 发送消息给服务器 (尚未连接时忽略)
+    pub(crate) fn send(&self, message: C) {
+        self.sender.with_value(|sender| {
+            if let Some(sender) = sender {
+                let _ = sender.unbounded_send(message);
+            }
+        });
+    }
+}
+
+// 将单元格的变化应用到对应的信号上
+pub(crate) fn apply_cells(cells: &CellSignals, updates: &[CellUpdate]) {
+    for update in updates {
+        if let Some(Some(set_cell_state)) = cells.get(update.index) {
+            let _ = set_cell_state.try_set((update.interaction, update.kind));
+        }
+    }
+}
+
+// 渲染多人房间: 所有玩家在服务器上的同一块棋盘上游戏, 操作通过 WebSocket 发送
+// 夺旗模式下前两位玩家轮流挖掘
+#[component]
+pub fn Room(room: String, difficulty: Difficulty, size: Size, mode: Mode) -> impl IntoView {
+    let username = expect_context::<ReadSignal<Username>>();
+    let (player, set_player) = create_signal(None::<u32>); // 自己的玩家编号
+    let (players, set_players) = create_signal(Vec::<Player>::new());
+    let (board, set_board) = create_signal(None::<BoardSnapshot>); // 最近一次收到的完整棋盘
+    let (status, set_status) = create_signal(GameStatus::Idle);
+    let (cleared, set_cleared) = create_signal(0);
+    let (flags, set_flags) = create_signal(None::<FlagsScore>); // 双方的比分 (仅夺旗模式)
+    let (cursors, set_cursors) = create_signal(HashMap::<u32, (isize, isize)>::new()); // 其他玩家的鼠标位置
+    let (host, set_host) = create_signal(None::<u32>); // 房主的编号
+    let (full, set_full) = create_signal(false);
+    let (connected, set_connected) = create_signal(true);
+    let cells = store_value(CellSignals::new());
+
+    // 页面销毁后信号不再可用, 因此都使用 try_set
+    let on_message = move |message: ServerMessage| match message {
+        ServerMessage::Welcome { player } => {
+            let _ = set_player.try_set(Some(player));
+        }
+        ServerMessage::Players(list) => {
+            let _ = set_cursors.try_update(|cursors| {
+                cursors.retain(|id, _| list.iter().any(|player| player.id == *id))
+            });
+            let _ = set_players.try_set(list);
+        }
+        ServerMessage::Host(id) => {
+            let _ = set_host.try_set(Some(id));
+        }
+        ServerMessage::Full => {
+            let _ = set_full.try_set(true);
+        }
+        ServerMessage::Board(snapshot) => {
+            let _ = set_status.try_set(snapshot.status);
+            let _ = set_cleared.try_set(snapshot.cleared);
+            let _ = set_flags.try_set(snapshot.flags);
+            let _ = set_board.try_set(Some(snapshot));
+        }
+        ServerMessage::Cells {
+            cells: updates,
+            status,
+            cleared,
+            flags,
+        } => {
+            cells.try_with_value(|cells| apply_cells(cells, &updates));
+            let _ = set_status.try_set(status);
+            let _ = set_cleared.try_set(cleared);
+            let _ = set_flags.try_set(flags);
+        }
+        // 不显示自己的鼠标位置
+        ServerMessage::Cursor {
+            player: id,
+            row,
+            column,
+        } => {
+            if player.try_get_untracked() != Some(Some(id)) {
+                let _ = set_cursors.try_update(|cursors| {
+                    cursors.insert(id, (row, column));
+                });
+            }
+        }
+    };
+
+    // 棋盘参数只在创建房间时使用
+    let connection = Connection::<ClientMessage>::open(
+        format!(
+            "/ws/{room}?username={}&difficulty={difficulty}&size={size}&mode={mode}",
+            username.get_untracked().name
+        ),
+        on_message,
+        move || {
+            let _ = set_connected.try_set(false);
+        },
+    );
+    // 发送操作给服务器, 棋盘只在收到服务器的更新后改变
+    let send = move |message: ClientMessage| connection.send(message);
+
+    // 夺旗模式下座位上的玩家名称 (按加入的顺序), 座位空缺时显示等待
+    let seat_name = move |seat: usize| {
+        players.with(|players| match players.get(seat) {
+            Some(other) if Some(other.id) == player() => "You".to_string(),
+            Some(other) => other.name.clone(),
+            None => "Waiting".to_string(),
+        })
+    };
+
+    // 游戏信息: 进度或结果, 夺旗模式显示轮到的玩家和比分
+    let info = move || {
+        let clear_total = board.with(|board| board.as_ref().map_or(0, |board| board.clear_total));
+        match (status(), flags()) {
+            (GameStatus::Victory, Some(flags)) => match flags.scores[0].cmp(&flags.scores[1]) {
+                Ordering::Greater => format!("{} won! 🥳", seat_name(0)),
+                Ordering::Less => format!("{} won! 🥳", seat_name(1)),
+                Ordering::Equal => "It's a draw 🤝".to_string(),
+            },
+            (_, Some(flags)) => format!(
+                "{} - {} · {} - {} · {}'s turn",
+                seat_name(0),
+                flags.scores[0],
+                seat_name(1),
+                flags.scores[1],
+                seat_name(flags.turn)
+            ),
+            (GameStatus::Victory, None) => "Board cleared together! 🥳".to_string(),
+            (GameStatus::GameOver, None) => "Someone hit a mine 😭".to_string(),
+            _ => format!("{} cells cleared out of {}", cleared(), clear_total),
+        }
+    };
+
+    // 房间中的玩家名单, 标出自己和房主
+    let player_names = move || {
+        players.with(|players| {
+            players
+                .iter()
+                .map(|other| {
+                    let mut name = other.name.clone();
+                    if Some(other.id) == player() {
+                        name.push_str(" (you)");
+                    }
+                    if Some(other.id) == host() {
+                        name.push_str(" (host)");
+                    }
+                    name
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+    };
+
+    view! {
+        <div class="btns">
+            <div class=move || format!("btn {}", if status().is_finished() { "" } else { "disabled" })>
+                <A
+                    href=""
+                    on:click=move |ev| {
+                        ev.prevent_default();
+                        if status.get_untracked().is_finished() {
+                            send(ClientMessage::Restart);
+                        }
+                    }
+                >
+                    "New Game"
+                </A>
+            </div>
+            <div class="btn">
+                <A href="/">
+                    "Return"
+                </A>
+            </div>
+        </div>
+
+        <h2 class="info">
+            {info}
+        </h2>
+
+        <div class="daily-note">
+            {format!("Room - {room}")}
+            <br />
+            {move || format!("Players - {}", player_names())}
+            <br />
+            {move || match (full(), connected()) {
+                (true, _) => Some("This room is full"),
+                (false, false) => Some("Disconnected from the room"),
+                (false, true) => None,
+            }}
+        </div>
+
+        // 房主在游戏开始前或结束后可以修改棋盘参数
+        {move || {
+            let is_host = player().is_some() && player() == host();
+            let idle = !matches!(status(), GameStatus::Started);
+            board.with(|board| {
+                board
+                    .as_ref()
+                    .filter(|_| is_host && idle)
+                    .map(|board| (board.difficulty, board.size, board.mode))
+            })
+            .map(|(difficulty, size, mode)| view! {
+                <HostSettings
+                    difficulty
+                    size
+                    mode
+                    on_apply=move |(difficulty, size, mode): (Difficulty, Size, Mode)| {
+                        send(ClientMessage::Configure { difficulty, size, mode })
+                    }
+                />
+            })
+        }}
+
+        {move || board().map(|snapshot| view! {
+            <RoomBoardView snapshot cells players cursors send />
+        })}
+    }
+}
+
+// 渲染房间的棋盘和其他玩家的鼠标位置, 每次收到完整棋盘时重新创建
+#[component]
+fn RoomBoardView(
+    snapshot: BoardSnapshot,
+    cells: StoredValue<CellSignals>,
+    players: ReadSignal<Vec<Player>>,
+    cursors: ReadSignal<HashMap<u32, (isize, isize)>>,
+    #[prop(into)] send: Callback<ClientMessage>,
+) -> impl IntoView {
+    let BoardSnapshot {
+        size,
+        rows,
+        columns,
+        cells: updates,
+        ..
+    } = snapshot;
+
+    let cell_views = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (row, column)))
+        .map(|(row, column)| {
+            let (cell_state, set_cell_state) =
+                create_signal((CellInteraction::Untouched, CellKind::Clear(0)));
+            (
+                set_cell_state,
+                view! {
+                    <CellView
+                        row
+                        column
+                        cell_state
+                        on_dig=move |_| send(ClientMessage::Dig { row, column })
+                        on_flag=move |_| send(ClientMessage::Flag { row, column })
+                        on_hover=move |_| send(ClientMessage::Cursor { row, column })
+                    />
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+
+    cells.set_value(
+        cell_views
+            .iter()
+            .map(|(set_cell_state, _)| Some(*set_cell_state))
+            .collect(),
+    );
+    cells.with_value(|cells| apply_cells(cells, &updates));
+
+    // 其他玩家的鼠标位置, 与单元格放在同一个网格位置上
+    let cursor_views = move || {
+        cursors.with(|cursors| {
+            cursors
+                .iter()
+                .map(|(id, &(row, column))| {
+                    let name = players.with(|players| {
+                        players
+                            .iter()
+                            .find(|player| player.id == *id)
+                            .map(|player| player.name.clone())
+                            .unwrap_or_default()
+                    });
+                    view! {
+                        <div
+                            class="room-cursor"
+                            style:grid-row-start={row+1}
+                            style:grid-column-start={column+1}
+                        >
+                            {name}
+                        </div>
+                    }
+                })
+                .collect_view()
+        })
+    };
+
+    view! {
+        <div
+            class={ format!("game-board {size}") }
+            style:grid-template-rows=format!("repeat({rows}, minmax(0, 1fr))")
+            style:grid-template-columns=format!("repeat({columns}, minmax(0, 1fr))")
+        >
+            {cell_views.into_iter().map(|(_, view)| view).collect_view()}
+            {cursor_views}
+        </div>
+    }
+}
@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     app_error::AppError,
-    game_settings::{Difficulty, Size},
+    game_settings::{Difficulty, Mode, Size},
     pages::Error,
     utils::{to_time, to_title},
 };
@@ -15,29 +15,79 @@ const MAX_SCORES: usize = 10;
 // 得分结构体
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Score {
-    username: String,
-    time_in_seconds: i64,
+    pub username: String,
+    pub time_in_seconds: i64,
+    pub lives_used: i64,     // 消耗的生命数量 (仅生命模式)
+    pub cells_cleared: i64,  // 清除的单元格数量 (无尽模式按此排名)
+    pub rating: Option<f64>, // 布局的难度评估 (无法评估的模式为空)
 }
 
 // 获取得分
 #[server(GetScores)]
-async fn get_scores(difficulty: Difficulty, size: Size) -> Result<Vec<Score>, ServerFnError> {
+async fn get_scores(
+    difficulty: Difficulty,
+    size: Size,
+    mode: Mode,
+) -> Result<Vec<Score>, ServerFnError> {
+    // 连续挑战模式的成绩单独存储
+    if mode == Mode::Series {
+        return get_series_scores(difficulty, size).await;
+    }
+
     let pool = expect_context::<sqlx::SqlitePool>(); // 获取数据库连接池上下文
-    let (difficulty, size) = (difficulty.to_string(), size.to_string());
+    let (difficulty, size, mode) = (difficulty.to_string(), size.to_string(), mode.to_string());
 
-    // 查询数据库
+    // 查询数据库, 排行榜只显示服务器验证过且没有等待审核的成绩
     sqlx::query_as!(
         Score,
         "
-            SELECT username, time_in_seconds
+            SELECT username, time_in_seconds, lives_used, cells_cleared, rating
             FROM scores
             WHERE difficulty=?
                 AND size=?
-            ORDER BY time_in_seconds
+                AND mode=?
+                AND verified=1
+                AND approved=1
+            ORDER BY lives_used,
+                CASE WHEN mode='endless' THEN -cells_cleared ELSE 0 END,
+                CASE WHEN mode='timeattack' THEN -time_in_seconds ELSE time_in_seconds END
             LIMIT ?
         ",
         difficulty,
         size,
+        mode,
+        MAX_SCORES as i64
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(Into::into)
+}
+
+// 获取连续挑战模式的得分 (按整个系列的总用时排名, 只显示服务器验证过且没有等待审核的成绩)
+#[cfg(feature = "ssr")]
+async fn get_series_scores(
+    difficulty: Difficulty,
+    size: Size,
+) -> Result<Vec<Score>, ServerFnError> {
+    let pool = expect_context::<sqlx::SqlitePool>(); // 获取数据库连接池上下文
+    let (difficulty, size) = (difficulty.to_string(), size.to_string());
+
+    // 查询数据库
+    sqlx::query_as!(
+        Score,
+        r#"
+            SELECT username, time_in_seconds, 0 AS "lives_used!: i64", 0 AS "cells_cleared!: i64",
+                NULL AS "rating: f64"
+            FROM series_scores
+            WHERE difficulty=?
+                AND size=?
+                AND verified=1
+                AND approved=1
+            ORDER BY time_in_seconds
+            LIMIT ?
+        "#,
+        difficulty,
+        size,
         MAX_SCORES as i64
     )
     .fetch_all(&pool)
@@ -45,28 +95,256 @@ async fn get_scores(difficulty: Difficulty, size: Size) -> Result<Vec<Score>, Se
     .map_err(Into::into)
 }
 
-// 提交得分
+// 获取该玩家最近的每日挑战尝试 (包括失败的尝试) 中五五开的统计
+#[cfg(feature = "ssr")]
+async fn get_recent_coin_flips(
+    pool: &sqlx::SqlitePool,
+    username: &str,
+) -> Result<crate::review_logic::CoinFlips, ServerFnError> {
+    use crate::review_logic::{CoinFlips, RECENT_RUNS};
+
+    // 查询数据库
+    sqlx::query_as!(
+        CoinFlips,
+        r#"
+            SELECT COALESCE(SUM(attempted), 0) AS "attempted!: i64",
+                COALESCE(SUM(won), 0) AS "won!: i64"
+            FROM (
+                SELECT attempted, won
+                FROM coin_flips
+                WHERE username=?
+                ORDER BY id DESC
+                LIMIT ?
+            )
+        "#,
+        username,
+        RECENT_RUNS,
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(Into::into)
+}
+
+// 记录一次每日挑战尝试 (获胜或失败) 中五五开的统计
+// 只有服务器保存了每一步的尝试才记录, 客户端自行提交的失败对局可以伪造, 不作为依据
+#[cfg(feature = "ssr")]
+pub(crate) async fn post_coin_flips(
+    pool: &sqlx::SqlitePool,
+    username: &str,
+    coin_flips: crate::review_logic::CoinFlips,
+) -> Result<(), ServerFnError> {
+    // 向数据库中插入数据
+    sqlx::query!(
+        "INSERT INTO coin_flips(username, attempted, won) VALUES (?, ?, ?)",
+        username,
+        coin_flips.attempted,
+        coin_flips.won,
+    )
+    .execute(pool)
+    .await
+    .map(|_| ())
+    .map_err(Into::into)
+}
+
+// 审核一局获胜的分析, 返回以','分隔的可疑原因和成绩是否直接显示在排行榜上
+// 这一局有五五开时结合该玩家最近的每日挑战尝试判断胜率
+#[cfg(feature = "ssr")]
+pub(crate) async fn review_run(
+    pool: &sqlx::SqlitePool,
+    username: &str,
+    analysis: &crate::review_logic::RunAnalysis,
+) -> Result<(String, bool), ServerFnError> {
+    let recent_coin_flips = match analysis.coin_flips.attempted {
+        0 => Default::default(),
+        _ => get_recent_coin_flips(pool, username).await?,
+    };
+    let suspicions = analysis
+        .suspicions(recent_coin_flips)
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    Ok((suspicions.join(","), suspicions.is_empty()))
+}
+
+// 提交得分: 客户端只发送回放, 服务器重新进行一局, 获胜后按回放的时间记录成绩
+// 分析发现可疑之处的成绩进入审核队列, 管理员批准之前不显示在排行榜上
 #[server(PostScore)]
 pub async fn post_score(
     username: String,
-    time_in_seconds: i64,
     difficulty: Difficulty,
     size: Size,
+    mode: Mode,
+    replay: String,
+) -> Result<(), ServerFnError> {
+    use crate::replay::{EndlessReplay, ScoreReplay};
+    use crate::validation::{validate_score, validate_username, ValidationError};
+    use crate::verify_logic::{
+        check_replay_len, verify_endless, verify_replay, ReplayError, MAX_ENDLESS_MOVES,
+        MAX_REPLAY_MOVES,
+    };
+
+    // 无效的用户名, 过长或不可能的回放, 不可能的用时返回具体的错误
+    validate_username(&username).map_err(ValidationError::into_server_error)?;
+    let max_moves = match mode {
+        Mode::Endless => MAX_ENDLESS_MOVES,
+        _ => MAX_REPLAY_MOVES,
+    };
+    let run = check_replay_len(&replay, max_moves)
+        .and_then(|()| match mode {
+            // 无尽模式的棋盘没有边界, 回放记录单元格的行列
+            Mode::Endless => replay
+                .parse::<EndlessReplay>()
+                .map_err(ReplayError::from)
+                .and_then(|replay| verify_endless(difficulty, &replay)),
+            _ => replay
+                .parse::<ScoreReplay>()
+                .map_err(ReplayError::from)
+                .and_then(|replay| verify_replay(difficulty, size, mode, &replay)),
+        })
+        .map_err(|error| ServerFnError::ServerError(error.to_string()))?;
+    // 无尽模式按清除的单元格数量排名, 不检查用时
+    if mode != Mode::Endless {
+        validate_score(&username, run.time_in_seconds)
+            .map_err(ValidationError::into_server_error)?;
+    }
+
+    let pool = expect_context::<sqlx::SqlitePool>(); // 获取数据库连接池上下文
+    let rating = run.rating.map(|rating| rating.score());
+    let bbbv = run.rating.map(|rating| rating.bbbv);
+
+    // 分析发现可疑之处的成绩等待审核
+    let (suspicions, approved) = review_run(&pool, &username, &run.analysis).await?;
+    let (difficulty, size, mode) = (difficulty.to_string(), size.to_string(), mode.to_string());
+
+    // 向数据库中插入数据
+    sqlx::query!(
+        "
+            INSERT INTO scores(
+                username, time_in_seconds, difficulty, size, mode, lives_used, cells_cleared,
+                rating, bbbv, verified, suspicions, approved
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, 1, ?, ?)
+        ",
+        username,
+        run.time_in_seconds,
+        difficulty,
+        size,
+        mode,
+        run.lives_used,
+        run.cells_cleared,
+        rating,
+        bbbv,
+        suspicions,
+        approved,
+    )
+    .execute(&pool)
+    .await
+    .map(|_| ())
+    .map_err(Into::into)
+}
+
+// 提交连续挑战模式的得分: run 为服务器分配的挑战编号, replays 为每块棋盘的回放 (以空格分隔),
+// 服务器逐一验证并累计用时
+#[server(PostSeriesScore)]
+pub async fn post_series_score(
+    username: String,
+    difficulty: Difficulty,
+    size: Size,
+    run: i64,
+    replays: String,
 ) -> Result<(), ServerFnError> {
+    use crate::game_logic::GameState;
+    use crate::replay::ScoreReplay;
+    use crate::review_logic::RunAnalysis;
+    use crate::validation::{validate_score, validate_username, ValidationError};
+    use crate::verify_logic::{check_replay_len, verify_replay, ReplayError, MAX_REPLAY_MOVES};
+
+    // 每块棋盘都按经典模式的规则重新进行, 必须包含整个系列的棋盘
+    validate_username(&username).map_err(ValidationError::into_server_error)?;
+    check_replay_len(
+        &replays,
+        (MAX_REPLAY_MOVES + 1) * GameState::SERIES_BOARDS as usize,
+    )
+    .map_err(|error| ServerFnError::ServerError(error.to_string()))?;
+    let replays = replays
+        .split(' ')
+        .map(|replay| replay.parse::<ScoreReplay>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| ServerFnError::ServerError(ReplayError::from(error).to_string()))?;
+    if replays.len() != GameState::SERIES_BOARDS as usize {
+        return Err(ServerFnError::ServerError(
+            ReplayError::MissingBoards.to_string(),
+        ));
+    }
+    let runs = replays
+        .iter()
+        .map(|replay| verify_replay(difficulty, size, Mode::Classic, replay))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| ServerFnError::ServerError(error.to_string()))?;
+    let time_in_seconds = runs.iter().map(|run| run.time_in_seconds).sum::<i64>();
+    validate_score(&username, time_in_seconds).map_err(ValidationError::into_server_error)?;
+    let boards = GameState::SERIES_BOARDS;
+
     let pool = expect_context::<sqlx::SqlitePool>(); // 获取数据库连接池上下文
+
+    // 挑战必须是服务器分配给这个用户的, 每块棋盘的种子必须与它在挑战中的位置一致
+    let seed = sqlx::query_scalar!(
+        "SELECT seed FROM series_runs WHERE id=? AND username=?",
+        run,
+        username,
+    )
+    .fetch_optional(&pool)
+    .await?
+    .and_then(|seed| seed.parse::<u64>().ok());
+    let on_board = |seed: u64| {
+        replays
+            .iter()
+            .zip(1..)
+            .all(|(replay, board)| replay.seed == GameState::series_board_seed(seed, board))
+    };
+    if !seed.is_some_and(on_board) {
+        return Err(ServerFnError::ServerError(
+            ReplayError::WrongBoard.to_string(),
+        ));
+    }
+
+    // 每次挑战只记录一次成绩
+    let submitted = sqlx::query!(
+        "UPDATE series_runs SET submitted=1 WHERE id=? AND submitted=0",
+        run,
+    )
+    .execute(&pool)
+    .await?
+    .rows_affected();
+    if submitted == 0 {
+        return Err(ServerFnError::ServerError(
+            ReplayError::AlreadySubmitted.to_string(),
+        ));
+    }
+
+    // 按整个系列分析, 可疑的成绩等待审核
+    let analysis = runs
+        .into_iter()
+        .map(|run| run.analysis)
+        .fold(RunAnalysis::default(), RunAnalysis::add);
+    let (suspicions, approved) = review_run(&pool, &username, &analysis).await?;
     let (difficulty, size) = (difficulty.to_string(), size.to_string());
 
     // 向数据库中插入数据
-    sqlx::query_as!(
-        Score,
+    sqlx::query!(
         "
-            INSERT INTO scores(username, time_in_seconds, difficulty, size)
-            VALUES (?, ?, ?, ?)
+            INSERT INTO series_scores(
+                username, time_in_seconds, difficulty, size, boards, verified, suspicions, approved
+            )
+            VALUES (?, ?, ?, ?, ?, 1, ?, ?)
         ",
         username,
         time_in_seconds,
         difficulty,
         size,
+        boards,
+        suspicions,
+        approved,
     )
     .execute(&pool)
     .await
@@ -79,12 +357,14 @@ pub async fn post_score(
 pub fn Scores() -> impl IntoView {
     let (difficulty, set_difficulty) = create_query_signal::<Difficulty>("difficulty");
     let (size, set_size) = create_query_signal::<Size>("size");
-    provide_context((difficulty, size));
-    provide_context((set_difficulty, set_size));
+    let (mode, set_mode) = create_query_signal::<Mode>("mode");
+    provide_context((difficulty, size, mode));
+    provide_context((set_difficulty, set_size, set_mode));
 
     match (difficulty.get_untracked(), size.get_untracked()) {
         (Some(difficulty), Some(size)) => view! {
-            <ScoreFilters difficulty size /> // 过滤器组件(可根据难度/尺寸过滤排行榜)
+            // 过滤器组件(可根据难度/尺寸/模式过滤排行榜), 缺省模式为经典模式
+            <ScoreFilters difficulty size mode=mode.get_untracked().unwrap_or_default() />
 
             <Scoreboard /> // 排行榜组件
 
@@ -112,9 +392,12 @@ pub fn Scores() -> impl IntoView {
 
 // 过滤器组件
 #[component]
-fn ScoreFilters(difficulty: Difficulty, size: Size) -> impl IntoView {
-    let (set_difficulty, set_size) =
-        expect_context::<(SignalSetter<Option<Difficulty>>, SignalSetter<Option<Size>>)>();
+fn ScoreFilters(difficulty: Difficulty, size: Size, mode: Mode) -> impl IntoView {
+    let (set_difficulty, set_size, set_mode) = expect_context::<(
+        SignalSetter<Option<Difficulty>>,
+        SignalSetter<Option<Size>>,
+        SignalSetter<Option<Mode>>,
+    )>();
 
     view! {
         <div class="panel">
@@ -168,6 +451,32 @@ fn ScoreFilters(difficulty: Difficulty, size: Size) -> impl IntoView {
                         }
                         </select>
                     </td>
+                    <td>
+                        <select on:change=move |ev| {
+                            set_mode(Some(event_target_value(&ev).parse().expect("value is a mode")));
+                        }>
+                        {
+                            [
+                                Mode::Classic,
+                                Mode::MultiMine,
+                                Mode::Lives,
+                                Mode::TimeAttack,
+                                Mode::Endless,
+                                Mode::Series,
+                                Mode::Memory,
+                            ].iter().map(|curr_mode| {
+                                view! {
+                                    <option
+                                        value=curr_mode.to_string()
+                                        selected=move || mode == *curr_mode
+                                    >
+                                    {to_title(&curr_mode)}
+                                    </option>
+                                }
+                            }).collect_view()
+                        }
+                        </select>
+                    </td>
                 </tr>
             </table>
         </div>
@@ -177,11 +486,22 @@ fn ScoreFilters(difficulty: Difficulty, size: Size) -> impl IntoView {
 // 排行榜组件
 #[component]
 fn Scoreboard() -> impl IntoView {
-    let (difficulty, size) = expect_context::<(Memo<Option<Difficulty>>, Memo<Option<Size>>)>();
-    let filters = move || (difficulty().unwrap_or_default(), size().unwrap_or_default());
-    let score_getter = create_resource(filters, |(difficulty, size)| async move {
-        get_scores(difficulty, size).await.unwrap_or_default()
+    let (difficulty, size, mode) = expect_context::<(
+        Memo<Option<Difficulty>>,
+        Memo<Option<Size>>,
+        Memo<Option<Mode>>,
+    )>();
+    let filters = move || {
+        (
+            difficulty().unwrap_or_default(),
+            size().unwrap_or_default(),
+            mode().unwrap_or_default(),
+        )
+    };
+    let score_getter = create_resource(filters, |(difficulty, size, mode)| async move {
+        get_scores(difficulty, size, mode).await.unwrap_or_default()
     });
+    let current_mode = move || mode().unwrap_or_default();
 
     view! {
         <div>
@@ -194,20 +514,42 @@ fn Scoreboard() -> impl IntoView {
                         "Name"
                     </th>
                     <th class="time">
-                        "Time"
+                        // 限时模式按剩余时间排名, 连续挑战模式按总用时排名
+                        {move || match current_mode() {
+                            Mode::TimeAttack => "Time Left",
+                            Mode::Series => "Total Time",
+                            _ => "Time",
+                        }}
+                    </th>
+                    <th class="extra">
+                        "Rating"
                     </th>
+                    {move || extra_column(current_mode()).map(|label| view! { <th class="extra">{label}</th> })}
                 </tr>
-                <Transition fallback=move || view! { <ScoreRows scores=vec![] /> }>
-                    {move || view! { <ScoreRows scores=score_getter().unwrap_or_default() /> }}
+                <Transition fallback=move || view! { <ScoreRows scores=vec![] mode=current_mode() rating=true /> }>
+                    {move || view! { <ScoreRows scores=score_getter().unwrap_or_default() mode=current_mode() rating=true /> }}
                 </Transition>
             </table>
         </div>
     }
 }
 
-// 排行榜行组件(用于显示具体的分数记录: 包括名词 用户名 耗时)
+// 部分模式的排行榜额外显示一列 (生命模式显示消耗的生命, 无尽模式显示清除的单元格数量)
+fn extra_column(mode: Mode) -> Option<&'static str> {
+    match mode {
+        Mode::Lives => Some("Lives Used"),
+        Mode::Endless => Some("Cleared"),
+        _ => None,
+    }
+}
+
+// 排行榜行组件(用于显示具体的分数记录: 包括名词 用户名 耗时, 可选的难度评估, 以及部分模式的额外一列)
 #[component]
-fn ScoreRows(mut scores: Vec<Score>) -> impl IntoView {
+pub fn ScoreRows(
+    mut scores: Vec<Score>,
+    mode: Mode,
+    #[prop(optional)] rating: bool, // 是否显示难度评估一列
+) -> impl IntoView {
     scores.resize_with(MAX_SCORES, Default::default);
 
     scores
@@ -218,9 +560,18 @@ fn ScoreRows(mut scores: Vec<Score>) -> impl IntoView {
                 Score {
                     username,
                     time_in_seconds,
+                    lives_used,
+                    cells_cleared,
+                    rating: layout_rating,
                 },
                 n,
             )| {
+                let filled = !username.is_empty(); // 不足10条记录时用空行补齐
+                let extra = match mode {
+                    Mode::Lives => lives_used,
+                    _ => cells_cleared,
+                };
+
                 view! {
                     <tr class={ if n % 2 == 0 { "even" } else { "odd" }}>
                         <td class="n">
@@ -230,8 +581,18 @@ fn ScoreRows(mut scores: Vec<Score>) -> impl IntoView {
                             {username}
                         </td>
                         <td class="time">
-                            { (time_in_seconds > 0).then(|| to_time(time_in_seconds)) }
+                            { filled.then(|| to_time(time_in_seconds)) }
                         </td>
+                        {rating.then(|| view! {
+                            <td class="extra">
+                                { layout_rating.map(|layout_rating| format!("{layout_rating:.1}")) }
+                            </td>
+                        })}
+                        {extra_column(mode).map(|_| view! {
+                            <td class="extra">
+                                { filled.then(|| extra.to_string()) }
+                            </td>
+                        })}
                     </tr>
                 }
             },
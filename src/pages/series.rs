@@ -0,0 +1,73 @@
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_error::AppError,
+    game_logic::{GameParams, GameState},
+    game_settings::Username,
+    pages::{game::GameView, Error},
+};
+
+// 服务器分配的一次连续挑战, 提交成绩时用编号找到种子并检查每块棋盘的布局
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SeriesRun {
+    pub id: i64,   // 挑战编号
+    pub seed: u64, // 各块棋盘的种子由它和棋盘的序号推出
+}
+
+// 为当前用户开始一次新的连续挑战
+#[server(GetSeriesRun)]
+async fn get_series_run(username: String) -> Result<SeriesRun, ServerFnError> {
+    use crate::validation::{validate_username, ValidationError};
+
+    validate_username(&username).map_err(ValidationError::into_server_error)?;
+
+    let pool = expect_context::<sqlx::SqlitePool>(); // 获取数据库连接池上下文
+    let seed = rand::random::<u64>();
+    let seed_text = seed.to_string();
+
+    // 向数据库中插入数据
+    let id = sqlx::query!(
+        "INSERT INTO series_runs(username, seed) VALUES (?, ?)",
+        username,
+        seed_text,
+    )
+    .execute(&pool)
+    .await?
+    .last_insert_rowid();
+
+    Ok(SeriesRun { id, seed })
+}
+
+// 渲染连续挑战模式的游戏, 每开始新的一局都向服务器申请新的挑战
+#[component]
+pub fn Series(params: GameParams) -> impl IntoView {
+    let username = expect_context::<ReadSignal<Username>>();
+    let (round, set_round) = create_signal(0);
+    let series_run = create_resource(round, move |_| async move {
+        get_series_run(username.get_untracked().name).await
+    });
+
+    view! {
+        <Suspense fallback=|| ()>
+            {move || series_run().map(|series_run| match series_run {
+                Ok(series_run) => view! {
+                    <GameView
+                        game_state=GameState::series(params, series_run)
+                        on_new_game=move |_| set_round.update(|round| *round += 1)
+                    />
+                }
+                .into_view(),
+                Err(_) => {
+                    let mut outside_errors = Errors::default();
+                    outside_errors.insert_with_default_key(AppError::SeriesUnavailable);
+
+                    view! {
+                        <Error outside_errors /> // 显示错误信息
+                    }
+                    .into_view()
+                }
+            })}
+        </Suspense>
+    }
+}
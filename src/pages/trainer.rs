@@ -0,0 +1,195 @@
+use leptos::*;
+use leptos_router::*;
+
+use crate::game_logic::{CellInteraction, CellKind};
+use crate::pages::game::CellView;
+use crate::trainer_logic::{Exercise, TrainerStats, PATTERNS};
+use crate::utils::now_millis;
+
+// 单元格的边长(像素)
+const CELL_PIXELS: isize = 50;
+
+// 渲染定式训练页面
+#[component]
+pub fn Trainer() -> impl IntoView {
+    window_event_listener(ev::contextmenu, |ev| ev.prevent_default()); // 禁用右键菜单
+
+    let (stats, set_stats) = create_signal(TrainerStats::default());
+    let (exercise, set_exercise) = create_signal(None::<Exercise>);
+
+    // 本地存储只在浏览器中可用, 读取统计后出第一道题
+    create_effect(move |_| {
+        let stats = TrainerStats::load();
+        set_exercise(Some(Exercise::random(&stats, &mut rand::thread_rng())));
+        set_stats(stats);
+    });
+
+    // 记录答题结果并保存到本地存储
+    let on_answer = move |(name, correct, millis): (&'static str, bool, i64)| {
+        set_stats.update(|stats| {
+            stats.record(name, correct, millis);
+            stats.save();
+        });
+    };
+
+    view! {
+        <div class="btns">
+            <div class="btn">
+                <A
+                    href=""
+                    on:click=move |ev| {
+                        ev.prevent_default();
+                        set_exercise(Some(stats.with(|stats| Exercise::random(stats, &mut rand::thread_rng()))));
+                    }
+                >
+                    "Next"
+                </A>
+            </div>
+            <div class="btn">
+                <A href="/">
+                    "Return"
+                </A>
+            </div>
+        </div>
+
+        {move || exercise().map(|exercise| view! { <ExerciseView exercise on_answer /> })}
+
+        <TrainerStatsTable stats />
+    }
+}
+
+// 渲染一道训练题, 左键标记安全, 右键标记地雷, 全部回答后判定对错
+#[component]
+fn ExerciseView(
+    exercise: Exercise,
+    #[prop(into)] on_answer: Callback<(&'static str, bool, i64)>, // 回答完毕回调 (定式, 是否正确, 用时)
+) -> impl IntoView {
+    let name = exercise.name();
+    let started_at = now_millis();
+    let questions = exercise
+        .cells
+        .iter()
+        .filter(|cell| cell.map_or(false, |cell| cell.question))
+        .count();
+    let (answers, set_answers) = create_signal(Vec::<bool>::new()); // 每个回答是否正确
+    let finished = move || answers.with(|answers| answers.len() == questions);
+    let correct = move || answers.with(|answers| answers.iter().all(|&correct| correct));
+
+    // 回答一个单元格, 最后一个回答之后立即判定
+    let answer = move |right: bool| {
+        if finished() {
+            return;
+        }
+
+        set_answers.update(|answers| answers.push(right));
+        if finished() {
+            on_answer((name, correct(), now_millis() - started_at));
+        }
+    };
+
+    let cells = exercise
+        .cells
+        .iter()
+        .enumerate()
+        .filter_map(|(index, cell)| cell.map(|cell| (index as isize, cell)))
+        .map(|(index, cell)| {
+            let (row, column) = (index / exercise.columns, index % exercise.columns);
+            let (cell_state, set_cell_state) = create_signal((cell.interaction, cell.kind));
+            let is_mine = matches!(cell.kind, CellKind::Mine(_));
+            let answered = move || !matches!(cell_state().0, CellInteraction::Untouched);
+
+            view! {
+                <CellView
+                    row
+                    column
+                    cell_state
+                    highlight=cell.question
+                    on_dig=move |_| {
+                        if cell.question && !answered() && !finished() {
+                            set_cell_state((CellInteraction::Cleared, cell.kind));
+                            answer(!is_mine);
+                        }
+                    }
+                    on_flag=move |_| {
+                        if cell.question && !answered() && !finished() {
+                            set_cell_state((CellInteraction::Flagged(1), cell.kind));
+                            answer(is_mine);
+                        }
+                    }
+                />
+            }
+        })
+        .collect_view();
+
+    view! {
+        <h2 class="info">
+            {move || if finished() {
+                format!("{} - {}", name, if correct() { "Correct 🥳" } else { "Wrong 😭" })
+            } else {
+                "Mark each highlighted cell: left click safe, right click mine".to_string()
+            }}
+        </h2>
+
+        <div
+            class="game-board trainer"
+            style:grid-template-rows=format!("repeat({}, minmax(0, 1fr))", exercise.rows)
+            style:grid-template-columns=format!("repeat({}, minmax(0, 1fr))", exercise.columns)
+            style:width=format!("{}px", exercise.columns * CELL_PIXELS)
+            style:height=format!("{}px", exercise.rows * CELL_PIXELS)
+        >
+            {cells}
+        </div>
+    }
+}
+
+// 显示每个定式的正确率和平均用时
+#[component]
+fn TrainerStatsTable(stats: ReadSignal<TrainerStats>) -> impl IntoView {
+    view! {
+        <table class="scoreboard">
+            <tr class="header">
+                <th class="name">
+                    "Pattern"
+                </th>
+                <th class="extra">
+                    "Tries"
+                </th>
+                <th class="extra">
+                    "Accuracy"
+                </th>
+                <th class="time">
+                    "Avg Time"
+                </th>
+            </tr>
+            {PATTERNS.iter().zip(1..).map(|(pattern, n)| {
+                let name = pattern.name;
+                let pattern_stats = move || stats.with(|stats| stats.get(name));
+
+                view! {
+                    <tr class={ if n % 2 == 0 { "even" } else { "odd" }}>
+                        <td class="name">
+                            {name}
+                        </td>
+                        <td class="extra">
+                            {move || pattern_stats().attempts.to_string()}
+                        </td>
+                        <td class="extra">
+                            {move || {
+                                let pattern_stats = pattern_stats();
+                                (pattern_stats.attempts > 0)
+                                    .then(|| format!("{:.0}%", pattern_stats.accuracy() * 100.0))
+                            }}
+                        </td>
+                        <td class="time">
+                            {move || {
+                                let pattern_stats = pattern_stats();
+                                (pattern_stats.attempts > 0)
+                                    .then(|| format!("{:.1}s", pattern_stats.average_seconds()))
+                            }}
+                        </td>
+                    </tr>
+                }
+            }).collect_view()}
+        </table>
+    }
+}
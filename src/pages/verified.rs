@@ -0,0 +1,265 @@
+use leptos::*;
+use leptos_router::*;
+
+use crate::{
+    game_logic::{CellInteraction, CellKind, GameStatus},
+    game_settings::{Difficulty, Size, Username},
+    pages::{
+        game::CellView,
+        room::{apply_cells, CellSignals},
+    },
+    room::BoardSnapshot,
+    validation::error_message,
+    verified::VerifiedMove,
+};
+
+// 开始一局由服务器维护的游戏, 返回游戏编号和空白棋盘
+#[server(StartVerifiedGame)]
+async fn start_verified_game(
+    username: String,
+    difficulty: Difficulty,
+    size: Size,
+) -> Result<(String, BoardSnapshot), ServerFnError> {
+    use crate::validation::{validate_username, ValidationError};
+    use crate::verified_server::{self, VerifiedGames};
+
+    // 获胜后会以这个用户名写入排行榜, 开始前检查
+    validate_username(&username).map_err(ValidationError::into_server_error)?;
+
+    Ok(verified_server::start(
+        &expect_context::<VerifiedGames>(),
+        username,
+        difficulty,
+        size,
+    ))
+}
+
+// 在服务器上挖开单元格, 获胜时由服务器按记录的用时写入排行榜
+#[server(VerifiedDig)]
+async fn verified_dig(
+    id: String,
+    row: isize,
+    column: isize,
+) -> Result<VerifiedMove, ServerFnError> {
+    use crate::verified_server::{self, VerifiedGames};
+
+    let (verified_move, score) =
+        verified_server::dig(&expect_context::<VerifiedGames>(), &id, row, column)
+            .map_err(|error| ServerFnError::ServerError(error.to_string()))?;
+
+    if let Some(score) = score {
+        let pool = expect_context::<sqlx::SqlitePool>(); // 获取数据库连接池上下文
+        let (difficulty, size) = (score.difficulty.to_string(), score.size.to_string());
+        let time_in_seconds = score.time_millis / 1000;
+        let cells_cleared = score.cells_cleared as i64;
+        let rating = score.rating.map(|rating| rating.score());
+        let bbbv = score.rating.map(|rating| rating.bbbv);
+
+        // 向数据库中插入数据
+        sqlx::query!(
+            "
+                INSERT INTO scores(
+                    username, time_in_seconds, difficulty, size, mode, lives_used, cells_cleared,
+                    rating, bbbv, verified
+                )
+                VALUES (?, ?, ?, ?, 'classic', 0, ?, ?, ?, 1)
+            ",
+            score.username,
+            time_in_seconds,
+            difficulty,
+            size,
+            cells_cleared,
+            rating,
+            bbbv,
+        )
+        .execute(&pool)
+        .await?;
+    }
+
+    Ok(verified_move)
+}
+
+// 在服务器上标记或取消标记单元格
+#[server(VerifiedFlag)]
+async fn verified_flag(
+    id: String,
+    row: isize,
+    column: isize,
+) -> Result<VerifiedMove, ServerFnError> {
+    use crate::verified_server::{self, VerifiedGames};
+
+    verified_server::flag(&expect_context::<VerifiedGames>(), &id, row, column)
+        .map_err(|error| ServerFnError::ServerError(error.to_string()))
+}
+
+// 渲染验证模式: 布局只保存在服务器上, 每次操作都由服务器判定, 只有这样的成绩才能进入排行榜
+#[component]
+pub fn Verified() -> impl IntoView {
+    window_event_listener(ev::contextmenu, |ev| ev.prevent_default()); // 禁用右键菜单
+
+    let (difficulty, size) = use_query_map().with_untracked(|query| {
+        (
+            query
+                .get("difficulty")
+                .and_then(|difficulty| difficulty.parse::<Difficulty>().ok())
+                .unwrap_or_default(),
+            query
+                .get("size")
+                .and_then(|size| size.parse::<Size>().ok())
+                .unwrap_or_default(),
+        )
+    });
+    let username = expect_context::<ReadSignal<Username>>();
+    let (game, set_game) = create_signal(None::<(String, BoardSnapshot)>);
+    let (error, set_error) = create_signal(None::<String>);
+
+    let start = create_action(move |&()| {
+        let name = username.get_untracked().name;
+        async move {
+            match start_verified_game(name, difficulty, size).await {
+                Ok(game) => {
+                    set_error(None);
+                    set_game(Some(game));
+                }
+                Err(error) => set_error(Some(
+                    error_message(&error)
+                        .unwrap_or_else(|| "Could not start a verified game".into()),
+                )),
+            }
+        }
+    });
+    // 进入页面时开始第一局 (只在浏览器中)
+    create_effect(move |_| start.dispatch(()));
+
+    view! {
+        <div class="btns">
+            <div class="btn">
+                <A
+                    href=""
+                    on:click=move |ev| {
+                        ev.prevent_default();
+                        start.dispatch(());
+                    }
+                >
+                    "New Game"
+                </A>
+            </div>
+            <div class="btn">
+                <A href="/">
+                    "Return"
+                </A>
+            </div>
+        </div>
+
+        <div class="daily-note">
+            "Verified game: the board lives on the server, which also keeps the time"
+        </div>
+
+        <div class="mask-error">
+            {error}
+        </div>
+
+        {move || game().map(|(id, snapshot)| view! {
+            <VerifiedBoard id snapshot set_error />
+        })}
+    }
+}
+
+// 渲染服务器上的棋盘, 每局重新创建
+#[component]
+fn VerifiedBoard(
+    id: String,
+    snapshot: BoardSnapshot,
+    set_error: WriteSignal<Option<String>>,
+) -> impl IntoView {
+    let BoardSnapshot {
+        size,
+        rows,
+        columns,
+        clear_total,
+        ..
+    } = snapshot;
+    let (status, set_status) = create_signal(GameStatus::Idle);
+    let (cleared, set_cleared) = create_signal(0);
+    let (time_millis, set_time_millis) = create_signal(None::<i64>);
+    let cells = store_value(CellSignals::new());
+    let id = store_value(id);
+
+    // 应用服务器返回的结果, 页面销毁后信号不再可用, 因此都使用 try_set
+    let apply = move |result: Result<VerifiedMove, ServerFnError>| match result {
+        Ok(verified_move) => {
+            let _ = cells.try_with_value(|cells| apply_cells(cells, &verified_move.cells));
+            let _ = set_status.try_set(verified_move.status);
+            let _ = set_cleared.try_set(verified_move.cleared);
+            if verified_move.time_millis.is_some() {
+                let _ = set_time_millis.try_set(verified_move.time_millis);
+            }
+        }
+        Err(ServerFnError::ServerError(message)) => {
+            let _ = set_error.try_set(Some(message));
+        }
+        Err(_) => {
+            let _ = set_error.try_set(Some("Could not reach the server".into()));
+        }
+    };
+
+    let cell_views = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (row, column)))
+        .map(|(row, column)| {
+            let (cell_state, set_cell_state) =
+                create_signal((CellInteraction::Untouched, CellKind::Clear(0)));
+            let playing = move || !status.get_untracked().is_finished();
+            (
+                set_cell_state,
+                view! {
+                    <CellView
+                        row
+                        column
+                        cell_state
+                        on_dig=move |_| {
+                            if playing() {
+                                let id = id.get_value();
+                                spawn_local(async move { apply(verified_dig(id, row, column).await) });
+                            }
+                        }
+                        on_flag=move |_| {
+                            if playing() {
+                                let id = id.get_value();
+                                spawn_local(async move { apply(verified_flag(id, row, column).await) });
+                            }
+                        }
+                    />
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+
+    cells.set_value(
+        cell_views
+            .iter()
+            .map(|(set_cell_state, _)| Some(*set_cell_state))
+            .collect(),
+    );
+
+    let headline = move || match (status(), time_millis()) {
+        (GameStatus::Victory, Some(millis)) => {
+            format!("Verified time - {:.2}s 🥳", millis as f64 / 1000.0)
+        }
+        (GameStatus::GameOver, _) => "Game over 💥".to_string(),
+        _ => format!("Cleared - {}/{}", cleared(), clear_total),
+    };
+
+    view! {
+        <h2 class="info">
+            {headline}
+        </h2>
+
+        <div
+            class={ format!("game-board {size}") }
+            style:grid-template-rows=format!("repeat({rows}, minmax(0, 1fr))")
+            style:grid-template-columns=format!("repeat({columns}, minmax(0, 1fr))")
+        >
+            {cell_views.into_iter().map(|(_, view)| view).collect_view()}
+        </div>
+    }
+}
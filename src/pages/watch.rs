@@ -0,0 +1,521 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use futures::StreamExt;
+use gloo_net::eventsource::futures::EventSource;
+use gloo_timers::future::TimeoutFuture;
+use leptos::*;
+use leptos_router::*;
+
+use crate::{
+    board_mask::BoardMask,
+    game_logic::{CellInteraction, CellKind, GameInfo, GameParams, GameStatus},
+    game_settings::{Difficulty, Mode, Size, Username},
+    pages::{
+        game::CellView,
+        room::{apply_cells, CellSignals},
+    },
+    room::CellUpdate,
+    utils::to_title,
+    watch::{min_delay, visible_cell, LiveGame, WatchEvent, WATCH_DELAYS, WATCH_FLUSH_MS},
+};
+
+// 开始直播, 返回直播编号和发送更新所需的令牌
+#[server(PublishGame)]
+async fn publish_game(
+    username: String,
+    difficulty: Difficulty,
+    size: Size,
+    mode: Mode,
+    delay_seconds: u32,
+    rows: isize,
+    columns: isize,
+    mask: String,
+) -> Result<(String, String), ServerFnError> {
+    use crate::validation::{validate_username, ValidationError};
+
+    let broadcasts = expect_context::<crate::watch_server::Broadcasts>(); // 获取直播列表上下文
+
+    // 检查用户名, 延迟 (记录成绩的模式有最短延迟) 和棋盘形状
+    validate_username(&username).map_err(ValidationError::into_server_error)?;
+    if !WATCH_DELAYS.contains(&delay_seconds) || delay_seconds < min_delay(mode) {
+        return Err(ServerFnError::ServerError("invalid delay".into()));
+    }
+    let board_mask = mask
+        .parse::<BoardMask>()
+        .map_err(|error| ServerFnError::ServerError(error.to_string()))?;
+    if board_mask.dimensions() != (rows, columns) {
+        return Err(ServerFnError::ServerError(
+            "board size does not match its shape".into(),
+        ));
+    }
+
+    let game = LiveGame {
+        id: String::new(), // 由服务器生成
+        username,
+        difficulty,
+        size,
+        mode,
+        delay_seconds,
+    };
+    crate::watch_server::publish(&broadcasts, game, rows, columns, mask)
+        .ok_or_else(|| ServerFnError::ServerError("too many live games".into()))
+}
+
+// 发送一段时间内的棋盘变化, 事件为 JSON 格式的 WatchEvent::Cells
+#[server(PostWatchCells)]
+async fn post_watch_cells(id: String, token: String, event: String) -> Result<(), ServerFnError> {
+    let broadcasts = expect_context::<crate::watch_server::Broadcasts>(); // 获取直播列表上下文
+
+    let event = serde_json::from_str::<WatchEvent>(&event)
+        .ok()
+        .filter(|event| matches!(event, WatchEvent::Cells { .. }))
+        .ok_or_else(|| ServerFnError::ServerError("invalid watch event".into()))?;
+
+    if crate::watch_server::update(&broadcasts, &id, &token, event) {
+        Ok(())
+    } else {
+        Err(ServerFnError::ServerError("live game has ended".into()))
+    }
+}
+
+// 结束直播
+#[server(EndWatch)]
+async fn end_watch(id: String, token: String) -> Result<(), ServerFnError> {
+    let broadcasts = expect_context::<crate::watch_server::Broadcasts>(); // 获取直播列表上下文
+    crate::watch_server::end(&broadcasts, &id, &token);
+    Ok(())
+}
+
+// 获取正在直播的游戏列表
+#[server(GetLiveGames)]
+async fn get_live_games() -> Result<Vec<LiveGame>, ServerFnError> {
+    let broadcasts = expect_context::<crate::watch_server::Broadcasts>(); // 获取直播列表上下文
+    Ok(crate::watch_server::live_games(&broadcasts))
+}
+
+// 延迟选项的显示文本
+fn delay_label(delay_seconds: u32) -> String {
+    match delay_seconds {
+        0 => "No delay".to_string(),
+        seconds => format!("{seconds}s delay"),
+    }
+}
+
+// 记录棋盘上每个单元格对观众可见的状态, 直播时定期将变化发送给服务器
+// 由 GameView 提供给棋盘上的单元格
+#[derive(Copy, Clone)]
+pub struct Publisher {
+    columns: isize,
+    cells: StoredValue<BTreeMap<usize, CellUpdate>>, // 各单元格的当前状态
+    changed: StoredValue<BTreeSet<usize>>,           // 上次发送后改变的单元格
+    live: RwSignal<Option<(String, String)>>,        // 直播编号和令牌
+}
+
+impl Publisher {
+    pub fn new(columns: isize) -> Self {
+        Self {
+            columns,
+            cells: store_value(BTreeMap::new()),
+            changed: store_value(BTreeSet::new()),
+            live: create_rw_signal(None),
+        }
+    }
+
+    // 记录单元格的新状态
+    pub fn record(
+        &self,
+        row: isize,
+        column: isize,
+        (interaction, kind): (CellInteraction, CellKind),
+    ) {
+        let index = (row * self.columns + column) as usize;
+        let cell = visible_cell(index, interaction, kind);
+        self.cells.update_value(|cells| {
+            if cells.insert(index, cell) != Some(cell) {
+                self.changed.update_value(|changed| {
+                    changed.insert(index);
+                });
+            }
+        });
+    }
+
+    // 取出上次发送后改变的单元格
+    fn take_changed(&self) -> Vec<CellUpdate> {
+        let changed = self.changed.with_value(|changed| changed.clone());
+        self.changed.set_value(BTreeSet::new());
+        self.cells.with_value(|cells| {
+            changed
+                .into_iter()
+                .filter_map(|index| cells.get(&index).copied())
+                .collect()
+        })
+    }
+
+    // 开始直播: 先发送所有已翻开或插旗的单元格, 之后只发送变化, 直到直播结束或离开页面
+    fn start(&self, id: String, token: String, info: ReadSignal<GameInfo>) {
+        let publisher = *self;
+        let touched = self.cells.with_value(|cells| {
+            cells
+                .values()
+                .filter(|cell| cell.interaction != CellInteraction::Untouched)
+                .map(|cell| cell.index)
+                .collect()
+        });
+        self.changed.set_value(touched);
+        self.live.set(Some((id.clone(), token.clone())));
+
+        spawn_local(async move {
+            let mut sent = None;
+            loop {
+                // 直播结束或页面销毁后停止
+                let still_live = publisher.live.try_with_untracked(|live| {
+                    live.as_ref().is_some_and(|(live_id, _)| *live_id == id)
+                });
+                let progress = info.try_with_untracked(|info| (info.status(), info.progress()));
+                let (Some(true), Some(progress)) = (still_live, progress) else {
+                    break;
+                };
+
+                let cells = publisher.take_changed();
+                if !cells.is_empty() || sent != Some(progress) {
+                    let (status, (cleared, clear_total)) = progress;
+                    let event = WatchEvent::Cells {
+                        cells,
+                        status,
+                        cleared,
+                        clear_total,
+                    };
+                    let event = serde_json::to_string(&event).expect("event serializes");
+                    if post_watch_cells(id.clone(), token.clone(), event)
+                        .await
+                        .is_err()
+                    {
+                        let _ = publisher.live.try_set(None);
+                        break;
+                    }
+                    sent = Some(progress);
+                }
+
+                TimeoutFuture::new(WATCH_FLUSH_MS).await;
+            }
+        });
+    }
+}
+
+// 直播当前游戏的按钮, 可以选择延迟, 直播期间显示观看链接
+#[component]
+pub fn GoLive(
+    publisher: Publisher,
+    params: GameParams,
+    rows: isize,
+    columns: isize,
+    mask: BoardMask,
+    info: ReadSignal<GameInfo>,
+) -> impl IntoView {
+    let username = expect_context::<ReadSignal<Username>>();
+    // 记录成绩的游戏只能选择不短于最短延迟的选项
+    let delays = Vec::from_iter(
+        WATCH_DELAYS
+            .into_iter()
+            .filter(|&delay| delay >= min_delay(params.mode())),
+    );
+    let (delay_seconds, set_delay_seconds) = create_signal(delays[0]);
+    let (message, set_message) = create_signal(String::new());
+    let live = publisher.live;
+
+    let go_live = create_action(move |&delay_seconds: &u32| {
+        let username = username.get_untracked().name;
+        let mask = mask.to_string();
+        async move {
+            match publish_game(
+                username,
+                params.difficulty,
+                params.size,
+                params.mode(),
+                delay_seconds,
+                rows,
+                columns,
+                mask,
+            )
+            .await
+            {
+                Ok((id, token)) => {
+                    publisher.start(id, token, info);
+                    set_message(String::new());
+                }
+                Err(_) => set_message("Could not start the live game".into()),
+            }
+        }
+    });
+
+    let stop = move || {
+        if let Some(Some((id, token))) = live.try_get_untracked() {
+            let _ = live.try_set(None);
+            spawn_local(async move {
+                let _ = end_watch(id, token).await;
+            });
+        }
+    };
+
+    // 离开页面时结束直播
+    on_cleanup(stop);
+
+    view! {
+        <div class="btns">
+            {move || match live() {
+                None => view! {
+                    <select
+                        class="inline-select"
+                        on:change=move |ev| {
+                            if let Ok(selected) = event_target_value(&ev).parse() {
+                                set_delay_seconds(selected);
+                            }
+                        }
+                    >
+                        {delays.iter().map(|&delay| view! {
+                            <option value=delay.to_string() selected=move || delay_seconds() == delay>
+                                {delay_label(delay)}
+                            </option>
+                        }).collect_view()}
+                    </select>
+                    <div class="btn">
+                        <A
+                            href=""
+                            on:click=move |ev| {
+                                ev.prevent_default();
+                                go_live.dispatch(delay_seconds.get_untracked());
+                            }
+                        >
+                            "Go Live"
+                        </A>
+                    </div>
+                }
+                .into_view(),
+                Some((id, _)) => view! {
+                    <div class="btn">
+                        <A
+                            href=""
+                            on:click=move |ev| {
+                                ev.prevent_default();
+                                stop();
+                            }
+                        >
+                            "End Live"
+                        </A>
+                    </div>
+                    <div class="daily-note">
+                        "Live - "
+                        <A class="watch-link" href=format!("/watch/{id}")>{format!("/watch/{id}")}</A>
+                    </div>
+                }
+                .into_view(),
+            }}
+        </div>
+
+        <div class="daily-note">
+            {message}
+        </div>
+    }
+}
+
+// 观看一局直播的游戏, 棋盘只随服务器推送的事件改变, 观众不能操作
+#[component]
+pub fn Watch() -> impl IntoView {
+    let id =
+        use_params_map().with_untracked(|params| params.get("id").cloned().unwrap_or_default());
+    let (board, set_board) = create_signal(None::<(LiveGame, isize, isize, BoardMask)>);
+    let (progress, set_progress) = create_signal((GameStatus::Idle, 0, 0));
+    let (ended, set_ended) = create_signal(false);
+    let cells = store_value(CellSignals::new());
+    let source = store_value(None::<EventSource>);
+
+    // 页面销毁后信号不再可用, 因此都使用 try_set
+    let on_event = move |event: WatchEvent| match event {
+        WatchEvent::Board {
+            game,
+            rows,
+            columns,
+            mask,
+        } => {
+            let mask = mask.parse().unwrap_or_else(|_| {
+                BoardMask::from_cells(rows, columns, vec![true; (rows * columns) as usize])
+            });
+            let _ = set_board.try_set(Some((game, rows, columns, mask)));
+        }
+        WatchEvent::Cells {
+            cells: updates,
+            status,
+            cleared,
+            clear_total,
+        } => {
+            cells.try_with_value(|cells| apply_cells(cells, &updates));
+            let _ = set_progress.try_set((status, cleared, clear_total));
+        }
+        WatchEvent::Ended => {
+            let _ = set_ended.try_set(true);
+        }
+    };
+
+    // EventSource 只在浏览器中可用
+    create_effect(move |_| {
+        let Ok(mut event_source) = EventSource::new(&format!("/sse/watch/{id}")) else {
+            set_ended(true);
+            return;
+        };
+        let Ok(mut messages) = event_source.subscribe("message") else {
+            set_ended(true);
+            return;
+        };
+        source.set_value(Some(event_source));
+
+        spawn_local(async move {
+            while let Some(Ok((_, message))) = messages.next().await {
+                let Some(event) = message
+                    .data()
+                    .as_string()
+                    .and_then(|text| serde_json::from_str::<WatchEvent>(&text).ok())
+                else {
+                    continue;
+                };
+                let finished = event == WatchEvent::Ended;
+                on_event(event);
+                if finished {
+                    return;
+                }
+            }
+            let _ = set_ended.try_set(true);
+        });
+    });
+
+    // 离开页面时关闭连接
+    on_cleanup(move || source.set_value(None));
+
+    // 游戏信息: 玩家, 进度或结果
+    let headline = move || {
+        let name = board.with(|board| board.as_ref().map(|(game, ..)| game.username.clone()));
+        let Some(name) = name else {
+            return if ended() {
+                "This game is not live".to_string()
+            } else {
+                "Connecting...".to_string()
+            };
+        };
+        let (status, cleared, clear_total) = progress();
+        match status {
+            GameStatus::Idle => format!("Waiting for {name}'s first move"),
+            GameStatus::Started => format!("{name} - {cleared} cells cleared out of {clear_total}"),
+            GameStatus::Victory => format!("{name} cleared the board! 🥳"),
+            GameStatus::GameOver => format!("{name} hit a mine 😭"),
+            GameStatus::TimedOut => format!("{name} ran out of time ⏰"),
+        }
+    };
+
+    view! {
+        <div class="btns">
+            <div class="btn">
+                <A href="/">
+                    "Return"
+                </A>
+            </div>
+        </div>
+
+        <h2 class="info">
+            {headline}
+        </h2>
+
+        <div class="daily-note">
+            {move || board.with(|board| board.as_ref().map(|(game, ..)| format!(
+                "{} · {} · {} · {}",
+                to_title(&game.difficulty),
+                to_title(&game.size),
+                to_title(&game.mode),
+                delay_label(game.delay_seconds)
+            )))}
+            <br />
+            {move || (ended() && board.with(Option::is_some)).then_some("The live game has ended")}
+        </div>
+
+        {move || board().map(|(game, rows, columns, mask)| view! {
+            <WatchBoard size=game.size rows columns mask cells />
+        })}
+    }
+}
+
+// 渲染观看的棋盘, 单元格不响应点击
+#[component]
+fn WatchBoard(
+    size: Size,
+    rows: isize,
+    columns: isize,
+    mask: BoardMask,
+    cells: StoredValue<CellSignals>,
+) -> impl IntoView {
+    let mut signals = CellSignals::new();
+    let cell_views = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (row, column)))
+        .filter_map(|(row, column)| {
+            if !mask.contains(row, column) {
+                signals.push(None);
+                return None;
+            }
+            let (cell_state, set_cell_state) =
+                create_signal((CellInteraction::Untouched, CellKind::Clear(0)));
+            signals.push(Some(set_cell_state));
+            Some(view! {
+                <CellView row column cell_state on_dig=|_| {} on_flag=|_| {} />
+            })
+        })
+        .collect_view();
+    cells.set_value(signals);
+
+    view! {
+        <div
+            class={ format!("game-board {size}") }
+            class:masked=mask.count() < (rows * columns) as usize
+            style:grid-template-rows=format!("repeat({rows}, minmax(0, 1fr))")
+            style:grid-template-columns=format!("repeat({columns}, minmax(0, 1fr))")
+        >
+            {cell_views}
+        </div>
+    }
+}
+
+// 主页上正在直播的游戏列表
+#[component]
+pub fn LiveGames() -> impl IntoView {
+    let live_games = create_resource(
+        || (),
+        |_| async move { get_live_games().await.unwrap_or_default() },
+    );
+
+    view! {
+        <div class="panel">
+            <div class="panel-label">"Live Games"</div>
+            <Transition fallback=|| ()>
+                {move || live_games().map(|games| {
+                    if games.is_empty() {
+                        return view! { <div class="daily-note">"No live games right now"</div> }.into_view();
+                    }
+                    view! {
+                        <table class="scoreboard">
+                            {games.into_iter().zip(1..).map(|(game, n)| view! {
+                                <tr class={ if n % 2 == 0 { "even" } else { "odd" }}>
+                                    <td class="name">
+                                        <A class="watch-link" href=format!("/watch/{}", game.id)>{game.username}</A>
+                                    </td>
+                                    <td class="extra">
+                                        {format!("{} · {} · {}", to_title(&game.difficulty), to_title(&game.size), to_title(&game.mode))}
+                                    </td>
+                                    <td class="time">
+                                        {delay_label(game.delay_seconds)}
+                                    </td>
+                                </tr>
+                            }).collect_view()}
+                        </table>
+                    }
+                    .into_view()
+                })}
+            </Transition>
+        </div>
+    }
+}
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game_settings::{Difficulty, Size},
+    replay::ScoreReplay,
+};
+
+// 开始比赛所需的最少玩家数量
+pub const MIN_RACERS: usize = 2;
+
+// 开始比赛前的倒计时(秒)
+pub const COUNTDOWN_SECONDS: u32 = 3;
+
+// 比赛的阶段
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum RacePhase {
+    #[default]
+    Lobby, // 等待玩家加入
+    Racing,   // 比赛进行中 (包括倒计时)
+    Finished, // 所有参赛玩家都已完成, 显示结果
+}
+
+// 玩家的比赛结果, 用时由服务器从倒计时结束开始计算
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RaceResult {
+    Cleared { millis: i64 },  // 清除了整块棋盘
+    Exploded { millis: i64 }, // 踩到了地雷
+}
+
+// 房间中的玩家及其比赛进度
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Racer {
+    pub id: u32,
+    pub name: String,
+    pub racing: bool, // 是否参加了当前这场比赛 (比赛开始后加入的玩家只能观看)
+    pub cleared: isize,
+    pub clear_total: isize,
+    pub result: Option<RaceResult>,
+}
+
+// 客户端发送给服务器的消息
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RaceClientMessage {
+    Start,                                           // 开始倒计时
+    Progress { cleared: isize, clear_total: isize }, // 当前进度
+    Finished { replay: ScoreReplay },                // 游戏结束, 由服务器重新进行一局判定是否获胜
+    // 房主修改棋盘参数 (比赛进行中无效)
+    Configure { difficulty: Difficulty, size: Size },
+}
+
+// 服务器发送给客户端的消息
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RaceServerMessage {
+    // 加入房间, 告知自己的编号
+    Welcome {
+        player: u32,
+    },
+    // 开始倒计时, 所有玩家使用同一个种子生成相同的棋盘
+    Countdown {
+        seed: u64,
+        difficulty: Difficulty,
+        size: Size,
+        seconds: u32,
+    },
+    // 比赛的当前状态, 比赛结束后玩家按名次排列
+    Standings {
+        phase: RacePhase,
+        winner: Option<u32>,
+        racers: Vec<Racer>,
+        host: Option<u32>,
+        difficulty: Difficulty,
+        size: Size,
+    },
+    // 房间已满, 无法加入
+    Full,
+}
@@ -0,0 +1,279 @@
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{ws::WebSocketUpgrade, Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use rand::Rng;
+use tokio::sync::broadcast;
+
+use crate::{
+    game_settings::{Difficulty, Mode, Size},
+    race::{
+        RaceClientMessage, RacePhase, RaceResult, RaceServerMessage, Racer, COUNTDOWN_SECONDS,
+        MIN_RACERS,
+    },
+    replay::ScoreReplay,
+    room::{valid_room_name, RoomInfo, RoomKind},
+    room_server::{play, HostedRoom, RoomQuery, Rooms, CHANNEL_CAPACITY},
+    validation::validate_username,
+    verify_logic::{verify_replay, MAX_REPLAY_MOVES},
+};
+
+// 竞速房间中一条客户端消息的字节数上限, 足够放下操作数量达到上限的回放 (JSON 中每次操作不超过128字节)
+const MAX_MESSAGE_BYTES: usize = MAX_REPLAY_MOVES * 128;
+
+// 竞速房间: 所有玩家在各自的浏览器中玩同一块固定布局的棋盘, 服务器记录每个人的完成时间
+pub struct RaceRoom {
+    difficulty: Difficulty,
+    size: Size,
+    racers: Vec<Racer>,
+    next_player: u32,
+    phase: RacePhase,
+    seed: u64,                   // 当前比赛的布局种子, 用于验证玩家提交的回放
+    started_at: Option<Instant>, // 倒计时结束的时刻
+    winner: Option<u32>,
+    host: Option<u32>, // 房主, 可以修改棋盘参数, 离开后由最早加入的玩家接替
+    sender: broadcast::Sender<RaceServerMessage>,
+}
+
+impl RaceRoom {
+    // 创建房间, 棋盘参数由第一个加入的玩家决定
+    pub fn new(difficulty: Difficulty, size: Size) -> Self {
+        Self {
+            difficulty,
+            size,
+            racers: Vec::new(),
+            next_player: 1,
+            phase: RacePhase::Lobby,
+            seed: 0,
+            started_at: None,
+            winner: None,
+            host: None,
+            sender: broadcast::channel(CHANNEL_CAPACITY).0,
+        }
+    }
+
+    // 当前比赛的状态
+    fn standings(&self) -> RaceServerMessage {
+        RaceServerMessage::Standings {
+            phase: self.phase,
+            winner: self.winner,
+            racers: self.racers.clone(),
+            host: self.host,
+            difficulty: self.difficulty,
+            size: self.size,
+        }
+    }
+
+    // 向房间中的所有玩家广播比赛状态
+    fn broadcast_standings(&self) {
+        let _ = self.sender.send(self.standings());
+    }
+
+    // 倒计时结束后经过的毫秒数, 倒计时期间为 None
+    fn race_millis(&self) -> Option<i64> {
+        let started_at = self.started_at?;
+        Instant::now()
+            .checked_duration_since(started_at)
+            .map(|elapsed| elapsed.as_millis() as i64)
+    }
+
+    // 开始新的比赛, 所有在场的玩家都参加
+    fn start(&mut self) {
+        if self.phase == RacePhase::Racing || self.racers.len() < MIN_RACERS {
+            return;
+        }
+
+        for racer in &mut self.racers {
+            racer.racing = true;
+            racer.cleared = 0;
+            racer.result = None;
+        }
+        self.phase = RacePhase::Racing;
+        self.winner = None;
+        self.seed = rand::thread_rng().gen();
+        self.started_at = Some(Instant::now() + Duration::from_secs(COUNTDOWN_SECONDS as u64));
+
+        let _ = self.sender.send(RaceServerMessage::Countdown {
+            seed: self.seed,
+            difficulty: self.difficulty,
+            size: self.size,
+            seconds: COUNTDOWN_SECONDS,
+        });
+    }
+
+    // 所有参赛玩家都完成后结束比赛, 按结果排列名次
+    fn finish_if_done(&mut self) {
+        if self.phase != RacePhase::Racing
+            || self
+                .racers
+                .iter()
+                .any(|racer| racer.racing && racer.result.is_none())
+        {
+            return;
+        }
+
+        // 清除棋盘的玩家按用时排列, 踩到地雷的玩家按进度排列, 未参赛的玩家排在最后
+        self.racers.sort_by_key(|racer| match racer.result {
+            Some(RaceResult::Cleared { millis }) => (0, millis),
+            Some(RaceResult::Exploded { .. }) => (1, -(racer.cleared as i64)),
+            None => (2, 0),
+        });
+        self.winner = self
+            .racers
+            .first()
+            .filter(|racer| matches!(racer.result, Some(RaceResult::Cleared { .. })))
+            .map(|racer| racer.id);
+        self.phase = RacePhase::Finished;
+    }
+}
+
+impl HostedRoom for RaceRoom {
+    type Client = RaceClientMessage;
+    type Server = RaceServerMessage;
+
+    fn subscribe(&self) -> broadcast::Receiver<RaceServerMessage> {
+        self.sender.subscribe()
+    }
+
+    fn join(&mut self, name: String) -> u32 {
+        let id = self.next_player;
+        self.next_player += 1;
+        self.racers.push(Racer {
+            id,
+            name,
+            racing: false,
+            cleared: 0,
+            clear_total: 0,
+            result: None,
+        });
+        self.host.get_or_insert(id);
+        self.broadcast_standings();
+        id
+    }
+
+    fn greeting(&self, player: u32) -> Vec<RaceServerMessage> {
+        vec![RaceServerMessage::Welcome { player }, self.standings()]
+    }
+
+    // 比赛中离开的玩家不再等待其结果
+    fn leave(&mut self, player: u32) {
+        self.racers.retain(|racer| racer.id != player);
+        if self.host == Some(player) {
+            // 比赛结束后玩家按名次排列, 因此按编号找出最早加入的玩家
+            self.host = self.racers.iter().map(|racer| racer.id).min();
+        }
+        self.finish_if_done();
+        self.broadcast_standings();
+    }
+
+    fn handle(&mut self, player: u32, message: RaceClientMessage) {
+        match message {
+            RaceClientMessage::Start => {
+                self.start();
+                self.broadcast_standings();
+                return;
+            }
+            // 只有房主可以修改参数, 比赛进行中不允许修改
+            RaceClientMessage::Configure { difficulty, size } => {
+                if self.host == Some(player) && self.phase != RacePhase::Racing {
+                    self.difficulty = difficulty;
+                    self.size = size;
+                    self.broadcast_standings();
+                }
+                return;
+            }
+            RaceClientMessage::Progress { .. } | RaceClientMessage::Finished { .. } => {}
+        }
+
+        // 只接受倒计时结束后参赛玩家的进度, 完成后不再改变
+        let Some(millis) = self.race_millis() else {
+            return;
+        };
+        let Some(racer) = self
+            .racers
+            .iter_mut()
+            .find(|racer| racer.id == player && racer.racing && racer.result.is_none())
+        else {
+            return;
+        };
+
+        match message {
+            RaceClientMessage::Progress {
+                cleared,
+                clear_total,
+            } => {
+                racer.cleared = cleared;
+                racer.clear_total = clear_total;
+            }
+            // 用比赛的种子重新进行玩家的回放, 只有验证获胜才算清除了棋盘, 用时以服务器为准
+            RaceClientMessage::Finished { replay } => {
+                let replay = ScoreReplay {
+                    seed: self.seed,
+                    ..replay
+                };
+                racer.result = Some(
+                    match verify_replay(self.difficulty, self.size, Mode::Classic, &replay) {
+                        Ok(run) => {
+                            racer.cleared = run.cells_cleared as isize;
+                            racer.clear_total = racer.cleared;
+                            RaceResult::Cleared { millis }
+                        }
+                        Err(_) => RaceResult::Exploded { millis },
+                    },
+                );
+                self.finish_if_done();
+            }
+            RaceClientMessage::Start | RaceClientMessage::Configure { .. } => {}
+        }
+
+        self.broadcast_standings();
+    }
+
+    fn player_count(&self) -> usize {
+        self.racers.len()
+    }
+
+    fn info(&self, name: String, capacity: usize) -> RoomInfo {
+        RoomInfo {
+            name,
+            kind: RoomKind::Race,
+            host: self
+                .racers
+                .iter()
+                .find(|racer| Some(racer.id) == self.host)
+                .map(|racer| racer.name.clone()),
+            players: self.racers.len(),
+            capacity,
+            difficulty: self.difficulty,
+            size: self.size,
+            mode: Mode::Classic,
+            playing: self.phase == RacePhase::Racing,
+        }
+    }
+
+    fn full() -> RaceServerMessage {
+        RaceServerMessage::Full
+    }
+}
+
+// 竞速房间的 WebSocket 入口
+pub async fn race_handler(
+    ws: WebSocketUpgrade,
+    Path(name): Path<String>,
+    Query(query): Query<RoomQuery>,
+    State(races): State<Rooms<RaceRoom>>,
+) -> Response {
+    if !valid_room_name(&name) || validate_username(&query.username).is_err() {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+
+    ws.max_message_size(MAX_MESSAGE_BYTES)
+        .on_upgrade(move |socket| {
+            play(socket, races, name, query.username, move || {
+                RaceRoom::new(query.difficulty, query.size)
+            })
+        })
+}
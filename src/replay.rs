@@ -0,0 +1,353 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use gloo_timers::future::TimeoutFuture;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::utils::now_millis;
+
+// 幽灵回放的刷新间隔(毫秒)
+const GHOST_TICK_MS: u32 = 100;
+
+// 回放中的一次操作: 第一次点击后经过的毫秒数, 以及这次操作翻开的单元格
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplayEvent {
+    pub millis: i64,
+    pub cells: Vec<usize>, // 单元格索引 (行 * 列数 + 列)
+}
+
+// 一局游戏的回放, 只记录翻开单元格的操作
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub events: Vec<ReplayEvent>,
+}
+
+impl Replay {
+    // 记录一次操作 (没有翻开单元格时忽略)
+    pub fn record(&mut self, millis: i64, cells: Vec<usize>) {
+        if !cells.is_empty() {
+            self.events.push(ReplayEvent { millis, cells });
+        }
+    }
+
+    // 回放的总时长(毫秒)
+    pub fn millis(&self) -> i64 {
+        self.events.last().map_or(0, |event| event.millis)
+    }
+}
+
+// 解析回放错误类型
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("Error parsing replay")]
+pub struct ParseReplayError;
+
+// 回放的文本格式: 操作之间用';'分隔, 每个操作为 "毫秒:索引,索引,..."
+impl FromStr for Replay {
+    type Err = ParseReplayError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let events = s
+            .split(';')
+            .filter(|event| !event.is_empty())
+            .map(|event| {
+                let (millis, cells) = event.split_once(':').ok_or(ParseReplayError)?;
+                Ok(ReplayEvent {
+                    millis: millis.parse().map_err(|_| ParseReplayError)?,
+                    cells: cells
+                        .split(',')
+                        .map(|cell| cell.parse().map_err(|_| ParseReplayError))
+                        .collect::<Result<_, _>>()?,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // 操作时间必须按顺序排列
+        if events
+            .windows(2)
+            .any(|pair| pair[0].millis > pair[1].millis)
+        {
+            return Err(ParseReplayError);
+        }
+
+        Ok(Self { events })
+    }
+}
+
+// 实现Display trait用于将回放转换为文本格式
+impl Display for Replay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, event) in self.events.iter().enumerate() {
+            if i > 0 {
+                write!(f, ";")?;
+            }
+            write!(f, "{}:", event.millis)?;
+            for (j, cell) in event.cells.iter().enumerate() {
+                if j > 0 {
+                    write!(f, ",")?;
+                }
+                write!(f, "{cell}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// 提交成绩的回放中的操作类型
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MoveKind {
+    Dig,  // 挖开单元格
+    Flag, // 插旗或拔旗
+    Peek, // 重新查看数字 (仅记忆模式)
+}
+
+// 提交成绩的回放中的一次操作: 第一次点击后经过的毫秒数, 操作类型和单元格索引
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplayMove {
+    pub millis: i64,
+    pub kind: MoveKind,
+    pub index: usize, // 单元格索引 (行 * 列数 + 列), 查看数字时为0
+}
+
+// 提交成绩使用的回放: 地雷布局种子和所有操作, 服务器据此重新进行一局并判定成绩
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScoreReplay {
+    pub seed: u64,
+    pub moves: Vec<ReplayMove>,
+}
+
+// 回放的文本格式: "种子/操作;操作;...", 每个操作为 "毫秒:类型:索引", 类型为 d (挖开), f (插旗) 或 p (查看)
+impl FromStr for ScoreReplay {
+    type Err = ParseReplayError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (seed, moves) = s.split_once('/').ok_or(ParseReplayError)?;
+        let moves = moves
+            .split(';')
+            .filter(|replay_move| !replay_move.is_empty())
+            .map(|replay_move| {
+                let mut parts = replay_move.split(':');
+                let (Some(millis), Some(kind), Some(index), None) =
+                    (parts.next(), parts.next(), parts.next(), parts.next())
+                else {
+                    return Err(ParseReplayError);
+                };
+
+                Ok(ReplayMove {
+                    millis: millis.parse().map_err(|_| ParseReplayError)?,
+                    kind: match kind {
+                        "d" => MoveKind::Dig,
+                        "f" => MoveKind::Flag,
+                        "p" => MoveKind::Peek,
+                        _ => return Err(ParseReplayError),
+                    },
+                    index: index.parse().map_err(|_| ParseReplayError)?,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // 操作时间必须按顺序排列
+        if moves.windows(2).any(|pair| pair[0].millis > pair[1].millis) {
+            return Err(ParseReplayError);
+        }
+
+        Ok(Self {
+            seed: seed.parse().map_err(|_| ParseReplayError)?,
+            moves,
+        })
+    }
+}
+
+// 实现Display trait用于将提交成绩的回放转换为文本格式
+impl Display for ScoreReplay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/", self.seed)?;
+        for (i, replay_move) in self.moves.iter().enumerate() {
+            if i > 0 {
+                write!(f, ";")?;
+            }
+            let kind = match replay_move.kind {
+                MoveKind::Dig => "d",
+                MoveKind::Flag => "f",
+                MoveKind::Peek => "p",
+            };
+            write!(f, "{}:{}:{}", replay_move.millis, kind, replay_move.index)?;
+        }
+        Ok(())
+    }
+}
+
+// 无尽模式回放中的一次操作: 第一次点击后经过的毫秒数, 操作类型和单元格位置 (棋盘没有边界, 行列可以为负)
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EndlessMove {
+    pub millis: i64,
+    pub kind: MoveKind,
+    pub row: isize,
+    pub column: isize,
+}
+
+// 无尽模式提交成绩使用的回放: 地雷布局种子和所有操作
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EndlessReplay {
+    pub seed: u64,
+    pub moves: Vec<EndlessMove>,
+}
+
+// 回放的文本格式: "种子/操作;操作;...", 每个操作为 "毫秒:类型:行,列", 类型为 d (挖开) 或 f (插旗)
+impl FromStr for EndlessReplay {
+    type Err = ParseReplayError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (seed, moves) = s.split_once('/').ok_or(ParseReplayError)?;
+        let moves = moves
+            .split(';')
+            .filter(|replay_move| !replay_move.is_empty())
+            .map(|replay_move| {
+                let mut parts = replay_move.split(':');
+                let (Some(millis), Some(kind), Some(position), None) =
+                    (parts.next(), parts.next(), parts.next(), parts.next())
+                else {
+                    return Err(ParseReplayError);
+                };
+                let (row, column) = position.split_once(',').ok_or(ParseReplayError)?;
+
+                Ok(EndlessMove {
+                    millis: millis.parse().map_err(|_| ParseReplayError)?,
+                    kind: match kind {
+                        "d" => MoveKind::Dig,
+                        "f" => MoveKind::Flag,
+                        _ => return Err(ParseReplayError),
+                    },
+                    row: row.parse().map_err(|_| ParseReplayError)?,
+                    column: column.parse().map_err(|_| ParseReplayError)?,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // 操作时间必须按顺序排列
+        if moves.windows(2).any(|pair| pair[0].millis > pair[1].millis) {
+            return Err(ParseReplayError);
+        }
+
+        Ok(Self {
+            seed: seed.parse().map_err(|_| ParseReplayError)?,
+            moves,
+        })
+    }
+}
+
+// 实现Display trait用于将无尽模式的回放转换为文本格式
+impl Display for EndlessReplay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/", self.seed)?;
+        for (i, replay_move) in self.moves.iter().enumerate() {
+            if i > 0 {
+                write!(f, ";")?;
+            }
+            let kind = match replay_move.kind {
+                MoveKind::Dig => "d",
+                MoveKind::Flag => "f",
+                MoveKind::Peek => "p",
+            };
+            write!(
+                f,
+                "{}:{}:{},{}",
+                replay_move.millis, kind, replay_move.row, replay_move.column
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// 幽灵: 与玩家同时回放的一局记录, 只显示翻开的单元格
+#[derive(Copy, Clone)]
+pub struct Ghost {
+    columns: isize,
+    replay: RwSignal<Option<Replay>>, // 载入的回放
+    revealed: RwSignal<Vec<bool>>,    // 幽灵已翻开的单元格
+    cleared: RwSignal<usize>,         // 幽灵已翻开的单元格数量
+    run: RwSignal<u32>,               // 回放的编号, 用于停止上一次回放
+}
+
+impl Ghost {
+    // 创建与棋盘同样大小的幽灵
+    pub fn new(rows: isize, columns: isize) -> Self {
+        Self {
+            columns,
+            replay: create_rw_signal(None),
+            revealed: create_rw_signal(vec![false; (rows * columns) as usize]),
+            cleared: create_rw_signal(0),
+            run: create_rw_signal(0),
+        }
+    }
+
+    // 载入新的回放并回到起点
+    pub fn load(&self, replay: Replay) {
+        self.replay.set(Some(replay));
+        self.rewind();
+    }
+
+    // 是否已载入回放
+    pub fn is_loaded(&self) -> bool {
+        self.replay.with(Option::is_some)
+    }
+
+    // 停止回放并清空幽灵翻开的单元格
+    pub fn rewind(&self) {
+        self.run.update(|run| *run += 1);
+        self.revealed
+            .update(|revealed| revealed.iter_mut().for_each(|cell| *cell = false));
+        self.cleared.set(0);
+    }
+
+    // 从现在开始回放 (玩家第一次点击时调用)
+    pub fn play(&self) {
+        let Some(replay) = self.replay.get_untracked() else {
+            return;
+        };
+        let ghost = *self;
+        let run = self.run.get_untracked();
+        let started_at = now_millis();
+
+        spawn_local(async move {
+            for event in replay.events {
+                // 等到该操作的时间再翻开单元格
+                while now_millis() - started_at < event.millis {
+                    TimeoutFuture::new(GHOST_TICK_MS).await;
+                }
+
+                // 回放被重置或页面销毁后停止
+                if ghost.run.try_get_untracked() != Some(run) {
+                    return;
+                }
+
+                ghost
+                    .cleared
+                    .update(|cleared| *cleared += event.cells.len());
+                ghost.revealed.update(|revealed| {
+                    for cell in event.cells {
+                        if let Some(revealed) = revealed.get_mut(cell) {
+                            *revealed = true;
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    // 判断幽灵是否已翻开指定位置的单元格
+    pub fn is_revealed(&self, row: isize, column: isize) -> bool {
+        self.revealed.with(|revealed| {
+            revealed
+                .get((row * self.columns + column) as usize)
+                .copied()
+                .unwrap_or_default()
+        })
+    }
+
+    // 幽灵已翻开的单元格数量
+    pub fn cleared(&self) -> usize {
+        self.cleared.get()
+    }
+}
@@ -0,0 +1,290 @@
+use std::str::FromStr;
+
+use serde::{de::IntoDeserializer, Deserialize, Serialize};
+
+use crate::solver::{Knowledge, Layout, Solver};
+
+// 两次操作的间隔低于这个毫秒数视为过快 (人手难以做到)
+const FAST_MOVE_MS: i64 = 50;
+// 计算过快操作比例至少需要的操作间隔数量
+const MIN_TIMED_MOVES: usize = 10;
+// 过快操作占全部操作间隔的比例达到这个值时标记
+const FAST_MOVE_SHARE: f64 = 0.2;
+// 在没有任何信息的单元格上连续猜中的概率低于 10^-2 时标记
+const BLIND_LUCK_LIMIT: f64 = 2.0;
+// 地雷概率达到这个值的猜测视为五五开
+const COIN_FLIP_PROBABILITY: f64 = 0.5;
+// 五五开猜中至少这么多次的概率低于这个值时标记 (每次猜中的概率最多为一半)
+const COIN_FLIP_LIMIT: f64 = 0.001;
+// 统计五五开时回看的最近每日挑战尝试数量 (服务器保存了每一步, 包括失败的尝试)
+pub const RECENT_RUNS: i64 = 20;
+
+// 成绩被标记为可疑的原因
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Suspicion {
+    FastClicks, // 操作间隔过快
+    BlindLuck,  // 在没有信息的单元格上总是猜中
+    CoinFlips,  // 多局游戏中五五开的胜率远高于预期
+}
+
+impl Suspicion {
+    // 显示给管理员的说明
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::FastClicks => "Inhumanly fast click intervals",
+            Self::BlindLuck => "Keeps landing on safe cells with no information",
+            Self::CoinFlips => "Wins far more 50/50s than expected across recent games",
+        }
+    }
+}
+
+// 实现从字符串解析Suspicion
+impl FromStr for Suspicion {
+    type Err = serde::de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(s.into_deserializer())
+    }
+}
+
+// 实现Display trait用于格式化输出可疑原因Suspicion
+impl std::fmt::Display for Suspicion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.serialize(f)
+    }
+}
+
+// 五五开的猜测次数和猜中次数
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct CoinFlips {
+    pub attempted: i64,
+    pub won: i64,
+}
+
+impl CoinFlips {
+    // 合并另一组五五开的统计
+    pub fn add(self, other: Self) -> Self {
+        Self {
+            attempted: self.attempted + other.attempted,
+            won: self.won + other.won,
+        }
+    }
+
+    // 每次猜中的概率按一半计算时, 猜中至少这么多次的概率 (二项分布的上尾)
+    // 在对数空间中累加, 猜测次数很多时每一项的概率也不会下溢为0
+    pub fn chance(&self) -> f64 {
+        let attempted = self.attempted.max(0);
+        let mut ln_term = attempted as f64 * 0.5_f64.ln(); // 恰好猜中 k 次的概率的对数, 从 k = 0 开始
+        let mut ln_chance = f64::NEG_INFINITY;
+        for k in 0..=attempted {
+            if k >= self.won {
+                ln_chance = ln_add(ln_chance, ln_term);
+            }
+            ln_term += ((attempted - k) as f64).ln() - ((k + 1) as f64).ln();
+        }
+        ln_chance.exp().min(1.0)
+    }
+}
+
+// 计算 ln(e^a + e^b), 避免先取指数造成下溢
+fn ln_add(a: f64, b: f64) -> f64 {
+    let (high, low) = if a >= b { (a, b) } else { (b, a) };
+    if low == f64::NEG_INFINITY {
+        return high;
+    }
+    high + (low - high).exp().ln_1p()
+}
+
+// 对一局回放的分析, 在服务器重新进行这一局时逐步记录
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RunAnalysis {
+    pub intervals: usize,      // 记录的操作间隔数量
+    pub fast_moves: usize,     // 间隔过快的操作数量
+    pub blind_luck: f64,       // 没有信息时猜中的运气: 全部猜中的概率的负对数 (以10为底)
+    pub coin_flips: CoinFlips, // 五五开的猜测次数和猜中次数
+}
+
+impl RunAnalysis {
+    // 合并另一局的分析 (连续挑战模式按整个系列分析)
+    pub fn add(self, other: Self) -> Self {
+        Self {
+            intervals: self.intervals + other.intervals,
+            fast_moves: self.fast_moves + other.fast_moves,
+            blind_luck: self.blind_luck + other.blind_luck,
+            coin_flips: self.coin_flips.add(other.coin_flips),
+        }
+    }
+
+    // 记录与上一次操作的间隔(毫秒)
+    pub fn record_interval(&mut self, millis: i64) {
+        self.intervals += 1;
+        if millis < FAST_MOVE_MS {
+            self.fast_moves += 1;
+        }
+    }
+
+    // 记录一次挖开未翻开单元格的操作 (第一次挖掘之后), revealed 为挖掘前已翻开的单元格
+    // 推理能确定安全的单元格不计入, 其余视为猜测, 按玩家可见的信息估计其风险
+    // 踩到地雷说明猜错了, 不算运气, 但猜错的五五开同样计入猜测次数
+    pub fn record_dig(
+        &mut self,
+        layout: &Layout,
+        mines: usize,
+        revealed: impl IntoIterator<Item = usize>,
+        index: usize,
+    ) {
+        let mine = layout.is_mine(index);
+        let mut solver = Solver::new(layout, revealed);
+        let informed = layout
+            .neighbors(index)
+            .any(|neighbor| solver.knowledge(neighbor) == Knowledge::Safe);
+        solver.solve();
+        if solver.knowledge(index) == Knowledge::Safe {
+            return;
+        }
+
+        let probability = solver
+            .mine_probabilities(mines)
+            .into_iter()
+            .find_map(|(cell, probability)| (cell == index).then_some(probability))
+            .unwrap_or_default();
+        if !informed && !mine {
+            self.blind_luck -= (1.0 - probability).max(f64::EPSILON).log10();
+        }
+        if probability >= COIN_FLIP_PROBABILITY {
+            self.coin_flips.attempted += 1;
+            if !mine {
+                self.coin_flips.won += 1;
+            }
+        }
+    }
+
+    // 得出可疑原因, recent_coin_flips 为该玩家最近的每日挑战尝试 (包括失败的尝试) 中五五开的统计
+    pub fn suspicions(&self, recent_coin_flips: CoinFlips) -> Vec<Suspicion> {
+        let mut suspicions = Vec::new();
+
+        if self.intervals >= MIN_TIMED_MOVES
+            && self.fast_moves as f64 / self.intervals as f64 >= FAST_MOVE_SHARE
+        {
+            suspicions.push(Suspicion::FastClicks);
+        }
+        if self.blind_luck >= BLIND_LUCK_LIMIT {
+            suspicions.push(Suspicion::BlindLuck);
+        }
+        if recent_coin_flips.add(self.coin_flips).chance() < COIN_FLIP_LIMIT {
+            suspicions.push(Suspicion::CoinFlips);
+        }
+
+        suspicions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{board_mask::BoardMask, game_settings::Shape};
+
+    // 2行3列的棋盘, 地雷在右上角; 挖开左上角后只剩右边一列两个单元格, 其中恰好一个是地雷
+    fn fifty_fifty() -> Layout {
+        let mask = BoardMask::from_shape(Shape::Rectangle, 2, 3);
+        Layout::new(mask, vec![false, false, true, false, false, false])
+    }
+
+    #[test]
+    fn chance_is_the_binomial_upper_tail() {
+        let chance = |attempted, won| CoinFlips { attempted, won }.chance();
+
+        assert!((chance(10, 10) - 1.0 / 1024.0).abs() < 1e-12);
+        assert!((chance(4, 2) - 11.0 / 16.0).abs() < 1e-12);
+        assert!(chance(10, 0) > 1.0 - 1e-12);
+        assert_eq!(chance(10, 11), 0.0);
+    }
+
+    #[test]
+    fn chance_does_not_underflow_with_many_attempts() {
+        // 0.5^2000 已经下溢为0, 逐项相乘会得出0并把正常的胜率标记为可疑
+        let even = CoinFlips {
+            attempted: 2000,
+            won: 1000,
+        };
+        assert!((0.45..0.55).contains(&even.chance()));
+
+        let lucky = CoinFlips {
+            attempted: 2000,
+            won: 1200,
+        };
+        assert!(lucky.chance() < COIN_FLIP_LIMIT);
+    }
+
+    #[test]
+    fn coin_flips_count_wins_and_losses() {
+        let layout = fifty_fifty();
+        let mut analysis = RunAnalysis::default();
+
+        analysis.record_dig(&layout, 1, [0], 5);
+        assert_eq!(
+            analysis.coin_flips,
+            CoinFlips {
+                attempted: 1,
+                won: 1
+            }
+        );
+        analysis.record_dig(&layout, 1, [0], 2);
+        assert_eq!(
+            analysis.coin_flips,
+            CoinFlips {
+                attempted: 2,
+                won: 1
+            }
+        );
+        // 两个单元格都与已翻开的数字相邻, 不算没有信息的猜测
+        assert_eq!(analysis.blind_luck, 0.0);
+    }
+
+    #[test]
+    fn fast_clicks_are_flagged() {
+        let mut analysis = RunAnalysis::default();
+        for _ in 0..MIN_TIMED_MOVES {
+            analysis.record_interval(250);
+        }
+        assert!(analysis.suspicions(CoinFlips::default()).is_empty());
+
+        for _ in 0..MIN_TIMED_MOVES {
+            analysis.record_interval(FAST_MOVE_MS - 1);
+        }
+        assert_eq!(
+            analysis.suspicions(CoinFlips::default()),
+            vec![Suspicion::FastClicks]
+        );
+    }
+
+    #[test]
+    fn recent_coin_flips_count_towards_the_win_rate() {
+        let analysis = RunAnalysis {
+            coin_flips: CoinFlips {
+                attempted: 2,
+                won: 2,
+            },
+            ..Default::default()
+        };
+        assert!(analysis.suspicions(CoinFlips::default()).is_empty());
+
+        let recent = CoinFlips {
+            attempted: 10,
+            won: 10,
+        };
+        assert_eq!(analysis.suspicions(recent), vec![Suspicion::CoinFlips]);
+    }
+
+    #[test]
+    fn suspicions_round_trip_through_strings() {
+        for suspicion in [
+            Suspicion::FastClicks,
+            Suspicion::BlindLuck,
+            Suspicion::CoinFlips,
+        ] {
+            assert_eq!(suspicion.to_string().parse::<Suspicion>(), Ok(suspicion));
+        }
+    }
+}
@@ -0,0 +1,647 @@
+use std::fmt::{self, Display, Formatter};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use rand::{distributions::Alphanumeric, seq::SliceRandom, Rng};
+use serde::{de::IntoDeserializer, Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    board_mask::BoardMask,
+    flags_logic::{FlagsScore, FLAGS_PLAYERS},
+    game_logic::{CellInteraction, CellKind, GameState, GameStatus},
+    game_settings::{Difficulty, Mode, Shape, Size},
+    solver::Layout,
+};
+
+// 房间名称的最大长度
+pub const ROOM_NAME_MAX: usize = 20;
+
+// 验证房间名称 (字母, 数字, 下划线和连字符)
+pub fn valid_room_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= ROOM_NAME_MAX
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+// 生成随机的房间名称, 用于创建新房间
+pub fn random_room_name(rng: &mut impl Rng) -> String {
+    rng.sample_iter(Alphanumeric)
+        .take(6)
+        .map(|c| char::from(c).to_ascii_lowercase())
+        .collect()
+}
+
+// 房间可容纳的玩家数量范围
+pub const ROOM_CAPACITY_BOUNDS: RangeInclusive<usize> = 2..=8;
+
+// 通过链接直接创建的房间的容量
+pub const DEFAULT_ROOM_CAPACITY: usize = 4;
+
+// 合作房间支持的模式
+pub const ROOM_MODES: [Mode; 2] = [Mode::Classic, Mode::Flags];
+
+// 房间的类型
+#[derive(PartialEq, Copy, Clone, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoomKind {
+    #[default]
+    Coop, // 合作房间, 所有玩家在同一块棋盘上游戏
+    Race, // 竞速房间, 每个玩家在自己的棋盘上比赛
+}
+
+// 实现从字符串解析RoomKind
+impl FromStr for RoomKind {
+    type Err = serde::de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(s.into_deserializer())
+    }
+}
+
+// 实现Display trait用于格式化输出房间类型RoomKind
+impl Display for RoomKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.serialize(f)
+    }
+}
+
+// 大厅中显示的房间信息
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RoomInfo {
+    pub name: String,
+    pub kind: RoomKind,
+    pub host: Option<String>, // 房主的名称, 房间中没有玩家时为空
+    pub players: usize,
+    pub capacity: usize,
+    pub difficulty: Difficulty,
+    pub size: Size,
+    pub mode: Mode,
+    pub playing: bool, // 游戏或比赛是否正在进行
+}
+
+impl RoomInfo {
+    // 房间是否已满
+    pub fn is_full(&self) -> bool {
+        self.players >= self.capacity
+    }
+
+    // 加入房间的页面地址
+    pub fn join_path(&self) -> String {
+        let Self {
+            name,
+            difficulty,
+            size,
+            mode,
+            ..
+        } = self;
+        match self.kind {
+            RoomKind::Coop => {
+                format!("/game?room={name}&difficulty={difficulty}&size={size}&mode={mode}")
+            }
+            RoomKind::Race => format!("/race?room={name}&difficulty={difficulty}&size={size}"),
+        }
+    }
+}
+
+// 创建房间错误类型
+#[derive(Error, Clone, Debug, PartialEq)]
+pub enum LobbyError {
+    #[error("Room names are 1-{ROOM_NAME_MAX} letters, digits, underscores and hyphens")]
+    InvalidName,
+    #[error("A room with this name already exists")]
+    NameTaken,
+    #[error("Rooms hold {} to {} players", ROOM_CAPACITY_BOUNDS.start(), ROOM_CAPACITY_BOUNDS.end())]
+    InvalidCapacity,
+    #[error("This mode is not available in rooms")]
+    InvalidMode,
+}
+
+// 房间中的玩家
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Player {
+    pub id: u32,
+    pub name: String,
+}
+
+// 单元格的变化, 未翻开的单元格不发送类型, 避免泄露地雷位置
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CellUpdate {
+    pub index: usize,
+    pub interaction: CellInteraction,
+    pub kind: CellKind,
+}
+
+// 棋盘的完整状态, 加入房间或重新开始时发送
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BoardSnapshot {
+    pub difficulty: Difficulty,
+    pub size: Size,
+    pub mode: Mode,
+    pub rows: isize,
+    pub columns: isize,
+    pub status: GameStatus,
+    pub cleared: usize,
+    pub clear_total: usize,
+    pub flags: Option<FlagsScore>, // 双方的比分和当前轮到的玩家 (仅夺旗模式)
+    pub cells: Vec<CellUpdate>,
+}
+
+// 客户端发送给服务器的消息
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ClientMessage {
+    // 挖开单元格
+    Dig {
+        row: isize,
+        column: isize,
+    },
+    // 插旗或拔旗
+    Flag {
+        row: isize,
+        column: isize,
+    },
+    // 鼠标所在的单元格
+    Cursor {
+        row: isize,
+        column: isize,
+    },
+    // 游戏结束后重新开始
+    Restart,
+    // 房主修改棋盘参数, 以新的参数重新开始
+    Configure {
+        difficulty: Difficulty,
+        size: Size,
+        mode: Mode,
+    },
+}
+
+// 服务器发送给客户端的消息
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ServerMessage {
+    // 加入房间, 告知自己的编号
+    Welcome {
+        player: u32,
+    },
+    Players(Vec<Player>), // 房间中的玩家列表
+    Host(u32),            // 房主的编号
+    Full,                 // 房间已满, 无法加入
+    Board(BoardSnapshot), // 棋盘的完整状态
+    Cells {
+        cells: Vec<CellUpdate>,    // 本次操作改变的单元格
+        status: GameStatus,        // 操作后的游戏状态
+        cleared: usize,            // 已清除的单元格数量
+        flags: Option<FlagsScore>, // 操作后的比分 (仅夺旗模式)
+    },
+    // 其他玩家的鼠标位置
+    Cursor {
+        player: u32,
+        row: isize,
+        column: isize,
+    },
+}
+
+// 房间中共享的棋盘, 由服务器维护, 所有玩家的操作都作用在同一块棋盘上
+// 夺旗模式下前两位玩家轮流挖掘, 其他玩家只能观看
+pub struct RoomBoard {
+    difficulty: Difficulty,
+    size: Size,
+    mode: Mode, // 只支持经典模式和夺旗模式
+    rows: isize,
+    columns: isize,
+    mask: BoardMask,
+    mines: usize,
+    layout: Option<Layout>, // 第一次挖掘时生成
+    interactions: Vec<CellInteraction>,
+    status: GameStatus,
+    cleared: usize,
+    flags: Option<FlagsScore>,
+}
+
+impl RoomBoard {
+    // 创建空白棋盘, 房间只使用矩形棋盘
+    pub fn new(difficulty: Difficulty, size: Size, mode: Mode) -> Self {
+        let (rows, columns) = GameState::size_dimensions(size);
+        let mask = BoardMask::from_shape(Shape::Rectangle, rows, columns);
+        let mines = (mask.count() as f64 * GameState::mine_probability(difficulty)) as usize;
+
+        Self {
+            difficulty,
+            size,
+            mode,
+            rows,
+            columns,
+            interactions: vec![Default::default(); (rows * columns) as usize],
+            mask,
+            mines,
+            layout: None,
+            status: GameStatus::Idle,
+            cleared: 0,
+            flags: (mode == Mode::Flags).then(FlagsScore::default),
+        }
+    }
+
+    // 棋盘的难度, 大小和模式
+    pub fn params(&self) -> (Difficulty, Size, Mode) {
+        (self.difficulty, self.size, self.mode)
+    }
+
+    // 以相同的参数重新开始
+    pub fn restart(&mut self) {
+        *self = Self::new(self.difficulty, self.size, self.mode);
+    }
+
+    // 获取游戏状态
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+
+    // 已清除的单元格数量
+    pub fn cleared(&self) -> usize {
+        self.cleared
+    }
+
+    // 地雷布局 (第一次挖掘后才有), 服务器用于评估难度
+    pub fn layout(&self) -> Option<&Layout> {
+        self.layout.as_ref()
+    }
+
+    // 双方的比分 (仅夺旗模式)
+    pub fn flags(&self) -> Option<FlagsScore> {
+        self.flags
+    }
+
+    // 总共需要清除的单元格数量
+    pub fn clear_total(&self) -> usize {
+        self.mask.count() - self.mines
+    }
+
+    // 获取棋盘的完整状态
+    pub fn snapshot(&self) -> BoardSnapshot {
+        BoardSnapshot {
+            difficulty: self.difficulty,
+            size: self.size,
+            mode: self.mode,
+            rows: self.rows,
+            columns: self.columns,
+            status: self.status,
+            cleared: self.cleared,
+            clear_total: self.clear_total(),
+            flags: self.flags,
+            cells: (0..self.interactions.len())
+                .filter(|&index| !matches!(self.interactions[index], CellInteraction::Untouched))
+                .map(|index| self.update(index))
+                .collect(),
+        }
+    }
+
+    // 挖开指定位置的单元格, 返回改变的单元格; seat 为玩家的座位, 夺旗模式下只有轮到的玩家可以挖掘
+    pub fn dig(
+        &mut self,
+        row: isize,
+        column: isize,
+        seat: Option<usize>,
+        rng: &mut impl Rng,
+    ) -> Vec<CellUpdate> {
+        let Some(index) = self.index(row, column) else {
+            return Vec::new();
+        };
+        // 插了旗的单元格不能挖掘 (第一次挖掘之前插的旗也不会生成布局)
+        if self.status.is_finished()
+            || matches!(self.interactions[index], CellInteraction::Flagged(_))
+            || self.flags.map_or(false, |flags| {
+                seat.filter(|&seat| seat < FLAGS_PLAYERS) != Some(flags.turn)
+            })
+        {
+            return Vec::new();
+        }
+        let flags_before = self.flags;
+        if self.layout.is_none() {
+            self.generate(index, rng);
+            self.status = GameStatus::Started;
+        }
+
+        let mut changed = Vec::new();
+        match self.interactions[index] {
+            CellInteraction::Untouched => self.reveal(index, &mut changed),
+            // 周围的旗子和已引爆的地雷数量等于数字时, 挖开周围所有未标记的单元格
+            CellInteraction::Cleared => {
+                let layout = self.layout.as_ref().expect("layout generated");
+                let flags = layout
+                    .neighbors(index)
+                    .filter(|&neighbor| {
+                        matches!(
+                            self.interactions[neighbor],
+                            CellInteraction::Flagged(_) | CellInteraction::Exploded
+                        )
+                    })
+                    .count() as u32;
+
+                if !layout.is_mine(index) && layout.number(index) == flags {
+                    for neighbor in layout.neighbors(index).collect::<Vec<_>>() {
+                        if matches!(self.interactions[neighbor], CellInteraction::Untouched) {
+                            self.reveal(neighbor, &mut changed);
+                        }
+                    }
+                }
+            }
+            CellInteraction::Flagged(_) | CellInteraction::Exploded => {}
+        }
+
+        // 夺旗模式下挖开安全的单元格后轮到对方, 找到地雷则继续挖掘
+        if let (Some(before), Some(flags)) = (flags_before, &mut self.flags) {
+            if flags.scores == before.scores && !changed.is_empty() {
+                flags.pass_turn();
+            }
+        }
+
+        self.finish(&mut changed);
+        changed
+            .into_iter()
+            .map(|index| self.update(index))
+            .collect()
+    }
+
+    // 标记或取消标记指定位置的单元格, 与单人游戏一样在第一次挖掘之前也可以插旗
+    pub fn flag(&mut self, row: isize, column: isize) -> Vec<CellUpdate> {
+        let Some(index) = self.index(row, column) else {
+            return Vec::new();
+        };
+        // 夺旗模式下旗子只能通过挖到地雷获得
+        if self.status.is_finished() || self.flags.is_some() {
+            return Vec::new();
+        }
+
+        self.interactions[index] = match self.interactions[index] {
+            CellInteraction::Untouched => CellInteraction::Flagged(1),
+            CellInteraction::Flagged(_) => CellInteraction::Untouched,
+            _ => return Vec::new(),
+        };
+
+        vec![self.update(index)]
+    }
+
+    // 获取指定位置在遮罩中的索引
+    fn index(&self, row: isize, column: isize) -> Option<usize> {
+        self.mask
+            .contains(row, column)
+            .then_some((row * self.columns + column) as usize)
+    }
+
+    // 生成地雷布局, 确保第一次挖掘的单元格及其周围没有地雷
+    fn generate(&mut self, start: usize, rng: &mut impl Rng) {
+        let empty = Layout::new(self.mask.clone(), vec![false; self.interactions.len()]);
+        let exclude = Vec::from_iter(std::iter::once(start).chain(empty.neighbors(start)));
+        let candidates = Vec::from_iter(empty.indices().filter(|index| !exclude.contains(index)));
+
+        let mut mines = vec![false; self.interactions.len()];
+        for &index in candidates.choose_multiple(rng, self.mines) {
+            mines[index] = true;
+        }
+        self.mines = mines.iter().filter(|&&mine| mine).count();
+        self.layout = Some(Layout::new(self.mask.clone(), mines));
+    }
+
+    // 翻开单元格, 数字为0时继续翻开周围的单元格 (跳过已标记的单元格)
+    fn reveal(&mut self, index: usize, changed: &mut Vec<usize>) {
+        let layout = self.layout.as_ref().expect("layout generated");
+
+        // 夺旗模式下找到地雷为当前玩家得分
+        if let (true, Some(flags)) = (layout.is_mine(index), &mut self.flags) {
+            flags.found_mine(1);
+            self.interactions[index] = CellInteraction::Flagged(1);
+            changed.push(index);
+            return;
+        }
+
+        // 任何一个玩家踩到地雷, 所有人的游戏都结束
+        if layout.is_mine(index) {
+            self.interactions[index] = CellInteraction::Exploded;
+            self.status = GameStatus::GameOver;
+            changed.push(index);
+            return;
+        }
+
+        let mut stack = vec![index];
+        while let Some(index) = stack.pop() {
+            if !matches!(self.interactions[index], CellInteraction::Untouched) {
+                continue;
+            }
+
+            self.interactions[index] = CellInteraction::Cleared;
+            self.cleared += 1;
+            changed.push(index);
+
+            if layout.number(index) == 0 {
+                stack.extend(layout.neighbors(index));
+            }
+        }
+    }
+
+    // 判断胜负, 游戏结束后翻开 (失败) 或标记 (胜利) 剩余的地雷
+    fn finish(&mut self, changed: &mut Vec<usize>) {
+        let won = match self.flags {
+            Some(flags) => flags.is_over(self.mines as u32),
+            None => self.cleared == self.clear_total(),
+        };
+        if matches!(self.status, GameStatus::Started) && won {
+            self.status = GameStatus::Victory;
+        }
+
+        let interaction = match self.status {
+            GameStatus::Victory => CellInteraction::Flagged(1),
+            GameStatus::GameOver => CellInteraction::Cleared,
+            _ => return,
+        };
+        let layout = self.layout.as_ref().expect("layout generated");
+        for index in layout.indices().filter(|&index| layout.is_mine(index)) {
+            if matches!(self.interactions[index], CellInteraction::Untouched) {
+                self.interactions[index] = interaction;
+                changed.push(index);
+            }
+        }
+    }
+
+    // 单元格的当前状态, 只有翻开的单元格才包含类型
+    fn update(&self, index: usize) -> CellUpdate {
+        let interaction = self.interactions[index];
+        let kind = match (interaction, &self.layout) {
+            (CellInteraction::Cleared | CellInteraction::Exploded, Some(layout)) => {
+                layout.kind(index)
+            }
+            _ => Default::default(),
+        };
+
+        CellUpdate {
+            index,
+            interaction,
+            kind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    // 小尺寸棋盘的中心, 第一次挖掘的位置
+    const START: (isize, isize) = (4, 6);
+
+    // 在经典模式的棋盘上挖开起点
+    fn started_board(rng: &mut StdRng) -> RoomBoard {
+        let mut board = RoomBoard::new(Difficulty::Easy, Size::Small, Mode::Classic);
+        board.dig(START.0, START.1, None, rng);
+        board
+    }
+
+    // 单元格索引对应的行列
+    fn position(board: &RoomBoard, index: usize) -> (isize, isize) {
+        (
+            index as isize / board.columns,
+            index as isize % board.columns,
+        )
+    }
+
+    #[test]
+    fn first_dig_is_safe_and_opens_an_area() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let board = started_board(&mut rng);
+        let layout = board.layout().expect("layout generated on the first dig");
+        let start = board
+            .index(START.0, START.1)
+            .expect("start is on the board");
+
+        assert_eq!(board.status(), GameStatus::Started);
+        assert!(!layout.is_mine(start));
+        assert!(layout.neighbors(start).all(|index| !layout.is_mine(index)));
+        assert!(board.cleared() > 1);
+    }
+
+    #[test]
+    fn clearing_every_safe_cell_wins() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut board = started_board(&mut rng);
+        let layout = board.layout().expect("layout generated").clone();
+
+        for index in layout.indices().filter(|&index| !layout.is_mine(index)) {
+            let (row, column) = position(&board, index);
+            board.dig(row, column, None, &mut rng);
+        }
+
+        assert_eq!(board.status(), GameStatus::Victory);
+        assert_eq!(board.cleared(), board.clear_total());
+        // 胜利后剩余的地雷都被标记
+        assert!(layout
+            .indices()
+            .filter(|&index| layout.is_mine(index))
+            .all(|index| board.interactions[index] == CellInteraction::Flagged(1)));
+    }
+
+    #[test]
+    fn digging_a_mine_ends_the_game_for_everyone() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut board = started_board(&mut rng);
+        let layout = board.layout().expect("layout generated").clone();
+        let mine = layout
+            .indices()
+            .find(|&index| layout.is_mine(index))
+            .expect("board has mines");
+        let (row, column) = position(&board, mine);
+
+        let changed = board.dig(row, column, None, &mut rng);
+        assert_eq!(board.status(), GameStatus::GameOver);
+        assert!(changed.contains(&CellUpdate {
+            index: mine,
+            interaction: CellInteraction::Exploded,
+            kind: CellKind::Mine(1),
+        }));
+
+        let safe = layout
+            .indices()
+            .find(|&index| {
+                !layout.is_mine(index) && board.interactions[index] == Default::default()
+            })
+            .expect("some safe cells are still covered");
+        let (row, column) = position(&board, safe);
+        assert!(board.dig(row, column, None, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn chording_opens_neighbours_once_the_mines_are_marked() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut board = started_board(&mut rng);
+        let layout = board.layout().expect("layout generated").clone();
+
+        // 已翻开的数字单元格, 周围还有未翻开的安全单元格
+        let covered_safe = |board: &RoomBoard, index: usize| {
+            layout.neighbors(index).any(|neighbor| {
+                !layout.is_mine(neighbor) && board.interactions[neighbor] == Default::default()
+            })
+        };
+        let number = layout
+            .indices()
+            .find(|&index| {
+                board.interactions[index] == CellInteraction::Cleared
+                    && layout.number(index) > 0
+                    && covered_safe(&board, index)
+            })
+            .expect("the opening has a numbered edge");
+        let (row, column) = position(&board, number);
+        let mut mines = layout
+            .neighbors(number)
+            .filter(|&neighbor| layout.is_mine(neighbor));
+
+        // 旗子不够时不挖开周围的单元格
+        assert!(board.dig(row, column, None, &mut rng).is_empty());
+
+        // 已引爆的地雷与旗子一样计数, 与服务器重新进行回放时的规则一致
+        let exploded = mines.next().expect("numbered cell has a mine");
+        board.interactions[exploded] = CellInteraction::Exploded;
+        for mine in mines {
+            let (row, column) = position(&board, mine);
+            assert_eq!(board.flag(row, column).len(), 1);
+        }
+
+        assert!(!board.dig(row, column, None, &mut rng).is_empty());
+        assert!(!covered_safe(&board, number));
+        assert_eq!(board.status(), GameStatus::Started);
+    }
+
+    #[test]
+    fn flags_can_be_placed_before_the_first_dig() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut board = RoomBoard::new(Difficulty::Easy, Size::Small, Mode::Classic);
+
+        let changed = board.flag(START.0, START.1);
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].interaction, CellInteraction::Flagged(1));
+
+        // 插了旗的单元格不能挖掘, 也不会生成布局
+        assert!(board.dig(START.0, START.1, None, &mut rng).is_empty());
+        assert!(board.layout().is_none());
+
+        // 拔旗之后可以正常开始
+        assert_eq!(
+            board.flag(START.0, START.1)[0].interaction,
+            CellInteraction::Untouched
+        );
+        assert!(!board.dig(START.0, START.1, None, &mut rng).is_empty());
+        assert_eq!(board.status(), GameStatus::Started);
+    }
+
+    #[test]
+    fn only_the_player_whose_turn_it_is_digs_in_flags_mode() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut board = RoomBoard::new(Difficulty::Easy, Size::Small, Mode::Flags);
+
+        assert!(board.dig(START.0, START.1, Some(1), &mut rng).is_empty());
+        assert!(board.dig(START.0, START.1, None, &mut rng).is_empty());
+        assert!(board.flag(START.0, START.1).is_empty());
+
+        assert!(!board.dig(START.0, START.1, Some(0), &mut rng).is_empty());
+        assert_eq!(board.flags().map(|flags| flags.turn), Some(1));
+    }
+}
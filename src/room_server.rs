@@ -0,0 +1,458 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use futures::{stream::SplitSink, SinkExt, StreamExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::{
+    flags_logic::FLAGS_PLAYERS,
+    game_logic::GameStatus,
+    game_settings::{Difficulty, Mode, Size},
+    room::{
+        valid_room_name, ClientMessage, LobbyError, Player, RoomBoard, RoomInfo, RoomKind,
+        ServerMessage, DEFAULT_ROOM_CAPACITY, ROOM_CAPACITY_BOUNDS, ROOM_MODES,
+    },
+    validation::validate_username,
+};
+
+// 每个房间广播队列的容量
+pub(crate) const CHANNEL_CAPACITY: usize = 256;
+
+// 没有玩家的房间保留的时间 (在大厅中创建后等待玩家加入)
+const EMPTY_ROOM_IDLE: Duration = Duration::from_secs(2 * 60);
+
+// 有玩家但长时间没有任何操作的房间保留的时间
+const ROOM_IDLE: Duration = Duration::from_secs(30 * 60);
+
+// 检查空闲房间的间隔
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+// 登记在大厅中的房间, 记录容量和最近一次活动的时刻
+pub struct RoomEntry<R> {
+    room: R,
+    capacity: usize,
+    last_active: Instant,
+}
+
+impl<R: HostedRoom> RoomEntry<R> {
+    fn new(room: R, capacity: usize) -> Self {
+        Self {
+            room,
+            capacity,
+            last_active: Instant::now(),
+        }
+    }
+
+    // 记录一次活动
+    fn touch(&mut self) {
+        self.last_active = Instant::now();
+    }
+
+    // 没有玩家的房间和长时间没有操作的房间视为空闲
+    fn is_idle(&self) -> bool {
+        let idle = self.last_active.elapsed();
+        idle > ROOM_IDLE || (self.room.player_count() == 0 && idle > EMPTY_ROOM_IDLE)
+    }
+}
+
+// 所有房间, 以房间名称为键
+pub type Rooms<R = Room> = Arc<Mutex<HashMap<String, RoomEntry<R>>>>;
+
+// 由服务器维护的房间, 合作房间和竞速房间共用同一套连接处理
+pub trait HostedRoom: Send + 'static {
+    // 客户端发送的消息
+    type Client: DeserializeOwned;
+    // 服务器广播的消息
+    type Server: Serialize + Clone + Send + Sync;
+
+    // 订阅房间的广播
+    fn subscribe(&self) -> broadcast::Receiver<Self::Server>;
+
+    // 加入房间, 返回玩家编号
+    fn join(&mut self, name: String) -> u32;
+
+    // 刚加入的玩家需要先收到的消息
+    fn greeting(&self, player: u32) -> Vec<Self::Server>;
+
+    // 离开房间
+    fn leave(&mut self, player: u32);
+
+    // 处理玩家的操作
+    fn handle(&mut self, player: u32, message: Self::Client);
+
+    // 房间中的玩家数量
+    fn player_count(&self) -> usize;
+
+    // 大厅中显示的房间信息
+    fn info(&self, name: String, capacity: usize) -> RoomInfo;
+
+    // 房间已满时发送给玩家的消息
+    fn full() -> Self::Server;
+}
+
+// 在大厅中创建房间, 名称已被使用时返回错误
+pub fn create_room<R: HostedRoom>(
+    rooms: &Rooms<R>,
+    name: String,
+    capacity: usize,
+    room: R,
+) -> Result<(), LobbyError> {
+    if !valid_room_name(&name) {
+        return Err(LobbyError::InvalidName);
+    }
+    if !ROOM_CAPACITY_BOUNDS.contains(&capacity) {
+        return Err(LobbyError::InvalidCapacity);
+    }
+
+    let mut rooms = rooms.lock().expect("rooms lock is not poisoned");
+    if rooms.contains_key(&name) {
+        return Err(LobbyError::NameTaken);
+    }
+    rooms.insert(name, RoomEntry::new(room, capacity));
+    Ok(())
+}
+
+// 大厅中的房间列表
+pub fn room_list<R: HostedRoom>(rooms: &Rooms<R>) -> Vec<RoomInfo> {
+    rooms
+        .lock()
+        .expect("rooms lock is not poisoned")
+        .iter()
+        .map(|(name, entry)| entry.room.info(name.clone(), entry.capacity))
+        .collect()
+}
+
+// 定期删除空闲的房间, 删除后房间的广播通道关闭, 仍在连接的玩家随之断开
+pub fn spawn_cleanup<R: HostedRoom>(rooms: Rooms<R>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+            rooms
+                .lock()
+                .expect("rooms lock is not poisoned")
+                .retain(|_, entry| !entry.is_idle());
+        }
+    });
+}
+
+// 一个房间: 共享的棋盘, 玩家列表和广播通道
+pub struct Room {
+    board: RoomBoard,
+    players: Vec<Player>,
+    next_player: u32,  // 下一个加入的玩家编号
+    host: Option<u32>, // 房主, 可以修改棋盘参数, 离开后由最早加入的玩家接替
+    sender: broadcast::Sender<ServerMessage>,
+}
+
+impl Room {
+    // 创建房间, 棋盘参数由第一个加入的玩家决定
+    pub fn new(difficulty: Difficulty, size: Size, mode: Mode) -> Self {
+        Self {
+            board: RoomBoard::new(difficulty, size, mode),
+            players: Vec::new(),
+            next_player: 1,
+            host: None,
+            sender: broadcast::channel(CHANNEL_CAPACITY).0,
+        }
+    }
+
+    // 向房间中的所有玩家广播消息 (没有玩家时忽略)
+    fn broadcast(&self, message: ServerMessage) {
+        let _ = self.sender.send(message);
+    }
+}
+
+impl HostedRoom for Room {
+    type Client = ClientMessage;
+    type Server = ServerMessage;
+
+    fn subscribe(&self) -> broadcast::Receiver<ServerMessage> {
+        self.sender.subscribe()
+    }
+
+    fn join(&mut self, name: String) -> u32 {
+        let id = self.next_player;
+        self.next_player += 1;
+        self.players.push(Player { id, name });
+        self.broadcast(ServerMessage::Players(self.players.clone()));
+        if self.host.is_none() {
+            self.host = Some(id);
+            self.broadcast(ServerMessage::Host(id));
+        }
+        id
+    }
+
+    // 先告知玩家编号和房主, 再发送当前棋盘
+    fn greeting(&self, player: u32) -> Vec<ServerMessage> {
+        let mut greeting = vec![ServerMessage::Welcome { player }];
+        greeting.extend(self.host.map(ServerMessage::Host));
+        greeting.push(ServerMessage::Board(self.board.snapshot()));
+        greeting
+    }
+
+    fn leave(&mut self, id: u32) {
+        self.players.retain(|player| player.id != id);
+        self.broadcast(ServerMessage::Players(self.players.clone()));
+        if self.host == Some(id) {
+            self.host = self.players.first().map(|player| player.id);
+            if let Some(host) = self.host {
+                self.broadcast(ServerMessage::Host(host));
+            }
+        }
+    }
+
+    // 处理玩家的操作, 并把结果广播给所有玩家
+    fn handle(&mut self, player: u32, message: ClientMessage) {
+        let cells = match message {
+            // 按加入的顺序, 前两位玩家是夺旗模式的双方
+            ClientMessage::Dig { row, column } => {
+                let seat = self
+                    .players
+                    .iter()
+                    .position(|other| other.id == player)
+                    .filter(|&seat| seat < FLAGS_PLAYERS);
+                self.board.dig(row, column, seat, &mut rand::thread_rng())
+            }
+            ClientMessage::Flag { row, column } => self.board.flag(row, column),
+            ClientMessage::Cursor { row, column } => {
+                self.broadcast(ServerMessage::Cursor {
+                    player,
+                    row,
+                    column,
+                });
+                return;
+            }
+            // 游戏进行中不允许重新开始, 避免打断其他玩家
+            ClientMessage::Restart => {
+                if !matches!(self.board.status(), GameStatus::Started) {
+                    self.board.restart();
+                    self.broadcast(ServerMessage::Board(self.board.snapshot()));
+                }
+                return;
+            }
+            // 只有房主可以修改参数, 游戏进行中不允许修改
+            ClientMessage::Configure {
+                difficulty,
+                size,
+                mode,
+            } => {
+                if self.host == Some(player)
+                    && !matches!(self.board.status(), GameStatus::Started)
+                    && ROOM_MODES.contains(&mode)
+                {
+                    self.board = RoomBoard::new(difficulty, size, mode);
+                    self.broadcast(ServerMessage::Board(self.board.snapshot()));
+                }
+                return;
+            }
+        };
+
+        if !cells.is_empty() {
+            self.broadcast(ServerMessage::Cells {
+                cells,
+                status: self.board.status(),
+                cleared: self.board.cleared(),
+                flags: self.board.flags(),
+            });
+        }
+    }
+
+    fn player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    fn info(&self, name: String, capacity: usize) -> RoomInfo {
+        let (difficulty, size, mode) = self.board.params();
+        RoomInfo {
+            name,
+            kind: RoomKind::Coop,
+            host: self
+                .players
+                .iter()
+                .find(|player| Some(player.id) == self.host)
+                .map(|player| player.name.clone()),
+            players: self.players.len(),
+            capacity,
+            difficulty,
+            size,
+            mode,
+            playing: matches!(self.board.status(), GameStatus::Started),
+        }
+    }
+
+    fn full() -> ServerMessage {
+        ServerMessage::Full
+    }
+}
+
+// 加入房间时的查询参数
+#[derive(Deserialize)]
+pub struct RoomQuery {
+    pub(crate) username: String,
+    #[serde(default)]
+    pub(crate) difficulty: Difficulty,
+    #[serde(default)]
+    pub(crate) size: Size,
+    #[serde(default)]
+    pub(crate) mode: Mode,
+}
+
+// 房间的 WebSocket 入口
+// 通过链接加入不存在的房间时会创建房间, 因此按大厅创建房间的规则检查名称和模式, 并检查玩家的用户名
+pub async fn room_handler(
+    ws: WebSocketUpgrade,
+    Path(name): Path<String>,
+    Query(query): Query<RoomQuery>,
+    State(rooms): State<Rooms>,
+) -> Response {
+    if !valid_room_name(&name)
+        || !ROOM_MODES.contains(&query.mode)
+        || validate_username(&query.username).is_err()
+    {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+
+    ws.on_upgrade(move |socket| {
+        play(socket, rooms, name, query.username, move || {
+            Room::new(query.difficulty, query.size, query.mode)
+        })
+    })
+}
+
+// 将消息序列化后发送给一个玩家
+async fn send(
+    sink: &mut SplitSink<WebSocket, Message>,
+    message: &impl Serialize,
+) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(message).expect("message serializes");
+    sink.send(Message::Text(text)).await
+}
+
+// 一个玩家在房间中的整个连接过程, 房间不存在时用 create 创建 (使用默认容量)
+pub(crate) async fn play<R: HostedRoom>(
+    socket: WebSocket,
+    rooms: Rooms<R>,
+    name: String,
+    username: String,
+    create: impl FnOnce() -> R,
+) {
+    // 先订阅广播再加入, 保证不会错过加入之后的消息
+    let joined = {
+        let mut rooms = rooms.lock().expect("rooms lock is not poisoned");
+        let entry = rooms
+            .entry(name.clone())
+            .or_insert_with(|| RoomEntry::new(create(), DEFAULT_ROOM_CAPACITY));
+        if entry.room.player_count() >= entry.capacity {
+            None
+        } else {
+            entry.touch();
+            let receiver = entry.room.subscribe();
+            let player = entry.room.join(username);
+            Some((player, receiver, entry.room.greeting(player)))
+        }
+    };
+
+    let (mut sink, mut stream) = socket.split();
+
+    // 房间已满时告知玩家后断开
+    let Some((player, mut receiver, greeting)) = joined else {
+        let _ = send(&mut sink, &R::full()).await;
+        let _ = sink.close().await;
+        return;
+    };
+
+    // 转发房间的广播, 先发送加入时的消息, 房间被删除时返回 true
+    let mut forward = tokio::spawn({
+        let (rooms, name) = (rooms.clone(), name.clone());
+
+        async move {
+            for message in greeting {
+                if send(&mut sink, &message).await.is_err() {
+                    return false;
+                }
+            }
+
+            loop {
+                match receiver.recv().await {
+                    Ok(message) => {
+                        if send(&mut sink, &message).await.is_err() {
+                            return false;
+                        }
+                    }
+                    // 处理过慢时跳过了积压的消息, 重新发送房间的当前状态
+                    Err(RecvError::Lagged(_)) => {
+                        let greeting = rooms
+                            .lock()
+                            .expect("rooms lock is not poisoned")
+                            .get(&name)
+                            .map(|entry| entry.room.greeting(player));
+                        let Some(greeting) = greeting else {
+                            return true;
+                        };
+                        for message in greeting {
+                            if send(&mut sink, &message).await.is_err() {
+                                return false;
+                            }
+                        }
+                    }
+                    Err(RecvError::Closed) => return true,
+                }
+            }
+        }
+    });
+    let mut room_closed = false;
+
+    // 处理玩家发来的操作
+    loop {
+        tokio::select! {
+            message = stream.next() => {
+                let Some(Ok(message)) = message else {
+                    break;
+                };
+                let Message::Text(text) = message else {
+                    continue;
+                };
+                let Ok(message) = serde_json::from_str::<R::Client>(&text) else {
+                    continue;
+                };
+
+                let mut rooms = rooms.lock().expect("rooms lock is not poisoned");
+                if let Some(entry) = rooms.get_mut(&name) {
+                    entry.touch();
+                    entry.room.handle(player, message);
+                }
+            }
+            closed = &mut forward => {
+                room_closed = closed.unwrap_or(false);
+                break;
+            }
+        }
+    }
+    forward.abort();
+
+    // 房间已被删除时, 同名的房间可能是新创建的, 不再离开
+    if room_closed {
+        return;
+    }
+
+    // 离开房间, 最后一个玩家离开后删除房间
+    let mut rooms = rooms.lock().expect("rooms lock is not poisoned");
+    if let Some(entry) = rooms.get_mut(&name) {
+        entry.touch();
+        entry.room.leave(player);
+        if entry.room.player_count() == 0 {
+            rooms.remove(&name);
+        }
+    }
+}
@@ -0,0 +1,504 @@
+use std::ops::RangeInclusive;
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    board_mask::BoardMask,
+    game_logic::{CellKind, ADJACENTS},
+};
+
+// 地雷布局, 与遮罩一起描述一块完整的棋盘
+#[derive(Clone, Debug)]
+pub struct Layout {
+    mask: BoardMask,
+    mines: Vec<bool>, // 按行存储, true 表示该位置有地雷
+}
+
+impl Layout {
+    // 使用指定的地雷位置创建布局
+    pub fn new(mask: BoardMask, mines: Vec<bool>) -> Self {
+        Self { mask, mines }
+    }
+
+    // 在遮罩范围内随机放置指定数量的地雷
+    pub fn random(mask: BoardMask, mines: usize, rng: &mut impl Rng) -> Self {
+        let (rows, columns) = mask.dimensions();
+        let mut layout = Self::new(mask, vec![false; (rows * columns) as usize]);
+
+        let mut cells = layout.indices().collect::<Vec<_>>();
+        cells.shuffle(rng);
+        for index in cells.into_iter().take(mines) {
+            layout.mines[index] = true;
+        }
+
+        layout
+    }
+
+    // 遮罩之内所有单元格的索引
+    pub fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+        let (rows, columns) = self.mask.dimensions();
+
+        (0..rows)
+            .flat_map(move |row| (0..columns).map(move |column| (row, column)))
+            .filter(|&(row, column)| self.mask.contains(row, column))
+            .map(move |(row, column)| (row * columns + column) as usize)
+    }
+
+    // 获取相邻单元格的索引 (跳过遮罩之外的空位)
+    pub fn neighbors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let (_, columns) = self.mask.dimensions();
+        let (row, column) = (index as isize / columns, index as isize % columns);
+
+        ADJACENTS
+            .iter()
+            .map(move |(row_offset, column_offset)| (row + row_offset, column + column_offset))
+            .filter(|&(row, column)| self.mask.contains(row, column))
+            .map(move |(row, column)| (row * columns + column) as usize)
+    }
+
+    // 判断指定位置是否有地雷
+    pub fn is_mine(&self, index: usize) -> bool {
+        self.mines[index]
+    }
+
+    // 获取指定位置周围的地雷数量
+    pub fn number(&self, index: usize) -> u32 {
+        self.neighbors(index)
+            .filter(|&neighbor| self.is_mine(neighbor))
+            .count() as u32
+    }
+
+    // 获取指定位置的单元格类型
+    pub fn kind(&self, index: usize) -> CellKind {
+        if self.is_mine(index) {
+            CellKind::Mine(1)
+        } else {
+            CellKind::Clear(self.number(index))
+        }
+    }
+
+    // 挖开指定位置时会被清除的所有单元格 (与游戏中一样递归挖开空白区域)
+    pub fn opening(&self, index: usize) -> Vec<usize> {
+        let mut opened = vec![index];
+        let mut stack = vec![index];
+
+        while let Some(index) = stack.pop() {
+            if self.number(index) == 0 {
+                for neighbor in self.neighbors(index) {
+                    if !self.is_mine(neighbor) && !opened.contains(&neighbor) {
+                        opened.push(neighbor);
+                        stack.push(neighbor);
+                    }
+                }
+            }
+        }
+
+        opened
+    }
+}
+
+// 推理过程中对单元格的了解
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Knowledge {
+    Unknown, // 未知
+    Safe,    // 已知安全 (已翻开, 数字可见)
+    Mine,    // 已知是地雷
+}
+
+// 扫雷逻辑求解器, 只使用不需要猜测的推理规则
+pub struct Solver<'a> {
+    layout: &'a Layout,
+    known: Vec<Knowledge>,
+    steps: u32, // 推理的轮数, 每一轮同时应用所有能直接得出的结论
+}
+
+impl<'a> Solver<'a> {
+    // 从已翻开的单元格开始推理
+    pub fn new(layout: &'a Layout, revealed: impl IntoIterator<Item = usize>) -> Self {
+        let mut solver = Self {
+            layout,
+            known: vec![Knowledge::Unknown; layout.mines.len()],
+            steps: 0,
+        };
+
+        for index in revealed {
+            solver.reveal(index);
+        }
+
+        solver
+    }
+
+    // 翻开一个安全的单元格, 空白单元格会像游戏中一样继续翻开周围的单元格
+    pub fn reveal(&mut self, index: usize) {
+        let mut stack = vec![index];
+
+        while let Some(index) = stack.pop() {
+            if self.known[index] == Knowledge::Safe {
+                continue;
+            }
+
+            self.known[index] = Knowledge::Safe;
+            if self.layout.number(index) == 0 {
+                stack.extend(self.layout.neighbors(index));
+            }
+        }
+    }
+
+    // 获取对指定位置的了解
+    pub fn knowledge(&self, index: usize) -> Knowledge {
+        self.known[index]
+    }
+
+    // 已进行的推理轮数
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    // 判断是否所有安全的单元格都已确定
+    pub fn is_solved(&self) -> bool {
+        self.layout
+            .indices()
+            .all(|index| self.layout.is_mine(index) || self.known[index] == Knowledge::Safe)
+    }
+
+    // 收集所有约束: 每个已翻开的数字给出 (周围未知单元格, 其中剩余的地雷数量)
+    fn constraints(&self) -> Vec<(Vec<usize>, u32)> {
+        let mut constraints = self
+            .layout
+            .indices()
+            .filter(|&index| self.known[index] == Knowledge::Safe)
+            .filter_map(|index| {
+                let mut unknown = Vec::new();
+                let mut mines = 0;
+
+                for neighbor in self.layout.neighbors(index) {
+                    match self.known[neighbor] {
+                        Knowledge::Unknown => unknown.push(neighbor),
+                        Knowledge::Mine => mines += 1,
+                        Knowledge::Safe => {}
+                    }
+                }
+
+                (!unknown.is_empty())
+                    .then(|| (unknown, self.layout.number(index).saturating_sub(mines)))
+            })
+            .collect::<Vec<_>>();
+
+        constraints.sort();
+        constraints.dedup();
+        constraints
+    }
+
+    // 进行一轮推理并应用得出的结论, 返回本轮确定的单元格 (为空表示推理卡住)
+    pub fn step(&mut self) -> Vec<(usize, Knowledge)> {
+        let constraints = self.constraints();
+        let mut found = Vec::new();
+
+        // 基本规则: 剩余地雷为0则全部安全, 剩余地雷等于未知单元格数量则全部是地雷
+        let conclude = |cells: &[usize], mines: u32, found: &mut Vec<(usize, Knowledge)>| {
+            if mines == 0 {
+                found.extend(cells.iter().map(|&cell| (cell, Knowledge::Safe)));
+            } else if mines as usize == cells.len() {
+                found.extend(cells.iter().map(|&cell| (cell, Knowledge::Mine)));
+            }
+        };
+
+        for (cells, mines) in &constraints {
+            conclude(cells, *mines, &mut found);
+        }
+
+        // 子集规则: 约束A的单元格是约束B的子集时, B多出的单元格中恰好有 B-A 个地雷
+        if found.is_empty() {
+            for (cells_a, mines_a) in &constraints {
+                for (cells_b, mines_b) in &constraints {
+                    if cells_a.len() < cells_b.len()
+                        && cells_a.iter().all(|cell| cells_b.contains(cell))
+                    {
+                        let rest = cells_b
+                            .iter()
+                            .copied()
+                            .filter(|cell| !cells_a.contains(cell))
+                            .collect::<Vec<_>>();
+                        conclude(&rest, mines_b.saturating_sub(*mines_a), &mut found);
+                    }
+                }
+            }
+        }
+
+        found.sort_by_key(|&(index, _)| index);
+        found.dedup();
+
+        for &(index, knowledge) in &found {
+            match knowledge {
+                Knowledge::Safe => self.reveal(index),
+                _ => self.known[index] = knowledge,
+            }
+        }
+
+        if !found.is_empty() {
+            self.steps += 1;
+        }
+
+        found
+    }
+
+    // 反复推理直到解出或卡住, 返回是否解出
+    pub fn solve(&mut self) -> bool {
+        while !self.is_solved() {
+            if self.step().is_empty() {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    // 估计每个未知单元格是地雷的概率 (只使用玩家可见的信息和地雷总数)
+    // 与数字相邻的单元格取各约束中 剩余地雷/未知单元格 的最大值, 其余单元格取剩余地雷的平均密度
+    pub fn mine_probabilities(&self, mines: usize) -> Vec<(usize, f64)> {
+        let constraints = self.constraints();
+        let unknown = self
+            .layout
+            .indices()
+            .filter(|&index| self.known[index] == Knowledge::Unknown)
+            .collect::<Vec<_>>();
+        let known_mines = self
+            .known
+            .iter()
+            .filter(|&&knowledge| knowledge == Knowledge::Mine)
+            .count();
+        let density = mines.saturating_sub(known_mines) as f64 / unknown.len().max(1) as f64;
+
+        unknown
+            .into_iter()
+            .map(|index| {
+                let probability = constraints
+                    .iter()
+                    .filter(|(cells, _)| cells.contains(&index))
+                    .map(|(cells, remaining)| *remaining as f64 / cells.len() as f64)
+                    .reduce(f64::max)
+                    .unwrap_or(density);
+                (index, probability)
+            })
+            .collect()
+    }
+
+    // 推理卡住时可以翻开的未知安全单元格, 优先返回与已知区域相邻的单元格
+    pub fn guess_candidates(&self) -> Vec<usize> {
+        let unknown = self
+            .layout
+            .indices()
+            .filter(|&index| !self.layout.is_mine(index) && self.known[index] == Knowledge::Unknown)
+            .collect::<Vec<_>>();
+        let frontier = unknown
+            .iter()
+            .copied()
+            .filter(|&index| {
+                self.layout
+                    .neighbors(index)
+                    .any(|neighbor| self.known[neighbor] == Knowledge::Safe)
+            })
+            .collect::<Vec<_>>();
+
+        if frontier.is_empty() {
+            unknown
+        } else {
+            frontier
+        }
+    }
+}
+
+// 布局的难度评估
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Rating {
+    pub bbbv: u32,     // 3BV: 不插旗清除所有安全单元格至少需要的点击次数
+    pub openings: u32, // 空白区域的数量
+    pub steps: u32,    // 推理需要的轮数
+    pub guesses: u32,  // 推理卡住时不得不猜测的次数
+}
+
+impl Rating {
+    // 从第一次点击的位置开始评估布局, 卡住时按固定顺序猜测, 保证同一布局的评估结果相同
+    pub fn of(layout: &Layout, start: usize) -> Self {
+        let mut covered = vec![false; layout.mines.len()];
+        let (mut bbbv, mut openings) = (0, 0);
+
+        // 每个空白区域(连同其边缘的数字)只需要一次点击
+        for index in layout.indices() {
+            if !layout.is_mine(index) && layout.number(index) == 0 && !covered[index] {
+                openings += 1;
+                bbbv += 1;
+                for opened in layout.opening(index) {
+                    covered[opened] = true;
+                }
+            }
+        }
+
+        // 其余的数字单元格各需要一次点击
+        bbbv += layout
+            .indices()
+            .filter(|&index| !layout.is_mine(index) && !covered[index])
+            .count() as u32;
+
+        let mut solver = Solver::new(layout, [start]);
+        let mut guesses = 0;
+        while !solver.solve() {
+            guesses += 1;
+            solver.reveal(solver.guess_candidates()[0]);
+        }
+
+        Self {
+            bbbv,
+            openings,
+            steps: solver.steps(),
+            guesses,
+        }
+    }
+
+    // 综合难度分数: 3BV 越高, 推理轮数越多, 猜测越多则越难, 空白区域越多则越容易
+    pub fn score(&self) -> f64 {
+        let score = self.bbbv as f64 * 0.1 + self.steps as f64 * 0.2 + self.guesses as f64 * 1.5
+            - self.openings as f64 * 0.5;
+
+        (score.max(0.0) * 10.0).round() / 10.0
+    }
+}
+
+// 谜题: 部分单元格已翻开, 剩余部分可以完全靠推理解出
+// 求解器只使用对所有符合已知数字的布局都成立的推理, 完全解出即说明布局是唯一的
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    pub layout: Layout,
+    pub revealed: Vec<usize>, // 初始翻开的单元格
+    pub steps: u32,           // 解出谜题需要的推理轮数
+}
+
+impl Puzzle {
+    // 生成谜题时最多尝试的布局数量, 用完时使用推理轮数最接近目标的谜题
+    const ATTEMPTS: usize = 50;
+
+    // 生成一个可以唯一解出, 且推理轮数在 steps 范围内的谜题 (按推理轮数划分难度)
+    pub fn generate(
+        mask: &BoardMask,
+        mines: usize,
+        steps: RangeInclusive<u32>,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let distance = |puzzle: &Self| {
+            (*steps.start()).saturating_sub(puzzle.steps)
+                + puzzle.steps.saturating_sub(*steps.end())
+        };
+
+        let mut closest: Option<Self> = None;
+        for _ in 0..Self::ATTEMPTS {
+            let puzzle = Self::generate_once(mask, mines, rng);
+            if steps.contains(&puzzle.steps) {
+                return puzzle;
+            }
+            if closest
+                .as_ref()
+                .map_or(true, |closest| distance(&puzzle) < distance(closest))
+            {
+                closest = Some(puzzle);
+            }
+        }
+        closest.expect("at least one attempt")
+    }
+
+    // 随机生成布局, 从一片空白区域开始推理, 卡住时翻开一个安全单元格作为提示, 直到能完全解出
+    // 之后逐个去掉多余的提示 (去掉后仍能完全解出), 使谜题尽量依靠推理
+    fn generate_once(mask: &BoardMask, mines: usize, rng: &mut impl Rng) -> Self {
+        let layout = Layout::random(mask.clone(), mines, rng);
+        let safe = layout
+            .indices()
+            .filter(|&index| !layout.is_mine(index))
+            .collect::<Vec<_>>();
+
+        let openings = safe
+            .iter()
+            .copied()
+            .filter(|&index| layout.number(index) == 0)
+            .collect::<Vec<_>>();
+        let start = *openings
+            .choose(rng)
+            .or_else(|| safe.choose(rng))
+            .expect("board has safe cells");
+
+        // 翻开起点和各个提示所在的区域
+        let reveal = |hints: &[usize]| {
+            let mut revealed = layout.opening(start);
+            for index in hints.iter().flat_map(|&hint| layout.opening(hint)) {
+                if !revealed.contains(&index) {
+                    revealed.push(index);
+                }
+            }
+            revealed
+        };
+        let solves = |hints: &[usize]| Solver::new(&layout, reveal(hints)).solve();
+
+        let mut hints = Vec::new();
+        let mut solver = Solver::new(&layout, reveal(&hints));
+        while !solver.solve() {
+            let hint = *solver
+                .guess_candidates()
+                .choose(rng)
+                .expect("unsolved board has unknown safe cells");
+            hints.push(hint);
+            for index in layout.opening(hint) {
+                solver.reveal(index);
+            }
+        }
+
+        let mut candidates = hints.clone();
+        candidates.shuffle(rng);
+        for hint in candidates {
+            let fewer = hints
+                .iter()
+                .copied()
+                .filter(|&other| other != hint)
+                .collect::<Vec<_>>();
+            if solves(&fewer) {
+                hints = fewer;
+            }
+        }
+
+        // 从最终的初始状态重新推理, 统计需要的轮数
+        let revealed = reveal(&hints);
+        let mut solver = Solver::new(&layout, revealed.iter().copied());
+        solver.solve();
+        let steps = solver.steps();
+
+        Self {
+            layout,
+            revealed,
+            steps,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::game_settings::Shape;
+
+    #[test]
+    fn puzzles_solve_without_guessing_in_the_target_steps() {
+        let mask = BoardMask::from_shape(Shape::Rectangle, 8, 12);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for steps in [0..=14, 15..=28] {
+            let puzzle = Puzzle::generate(&mask, 24, steps.clone(), &mut rng);
+            assert!(steps.contains(&puzzle.steps));
+
+            let mut solver = Solver::new(&puzzle.layout, puzzle.revealed.iter().copied());
+            assert!(solver.solve());
+            assert_eq!(solver.steps(), puzzle.steps);
+            assert!(puzzle
+                .revealed
+                .iter()
+                .all(|&index| !puzzle.layout.is_mine(index)));
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use gloo_storage::{LocalStorage, Storage};
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board_mask::BoardMask,
+    game_logic::{CellInteraction, CellKind},
+    solver::Layout,
+};
+
+// 本地存储中保存训练统计的键
+const STATS_KEY: &str = "trainer_stats";
+
+// 训练用的定式
+// 每个字符表示一个位置: '.' 已翻开的安全单元格, '*' 需要回答的地雷, 's' 需要回答的安全单元格,
+// 'm' 不需要回答的地雷, 'h' 不需要回答的安全单元格, 'f' 已标记的地雷, '_' 空位
+// 定式外侧即棋盘边界
+pub struct Pattern {
+    pub name: &'static str,
+    rows: &'static [&'static str],
+}
+
+pub const PATTERNS: [Pattern; 8] = [
+    Pattern {
+        name: "1-1",
+        rows: &["mhs", ".._"],
+    },
+    Pattern {
+        name: "1-2",
+        rows: &["hm*", ".._"],
+    },
+    Pattern {
+        name: "1-2-1",
+        rows: &["s*s*s", "_..._"],
+    },
+    Pattern {
+        name: "1-2-2-1",
+        rows: &["ss**ss", "_...._"],
+    },
+    Pattern {
+        name: "1-1 (reduced)",
+        rows: &["fmhs", "_.._"],
+    },
+    Pattern {
+        name: "1-2 (reduced)",
+        rows: &["fhm*", "_.._"],
+    },
+    Pattern {
+        name: "Hole",
+        rows: &["...", ".*.", "..."],
+    },
+    Pattern {
+        name: "Double hole",
+        rows: &["....", ".*s.", "...."],
+    },
+];
+
+// 训练题中的单元格
+#[derive(Copy, Clone)]
+pub struct ExerciseCell {
+    pub kind: CellKind,               // 单元格类型
+    pub interaction: CellInteraction, // 初始交互状态
+    pub question: bool,               // 是否需要回答
+}
+
+// 一道训练题: 随机旋转或翻转后的定式
+#[derive(Clone)]
+pub struct Exercise {
+    pub pattern: usize, // 定式在 PATTERNS 中的索引
+    pub rows: isize,
+    pub columns: isize,
+    pub cells: Vec<Option<ExerciseCell>>, // 按行存储, None 表示空位
+}
+
+impl Exercise {
+    // 根据统计结果选择定式 (薄弱的定式出现得更频繁), 并随机旋转或翻转
+    pub fn random(stats: &TrainerStats, rng: &mut impl Rng) -> Self {
+        let pattern = *(0..PATTERNS.len())
+            .collect::<Vec<_>>()
+            .choose_weighted(rng, |&pattern| stats.get(PATTERNS[pattern].name).weight())
+            .expect("weights are positive");
+
+        Self::new(pattern, rng.gen_range(0..8))
+    }
+
+    // 生成定式的一种变体, symmetry 的三个二进制位分别表示上下翻转, 左右翻转和转置
+    pub fn new(pattern: usize, symmetry: u8) -> Self {
+        let text = PATTERNS[pattern].rows;
+        let (rows, columns) = (text.len() as isize, text[0].len() as isize);
+        let (new_rows, new_columns) = if symmetry & 4 != 0 {
+            (columns, rows)
+        } else {
+            (rows, columns)
+        };
+
+        // 计算原位置在变换后的索引
+        let transform = |row: isize, column: isize| {
+            let (row, column) = if symmetry & 4 != 0 {
+                (column, row)
+            } else {
+                (row, column)
+            };
+            let row = if symmetry & 1 != 0 {
+                new_rows - 1 - row
+            } else {
+                row
+            };
+            let column = if symmetry & 2 != 0 {
+                new_columns - 1 - column
+            } else {
+                column
+            };
+            (row * new_columns + column) as usize
+        };
+
+        let size = (rows * columns) as usize;
+        let (mut exists, mut mines, mut symbols) =
+            (vec![false; size], vec![false; size], vec!['_'; size]);
+        for (row, line) in text.iter().enumerate() {
+            for (column, symbol) in line.chars().enumerate() {
+                let index = transform(row as isize, column as isize);
+                exists[index] = symbol != '_';
+                mines[index] = matches!(symbol, '*' | 'm' | 'f');
+                symbols[index] = symbol;
+            }
+        }
+
+        // 数字由地雷布局计算得出, 保证与定式一致
+        let layout = Layout::new(BoardMask::from_cells(new_rows, new_columns, exists), mines);
+        let cells = symbols
+            .iter()
+            .enumerate()
+            .map(|(index, &symbol)| {
+                let interaction = match symbol {
+                    '_' => return None,
+                    '.' => CellInteraction::Cleared,
+                    'f' => CellInteraction::Flagged(1),
+                    _ => CellInteraction::Untouched,
+                };
+
+                Some(ExerciseCell {
+                    kind: layout.kind(index),
+                    interaction,
+                    question: matches!(symbol, '*' | 's'),
+                })
+            })
+            .collect();
+
+        Self {
+            pattern,
+            rows: new_rows,
+            columns: new_columns,
+            cells,
+        }
+    }
+
+    // 定式名称
+    pub fn name(&self) -> &'static str {
+        PATTERNS[self.pattern].name
+    }
+}
+
+// 单个定式的训练统计
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PatternStats {
+    pub attempts: u32,     // 练习次数
+    pub correct: u32,      // 回答正确的次数
+    pub total_millis: i64, // 累计用时(毫秒)
+}
+
+impl PatternStats {
+    // 正确率, 没有练习过时为0
+    pub fn accuracy(&self) -> f64 {
+        if self.attempts == 0 {
+            0.0
+        } else {
+            self.correct as f64 / self.attempts as f64
+        }
+    }
+
+    // 平均用时(秒)
+    pub fn average_seconds(&self) -> f64 {
+        if self.attempts == 0 {
+            0.0
+        } else {
+            self.total_millis as f64 / self.attempts as f64 / 1000.0
+        }
+    }
+
+    // 出题权重: 正确率越低, 用时越长, 出现得越频繁
+    fn weight(&self) -> f64 {
+        1.0 + 3.0 * (1.0 - self.accuracy()) + (self.average_seconds() / 10.0).min(2.0)
+    }
+}
+
+// 所有定式的训练统计, 保存在浏览器的本地存储中
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TrainerStats(HashMap<String, PatternStats>);
+
+impl TrainerStats {
+    // 从本地存储读取统计
+    pub fn load() -> Self {
+        LocalStorage::get(STATS_KEY).unwrap_or_default()
+    }
+
+    // 将统计写入本地存储
+    pub fn save(&self) {
+        let _ = LocalStorage::set(STATS_KEY, self);
+    }
+
+    // 获取指定定式的统计
+    pub fn get(&self, name: &str) -> PatternStats {
+        self.0.get(name).copied().unwrap_or_default()
+    }
+
+    // 记录一次练习结果
+    pub fn record(&mut self, name: &str, correct: bool, millis: i64) {
+        let stats = self.0.entry(name.to_string()).or_default();
+        stats.attempts += 1;
+        stats.correct += correct as u32;
+        stats.total_millis += millis;
+    }
+}
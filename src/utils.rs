@@ -14,3 +14,8 @@ pub fn to_time(seconds: i64) -> String {
         duration.num_seconds() % 60
     )
 }
+
+// 获取当前时间(毫秒), 用于统计用时和记录回放
+pub fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
@@ -0,0 +1,153 @@
+use std::ops::RangeInclusive;
+
+use leptos::ServerFnError;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+// 用户名长度范围
+pub const USERNAME_BOUNDS: RangeInclusive<usize> = 3..=10;
+
+// 提交成绩时的验证错误类型, 客户端和服务器函数使用同样的规则
+#[derive(Error, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ValidationError {
+    #[error(
+        "Username must be {} to {} characters long",
+        USERNAME_BOUNDS.start(),
+        USERNAME_BOUNDS.end()
+    )]
+    UsernameLength,
+    #[error("Username may only contain letters and underscores")]
+    UsernameChars,
+    #[error("Time must be greater than zero")]
+    Time,
+}
+
+// 服务器函数以这个前缀加错误代码返回验证错误, 客户端据此还原出 ValidationError
+const ERROR_CODE_PREFIX: &str = "validation:";
+
+impl ValidationError {
+    // 所有验证错误, 用于从错误代码还原
+    const ALL: [Self; 3] = [Self::UsernameLength, Self::UsernameChars, Self::Time];
+
+    // 错误代码, 不随显示给玩家的说明变化
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UsernameLength => "username_length",
+            Self::UsernameChars => "username_chars",
+            Self::Time => "time",
+        }
+    }
+
+    // 转换为服务器函数的错误, 只携带错误代码
+    pub fn into_server_error(self) -> ServerFnError {
+        ServerFnError::ServerError(format!("{ERROR_CODE_PREFIX}{}", self.code()))
+    }
+
+    // 从服务器函数的错误中还原验证错误, 其他错误返回 None
+    pub fn from_server_error(error: &ServerFnError) -> Option<Self> {
+        let ServerFnError::ServerError(message) = error else {
+            return None;
+        };
+        let code = message.strip_prefix(ERROR_CODE_PREFIX)?;
+        Self::ALL.into_iter().find(|error| error.code() == code)
+    }
+}
+
+// 服务器函数的错误显示给玩家的原因: 验证错误显示对应的说明, 其他错误显示服务器给出的原因,
+// 无法连接服务器时返回 None
+pub fn error_message(error: &ServerFnError) -> Option<String> {
+    match (ValidationError::from_server_error(error), error) {
+        (Some(error), _) => Some(error.to_string()),
+        (None, ServerFnError::ServerError(message)) => Some(message.clone()),
+        (None, _) => None,
+    }
+}
+
+// 验证用户名字符是否合法
+pub fn valid_chars(username: &str) -> bool {
+    username
+        .chars()
+        .all(|c| c.is_ascii_alphabetic() || c == '_')
+}
+
+// 验证用户名的长度和字符
+pub fn validate_username(username: &str) -> Result<(), ValidationError> {
+    if !USERNAME_BOUNDS.contains(&username.len()) {
+        return Err(ValidationError::UsernameLength);
+    }
+    if !valid_chars(username) {
+        return Err(ValidationError::UsernameChars);
+    }
+    Ok(())
+}
+
+// 验证一条成绩: 用户名合法且用时(或限时模式的剩余时间)为正数
+pub fn validate_score(username: &str, time_in_seconds: i64) -> Result<(), ValidationError> {
+    validate_username(username)?;
+    if time_in_seconds <= 0 {
+        return Err(ValidationError::Time);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn usernames_follow_the_length_and_character_rules() {
+        assert_eq!(validate_username("quan_mine"), Ok(()));
+        assert_eq!(
+            validate_username("ab"),
+            Err(ValidationError::UsernameLength)
+        );
+        assert_eq!(
+            validate_username(&"a".repeat(10_000)),
+            Err(ValidationError::UsernameLength)
+        );
+        assert_eq!(validate_username(""), Err(ValidationError::UsernameLength));
+        assert_eq!(
+            validate_username("quan mine"),
+            Err(ValidationError::UsernameChars)
+        );
+        assert_eq!(
+            validate_username("quan42"),
+            Err(ValidationError::UsernameChars)
+        );
+    }
+
+    #[test]
+    fn scores_need_a_valid_name_and_a_positive_time() {
+        assert_eq!(validate_score("quan", 1), Ok(()));
+        assert_eq!(validate_score("quan", 0), Err(ValidationError::Time));
+        assert_eq!(validate_score("quan", -5), Err(ValidationError::Time));
+        // 用户名先于用时检查
+        assert_eq!(validate_score("q", 0), Err(ValidationError::UsernameLength));
+    }
+
+    #[test]
+    fn validation_errors_round_trip_through_server_errors() {
+        for error in ValidationError::ALL {
+            let server_error = error.clone().into_server_error();
+            assert_eq!(
+                ValidationError::from_server_error(&server_error),
+                Some(error.clone())
+            );
+            assert_eq!(error_message(&server_error), Some(error.to_string()));
+        }
+    }
+
+    #[test]
+    fn other_server_errors_keep_their_message() {
+        let server_error = ServerFnError::ServerError("replay is not a win".into());
+        assert_eq!(ValidationError::from_server_error(&server_error), None);
+        assert_eq!(
+            error_message(&server_error),
+            Some("replay is not a win".into())
+        );
+        assert_eq!(
+            error_message(&ServerFnError::Request("offline".into())),
+            None
+        );
+    }
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{game_logic::GameStatus, room::CellUpdate};
+
+// 服务器验证的游戏中一次操作的结果, 只包含已翻开的单元格
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VerifiedMove {
+    pub cells: Vec<CellUpdate>,   // 本次操作改变的单元格
+    pub status: GameStatus,       // 操作后的游戏状态
+    pub cleared: usize,           // 已清除的单元格数量
+    pub time_millis: Option<i64>, // 服务器记录的用时 (获胜后才有)
+}
+
+// 服务器验证的游戏错误类型
+#[derive(Error, Clone, Debug, PartialEq)]
+pub enum VerifiedError {
+    #[error("This game has ended or expired, start a new one")]
+    NotFound,
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rand::{distributions::Alphanumeric, Rng};
+
+use crate::{
+    game_logic::GameStatus,
+    game_settings::{Difficulty, Mode, Size},
+    room::{BoardSnapshot, RoomBoard},
+    solver::Rating,
+    verified::{VerifiedError, VerifiedMove},
+};
+
+// 超过这段时间没有操作的游戏会被删除
+const STALE_AFTER: Duration = Duration::from_secs(30 * 60);
+
+// 一局由服务器维护的游戏: 布局只保存在服务器上, 用时由服务器记录
+pub struct VerifiedGame {
+    username: String,
+    board: RoomBoard,
+    columns: isize,
+    start: Option<usize>,        // 第一次挖掘的位置, 用于评估布局难度
+    started_at: Option<Instant>, // 第一次挖掘的时刻
+    last_active: Instant,
+}
+
+// 所有进行中的验证游戏, 以游戏编号为键 (编号同时作为令牌, 只有创建者知道)
+pub type VerifiedGames = Arc<Mutex<HashMap<String, VerifiedGame>>>;
+
+// 获胜的验证游戏的成绩, 由服务器函数写入排行榜
+pub struct VerifiedScore {
+    pub username: String,
+    pub difficulty: Difficulty,
+    pub size: Size,
+    pub time_millis: i64,
+    pub cells_cleared: usize,
+    pub rating: Option<Rating>,
+}
+
+// 开始一局新的验证游戏 (经典模式), 返回游戏编号和空白棋盘
+pub fn start(
+    games: &VerifiedGames,
+    username: String,
+    difficulty: Difficulty,
+    size: Size,
+) -> (String, BoardSnapshot) {
+    let id = rand::thread_rng()
+        .sample_iter(Alphanumeric)
+        .take(24)
+        .map(char::from)
+        .collect::<String>();
+    let board = RoomBoard::new(difficulty, size, Mode::Classic);
+    let snapshot = board.snapshot();
+
+    let mut games = games.lock().expect("verified games lock is not poisoned");
+    games.retain(|_, game| game.last_active.elapsed() < STALE_AFTER);
+    games.insert(
+        id.clone(),
+        VerifiedGame {
+            username,
+            board,
+            columns: snapshot.columns,
+            start: None,
+            started_at: None,
+            last_active: Instant::now(),
+        },
+    );
+    (id, snapshot)
+}
+
+// 挖开单元格, 获胜时同时返回成绩; 游戏结束后删除
+pub fn dig(
+    games: &VerifiedGames,
+    id: &str,
+    row: isize,
+    column: isize,
+) -> Result<(VerifiedMove, Option<VerifiedScore>), VerifiedError> {
+    let mut games = games.lock().expect("verified games lock is not poisoned");
+    let game = games.get_mut(id).ok_or(VerifiedError::NotFound)?;
+    game.last_active = Instant::now();
+
+    let cells = game.board.dig(row, column, None, &mut rand::thread_rng());
+    if game.started_at.is_none() && game.board.status() == GameStatus::Started {
+        game.start = Some((row * game.columns + column) as usize);
+        game.started_at = Some(Instant::now());
+    }
+
+    let status = game.board.status();
+    let time_millis = game
+        .started_at
+        .filter(|_| status == GameStatus::Victory)
+        .map(|started_at| started_at.elapsed().as_millis() as i64);
+    let score = time_millis.map(|time_millis| {
+        let (difficulty, size, _) = game.board.params();
+        VerifiedScore {
+            username: game.username.clone(),
+            difficulty,
+            size,
+            time_millis,
+            cells_cleared: game.board.cleared(),
+            rating: game
+                .board
+                .layout()
+                .zip(game.start)
+                .map(|(layout, start)| Rating::of(layout, start)),
+        }
+    });
+    let verified_move = VerifiedMove {
+        cells,
+        status,
+        cleared: game.board.cleared(),
+        time_millis,
+    };
+
+    if status.is_finished() {
+        games.remove(id);
+    }
+    Ok((verified_move, score))
+}
+
+// 标记或取消标记单元格
+pub fn flag(
+    games: &VerifiedGames,
+    id: &str,
+    row: isize,
+    column: isize,
+) -> Result<VerifiedMove, VerifiedError> {
+    let mut games = games.lock().expect("verified games lock is not poisoned");
+    let game = games.get_mut(id).ok_or(VerifiedError::NotFound)?;
+    game.last_active = Instant::now();
+
+    Ok(VerifiedMove {
+        cells: game.board.flag(row, column),
+        status: game.board.status(),
+        cleared: game.board.cleared(),
+        time_millis: None,
+    })
+}
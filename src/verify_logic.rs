@@ -0,0 +1,855 @@
+use std::collections::HashMap;
+
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    board_mask::BoardMask,
+    endless_logic::{chunk_mines, chunk_of, index_in_chunk, ChunkPos},
+    game_logic::{generate_kinds, CellInteraction, CellKind, GameState, GameStatus, ADJACENTS},
+    game_settings::{Difficulty, Mode, Shape, Size},
+    replay::{EndlessReplay, MoveKind, ParseReplayError, Replay, ScoreReplay},
+    review_logic::RunAnalysis,
+    solver::{Layout, Rating},
+};
+
+// 可以通过回放验证成绩的模式 (矩形棋盘上的单局游戏)
+pub const REPLAY_MODES: [Mode; 5] = [
+    Mode::Classic,
+    Mode::MultiMine,
+    Mode::Lives,
+    Mode::TimeAttack,
+    Mode::Memory,
+];
+
+// 一块棋盘的回放最多包含的操作数量 (最大的棋盘只有216个单元格, 正常的对局远少于此)
+pub const MAX_REPLAY_MOVES: usize = 2_000;
+// 无尽模式的回放最多包含的操作数量
+pub const MAX_ENDLESS_MOVES: usize = 50_000;
+// 无尽模式最多生成的区块数量 (160000个单元格), 超过时说明回放探索的范围过大
+const MAX_ENDLESS_CHUNKS: usize = 2_500;
+// 每次操作在回放文本中最多占用的字节数 (所有数字都取最长时的长度)
+const MAX_MOVE_BYTES: usize = 64;
+
+// 回放验证错误类型
+#[derive(Error, Clone, Debug, PartialEq)]
+pub enum ReplayError {
+    #[error("The replay could not be read")]
+    Invalid(#[from] ParseReplayError),
+    #[error("The replay is not on this board")]
+    WrongBoard,
+    #[error("The first dig is not on the marked cell")]
+    WrongStart,
+    #[error("The replay does not include every board")]
+    MissingBoards,
+    #[error("Scores in this mode cannot be verified")]
+    UnsupportedMode,
+    #[error("The replay contains a move outside the board")]
+    OutOfBounds,
+    #[error("The replay contains moves after the game ended")]
+    MovesAfterEnd,
+    #[error("The replay does not end in victory")]
+    NotWon,
+    #[error("The replay ends in victory")]
+    Won,
+    #[error("A score for this run has already been submitted")]
+    AlreadySubmitted,
+    #[error("The replay is too long to verify")]
+    TooLarge,
+}
+
+// 在解析之前拒绝过长的回放文本, max_moves 为回放最多包含的操作数量 (种子另占一次操作的长度)
+pub fn check_replay_len(replay: &str, max_moves: usize) -> Result<(), ReplayError> {
+    if replay.len() > (max_moves + 1) * MAX_MOVE_BYTES {
+        return Err(ReplayError::TooLarge);
+    }
+    Ok(())
+}
+
+// 回放验证通过后由服务器得出的成绩
+#[derive(Clone, Debug, PartialEq)]
+pub struct VerifiedRun {
+    pub time_in_seconds: i64, // 计入排行榜的秒数, 与客户端显示的规则相同
+    pub lives_used: u32,
+    pub cells_cleared: i64,
+    pub rating: Option<Rating>,
+    pub analysis: RunAnalysis, // 用于发现可疑成绩的分析
+    pub ghost: Replay,         // 每次挖掘翻开的单元格, 用作幽灵比赛的回放
+}
+
+// 由服务器保存布局的对局进行到最后一步时的局面, 客户端据此翻开单元格
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplayPosition {
+    pub status: GameStatus,
+    pub cells: Vec<(usize, CellKind)>, // 已翻开的单元格的类型, 结束后为整个布局
+    pub mine_cells: isize,             // 含有地雷的单元格数量 (第一次挖掘之前为0)
+}
+
+// 不依赖界面的游戏规则, 与 GameState 的挖掘和插旗规则一致, 用于在服务器上重新进行一局
+struct HeadlessGame {
+    mask: BoardMask,
+    columns: isize,
+    mines: isize,
+    max_mines_per_cell: u32,
+    lives: Option<u32>,
+    time_limit: Option<i64>, // 限时模式的初始时间(秒)
+    seed: u64,
+    kinds: Vec<CellKind>,
+    layout: Option<Layout>, // 单雷模式下的地雷布局, 用于评估难度和分析猜测
+    interactions: Vec<CellInteraction>,
+    status: GameStatus,
+    start: Option<usize>, // 第一次挖掘的位置
+    cleared: isize,
+    mine_cells: isize,
+    lives_used: u32,
+    newly_revealed: Vec<usize>, // 本次挖掘翻开的单元格
+    opened: bool,               // 本次挖掘是否清除了空白区域
+    openings: i64,              // 开始后清除空白区域的次数 (限时模式的奖励时间)
+    peeks: i64,                 // 重新查看数字的次数 (记忆模式的加时)
+}
+
+impl HeadlessGame {
+    fn new(difficulty: Difficulty, size: Size, mode: Mode, seed: u64) -> Self {
+        let (rows, columns) = GameState::size_dimensions(size);
+        let mask = BoardMask::from_shape(Shape::Rectangle, rows, columns);
+        let mines = (mask.count() as f64 * GameState::mine_probability(difficulty)) as isize;
+
+        Self {
+            mask,
+            columns,
+            mines,
+            max_mines_per_cell: match mode {
+                Mode::MultiMine => GameState::MULTI_MINE_MAX,
+                _ => 1,
+            },
+            lives: (mode == Mode::Lives).then_some(GameState::LIVES),
+            time_limit: (mode == Mode::TimeAttack).then(|| GameState::time_limit(size)),
+            seed,
+            kinds: vec![Default::default(); (rows * columns) as usize],
+            layout: None,
+            interactions: vec![Default::default(); (rows * columns) as usize],
+            status: GameStatus::Idle,
+            start: None,
+            cleared: 0,
+            mine_cells: 0,
+            lives_used: 0,
+            newly_revealed: Vec::new(),
+            opened: false,
+            openings: 0,
+            peeks: 0,
+        }
+    }
+
+    // 获取指定位置的索引 (棋盘之外没有索引)
+    fn index(&self, row: isize, column: isize) -> Option<usize> {
+        self.mask
+            .contains(row, column)
+            .then_some((row * self.columns + column) as usize)
+    }
+
+    // 挖开单元格, 第一次挖掘时用回放的种子生成布局
+    fn dig(&mut self, row: isize, column: isize) {
+        let started = self.status != GameStatus::Idle;
+        if !started {
+            self.kinds = generate_kinds(
+                &self.mask,
+                self.mines,
+                self.max_mines_per_cell,
+                (row, column),
+                &mut StdRng::seed_from_u64(self.seed),
+            );
+            self.mine_cells = self
+                .kinds
+                .iter()
+                .filter(|kind| matches!(kind, CellKind::Mine(_)))
+                .count() as isize;
+            self.layout = (self.max_mines_per_cell == 1).then(|| {
+                Layout::new(
+                    self.mask.clone(),
+                    self.kinds
+                        .iter()
+                        .map(|kind| matches!(kind, CellKind::Mine(_)))
+                        .collect(),
+                )
+            });
+            self.start = self.index(row, column);
+            self.status = GameStatus::Started;
+        }
+
+        self.opened = false;
+        self.dig_inner(row, column);
+        if started && self.opened {
+            self.openings += 1;
+        }
+
+        if self.status == GameStatus::Started
+            && self.cleared == self.mask.count() as isize - self.mine_cells
+        {
+            self.status = GameStatus::Victory;
+        }
+    }
+
+    fn dig_inner(&mut self, row: isize, column: isize) {
+        let Some(index) = self.index(row, column) else {
+            return;
+        };
+
+        match self.interactions[index] {
+            CellInteraction::Untouched => match self.kinds[index] {
+                // 生命模式下踩到地雷消耗一条生命, 生命耗尽时游戏结束
+                CellKind::Mine(_) if self.lives.is_some() => {
+                    self.interactions[index] = CellInteraction::Exploded;
+                    self.lives_used += 1;
+                    if self
+                        .lives
+                        .map(|lives| lives.saturating_sub(self.lives_used))
+                        == Some(0)
+                    {
+                        self.status = GameStatus::GameOver;
+                    }
+                }
+                CellKind::Mine(_) => {
+                    self.interactions[index] = CellInteraction::Cleared;
+                    self.status = GameStatus::GameOver;
+                }
+                CellKind::Clear(mines) => {
+                    self.interactions[index] = CellInteraction::Cleared;
+                    self.cleared += 1;
+                    self.newly_revealed.push(index);
+
+                    if mines == 0 {
+                        self.opened = true;
+                        for (row_offset, column_offset) in ADJACENTS {
+                            self.dig_inner(row + row_offset, column + column_offset);
+                        }
+                    }
+                }
+            },
+
+            // 周围的旗子数量等于数字时, 挖开周围未被挖开的单元格
+            CellInteraction::Cleared => {
+                let CellKind::Clear(mines) = self.kinds[index] else {
+                    return;
+                };
+                let neighbors = ADJACENTS
+                    .iter()
+                    .filter_map(|(row_offset, column_offset)| {
+                        let (row, column) = (row + row_offset, column + column_offset);
+                        self.index(row, column).map(|index| (row, column, index))
+                    })
+                    .collect::<Vec<_>>();
+                let flags: u32 = neighbors
+                    .iter()
+                    .map(|&(_, _, index)| match self.interactions[index] {
+                        CellInteraction::Flagged(flags) => flags,
+                        CellInteraction::Exploded => match self.kinds[index] {
+                            CellKind::Mine(mines) => mines,
+                            CellKind::Clear(_) => 0,
+                        },
+                        _ => 0,
+                    })
+                    .sum();
+
+                if mines == flags {
+                    for (row, column, index) in neighbors {
+                        if matches!(self.interactions[index], CellInteraction::Untouched) {
+                            self.dig_inner(row, column);
+                        }
+                    }
+                }
+            }
+
+            CellInteraction::Flagged(_) | CellInteraction::Exploded => {}
+        }
+    }
+
+    // 已翻开的单元格
+    fn revealed(&self) -> impl Iterator<Item = usize> + '_ {
+        self.interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| matches!(interaction, CellInteraction::Cleared))
+            .map(|(index, _)| index)
+    }
+
+    // 限时模式下指定时间(毫秒)的剩余秒数, 包括清除空白区域的奖励时间
+    fn remaining_seconds(&self, millis: i64) -> Option<i64> {
+        self.time_limit
+            .map(|limit| limit + self.openings * GameState::OPENING_BONUS - millis / 1000)
+    }
+
+    // 插旗或拔旗, 多雷模式下循环切换旗帜数量
+    fn flag(&mut self, index: usize) {
+        self.interactions[index] = match self.interactions[index] {
+            CellInteraction::Untouched => CellInteraction::Flagged(1),
+            CellInteraction::Flagged(flags) if flags < self.max_mines_per_cell => {
+                CellInteraction::Flagged(flags + 1)
+            }
+            CellInteraction::Flagged(_) => CellInteraction::Untouched,
+            interaction => interaction,
+        };
+    }
+}
+
+// 在服务器上重新进行回放中的一局, 返回结束时的游戏, 对这一局的分析和幽灵回放
+fn replay_game(
+    difficulty: Difficulty,
+    size: Size,
+    mode: Mode,
+    replay: &ScoreReplay,
+) -> Result<(HeadlessGame, RunAnalysis, Replay), ReplayError> {
+    if !REPLAY_MODES.contains(&mode) {
+        return Err(ReplayError::UnsupportedMode);
+    }
+    if replay.moves.len() > MAX_REPLAY_MOVES {
+        return Err(ReplayError::TooLarge);
+    }
+
+    let mut game = HeadlessGame::new(difficulty, size, mode, replay.seed);
+    let mut analysis = RunAnalysis::default();
+    let mut ghost = Replay::default();
+    let mut last_millis = None; // 上一次挖掘或插旗的时间
+
+    for replay_move in &replay.moves {
+        // 限时模式下剩余时间归零后游戏超时
+        if game.status == GameStatus::Started
+            && game
+                .remaining_seconds(replay_move.millis)
+                .is_some_and(|remaining| remaining <= 0)
+        {
+            game.status = GameStatus::TimedOut;
+        }
+        if game.status.is_finished() {
+            return Err(ReplayError::MovesAfterEnd);
+        }
+
+        let (row, column) = (
+            replay_move.index as isize / game.columns,
+            replay_move.index as isize % game.columns,
+        );
+        if replay_move.kind != MoveKind::Peek && game.index(row, column).is_none() {
+            return Err(ReplayError::OutOfBounds);
+        }
+
+        // 记录操作间隔, 并分析第一次挖掘之后每次挖开未翻开单元格的操作
+        if replay_move.kind != MoveKind::Peek {
+            if let Some(last_millis) = last_millis {
+                analysis.record_interval(replay_move.millis - last_millis);
+            }
+            last_millis = Some(replay_move.millis);
+        }
+        if let (MoveKind::Dig, Some(layout)) = (replay_move.kind, &game.layout) {
+            if matches!(
+                game.interactions[replay_move.index],
+                CellInteraction::Untouched
+            ) {
+                analysis.record_dig(
+                    layout,
+                    game.mine_cells as usize,
+                    game.revealed(),
+                    replay_move.index,
+                );
+            }
+        }
+
+        match replay_move.kind {
+            MoveKind::Dig => {
+                game.dig(row, column);
+                ghost.record(replay_move.millis, std::mem::take(&mut game.newly_revealed));
+            }
+            MoveKind::Flag => game.flag(replay_move.index),
+            MoveKind::Peek if mode == Mode::Memory && game.status == GameStatus::Started => {
+                game.peeks += 1
+            }
+            MoveKind::Peek => {}
+        }
+    }
+
+    Ok((game, analysis, ghost))
+}
+
+// 在服务器上重新进行回放中的一局, 获胜时按回放的时间得出成绩
+pub fn verify_replay(
+    difficulty: Difficulty,
+    size: Size,
+    mode: Mode,
+    replay: &ScoreReplay,
+) -> Result<VerifiedRun, ReplayError> {
+    let (game, analysis, ghost) = replay_game(difficulty, size, mode, replay)?;
+    if game.status != GameStatus::Victory {
+        return Err(ReplayError::NotWon);
+    }
+
+    // 用时以最后一次操作 (获胜的一步) 为准
+    let millis = replay
+        .moves
+        .last()
+        .map_or(0, |replay_move| replay_move.millis);
+    let time_in_seconds = match game.remaining_seconds(millis) {
+        Some(remaining) => remaining.max(0),
+        None => millis / 1000 + game.peeks * GameState::MEMORY_PEEK_PENALTY,
+    };
+    let rating = game
+        .layout
+        .as_ref()
+        .zip(game.start)
+        .map(|(layout, start)| Rating::of(layout, start));
+
+    Ok(VerifiedRun {
+        time_in_seconds,
+        lives_used: game.lives_used,
+        cells_cleared: game.cleared as i64,
+        rating,
+        analysis,
+        ghost,
+    })
+}
+
+// 在服务器上进行到回放的最后一步, 只返回客户端可以看到的单元格; 第一次挖掘必须在指定的起点上
+pub fn replay_position(
+    difficulty: Difficulty,
+    size: Size,
+    mode: Mode,
+    start: usize,
+    replay: &ScoreReplay,
+) -> Result<ReplayPosition, ReplayError> {
+    let (game, _, _) = replay_game(difficulty, size, mode, replay)?;
+    if game.start.is_some_and(|first_dig| first_dig != start) {
+        return Err(ReplayError::WrongStart);
+    }
+
+    let cells = if game.status.is_finished() {
+        game.kinds.iter().copied().enumerate().collect()
+    } else {
+        game.revealed()
+            .map(|index| (index, game.kinds[index]))
+            .collect()
+    };
+
+    Ok(ReplayPosition {
+        status: game.status,
+        cells,
+        mine_cells: game.mine_cells,
+    })
+}
+
+// 在服务器上重新进行一局失败的回放, 只得出分析 (用于统计五五开的胜率)
+pub fn analyze_loss(
+    difficulty: Difficulty,
+    size: Size,
+    mode: Mode,
+    replay: &ScoreReplay,
+) -> Result<RunAnalysis, ReplayError> {
+    let (game, analysis, _) = replay_game(difficulty, size, mode, replay)?;
+    if game.status == GameStatus::Victory {
+        return Err(ReplayError::Won);
+    }
+    Ok(analysis)
+}
+
+// 不依赖界面的无尽模式规则, 与 EndlessState 的挖掘和插旗规则一致
+struct HeadlessEndless {
+    seed: u64,
+    probability: f64,                                       // 每个单元格是地雷的概率
+    safe_center: Option<(isize, isize)>,                    // 第一次挖掘的位置, 其周围不会有地雷
+    mines: HashMap<ChunkPos, Vec<bool>>,                    // 已生成的区块地雷布局
+    interactions: HashMap<(isize, isize), CellInteraction>, // 交互过的单元格
+    cleared: isize,
+    status: GameStatus,
+}
+
+impl HeadlessEndless {
+    fn new(difficulty: Difficulty, seed: u64) -> Self {
+        Self {
+            seed,
+            probability: GameState::mine_probability(difficulty),
+            safe_center: None,
+            mines: HashMap::new(),
+            interactions: HashMap::new(),
+            cleared: 0,
+            status: GameStatus::Idle,
+        }
+    }
+
+    // 判断指定位置是否为地雷 (第一次挖掘及其相邻区域除外)
+    fn is_mine(&mut self, row: isize, column: isize) -> bool {
+        if let Some((safe_row, safe_column)) = self.safe_center {
+            if (row - safe_row).abs() <= 1 && (column - safe_column).abs() <= 1 {
+                return false;
+            }
+        }
+
+        let chunk = chunk_of(row, column);
+        let (seed, probability) = (self.seed, self.probability);
+        self.mines
+            .entry(chunk)
+            .or_insert_with(|| chunk_mines(seed, chunk, probability))[index_in_chunk(row, column)]
+    }
+
+    // 计算指定位置周围的地雷数量
+    fn adjacent_mines(&mut self, row: isize, column: isize) -> u32 {
+        ADJACENTS
+            .iter()
+            .filter(|(row_offset, column_offset)| {
+                self.is_mine(row + row_offset, column + column_offset)
+            })
+            .count() as u32
+    }
+
+    fn interaction(&self, row: isize, column: isize) -> CellInteraction {
+        self.interactions
+            .get(&(row, column))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    // 挖开单元格, 已挖开的单元格周围的旗子数量等于数字时挖开周围的单元格
+    // 生成的区块超过上限时停止, 避免一次连锁翻开没有边界的区域
+    fn dig(&mut self, row: isize, column: isize) -> Result<(), ReplayError> {
+        if self.status == GameStatus::Idle {
+            self.safe_center = Some((row, column));
+            self.status = GameStatus::Started;
+        }
+
+        let mut pending = match self.interaction(row, column) {
+            CellInteraction::Cleared if !self.is_mine(row, column) => {
+                let neighbors = ADJACENTS
+                    .map(|(row_offset, column_offset)| (row + row_offset, column + column_offset));
+                let flags = neighbors
+                    .iter()
+                    .filter(|&&(row, column)| {
+                        matches!(self.interaction(row, column), CellInteraction::Flagged(_))
+                    })
+                    .count() as u32;
+
+                if flags == self.adjacent_mines(row, column) {
+                    neighbors.to_vec()
+                } else {
+                    Vec::new()
+                }
+            }
+            CellInteraction::Cleared => Vec::new(),
+            _ => vec![(row, column)],
+        };
+
+        while let Some((row, column)) = pending.pop() {
+            if self.mines.len() > MAX_ENDLESS_CHUNKS {
+                return Err(ReplayError::TooLarge);
+            }
+            if !matches!(self.interaction(row, column), CellInteraction::Untouched) {
+                continue;
+            }
+
+            self.interactions
+                .insert((row, column), CellInteraction::Cleared);
+            if self.is_mine(row, column) {
+                self.status = GameStatus::GameOver;
+                return Ok(());
+            }
+            if self.adjacent_mines(row, column) == 0 {
+                pending.extend(
+                    ADJACENTS.iter().map(|(row_offset, column_offset)| {
+                        (row + row_offset, column + column_offset)
+                    }),
+                );
+            }
+            self.cleared += 1;
+        }
+
+        Ok(())
+    }
+
+    // 插旗或拔旗
+    fn flag(&mut self, row: isize, column: isize) {
+        let interaction = match self.interaction(row, column) {
+            CellInteraction::Untouched => CellInteraction::Flagged(1),
+            CellInteraction::Flagged(_) => CellInteraction::Untouched,
+            _ => return,
+        };
+        self.interactions.insert((row, column), interaction);
+    }
+}
+
+// 在服务器上重新进行无尽模式的一局, 按回放得出清除的单元格数量和用时
+pub fn verify_endless(
+    difficulty: Difficulty,
+    replay: &EndlessReplay,
+) -> Result<VerifiedRun, ReplayError> {
+    if replay.moves.len() > MAX_ENDLESS_MOVES {
+        return Err(ReplayError::TooLarge);
+    }
+
+    let mut game = HeadlessEndless::new(difficulty, replay.seed);
+    let mut analysis = RunAnalysis::default();
+    let mut last_millis = None; // 上一次挖掘或插旗的时间
+
+    for replay_move in &replay.moves {
+        if game.status.is_finished() {
+            return Err(ReplayError::MovesAfterEnd);
+        }
+
+        if let Some(last_millis) = last_millis {
+            analysis.record_interval(replay_move.millis - last_millis);
+        }
+        last_millis = Some(replay_move.millis);
+
+        match replay_move.kind {
+            MoveKind::Dig => game.dig(replay_move.row, replay_move.column)?,
+            MoveKind::Flag => game.flag(replay_move.row, replay_move.column),
+            MoveKind::Peek => {}
+        }
+    }
+
+    // 用时以最后一次操作为准
+    let millis = replay
+        .moves
+        .last()
+        .map_or(0, |replay_move| replay_move.millis);
+
+    Ok(VerifiedRun {
+        time_in_seconds: millis / 1000,
+        lives_used: 0,
+        cells_cleared: game.cleared as i64,
+        rating: None,
+        analysis,
+        ghost: Default::default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::{EndlessMove, ReplayMove};
+
+    const SEED: u64 = 7;
+    const MOVE_MS: i64 = 250; // 相邻两次操作的间隔(毫秒)
+
+    // 按 GameState 开始一局的规则 (同一个种子, 起点周围没有地雷) 生成布局,
+    // 依次挖开所有尚未翻开的安全单元格并记录回放, 生命模式下先踩到一个地雷, 记忆模式下查看一次数字
+    // 返回回放, 安全单元格的数量和开始后清除空白区域的次数
+    fn record(mode: Mode) -> (ScoreReplay, i64, i64) {
+        let (rows, columns) = GameState::size_dimensions(Size::Small);
+        let mask = BoardMask::from_shape(Shape::Rectangle, rows, columns);
+        let mines = (mask.count() as f64 * GameState::mine_probability(Difficulty::Easy)) as isize;
+        let max_mines_per_cell = match mode {
+            Mode::MultiMine => GameState::MULTI_MINE_MAX,
+            _ => 1,
+        };
+        let start = (rows / 2, columns / 2);
+        let kinds = generate_kinds(
+            &mask,
+            mines,
+            max_mines_per_cell,
+            start,
+            &mut StdRng::seed_from_u64(SEED),
+        );
+
+        // 翻开单元格, 空白单元格继续翻开周围的单元格
+        let mut revealed = vec![false; kinds.len()];
+        let reveal = |index: usize, revealed: &mut Vec<bool>| {
+            let mut pending = vec![index];
+            while let Some(index) = pending.pop() {
+                if revealed[index] {
+                    continue;
+                }
+                revealed[index] = true;
+                if kinds[index] == CellKind::Clear(0) {
+                    let (row, column) = (index as isize / columns, index as isize % columns);
+                    pending.extend(ADJACENTS.iter().filter_map(|(row_offset, column_offset)| {
+                        let (row, column) = (row + row_offset, column + column_offset);
+                        mask.contains(row, column)
+                            .then_some((row * columns + column) as usize)
+                    }));
+                }
+            }
+        };
+
+        let start_index = (start.0 * columns + start.1) as usize;
+        let mut moves = vec![ReplayMove {
+            millis: 0,
+            kind: MoveKind::Dig,
+            index: start_index,
+        }];
+        reveal(start_index, &mut revealed);
+        let mut push = |kind: MoveKind, index: usize| {
+            let millis = moves.len() as i64 * MOVE_MS;
+            moves.push(ReplayMove {
+                millis,
+                kind,
+                index,
+            });
+        };
+
+        match mode {
+            Mode::Lives => {
+                let mine = kinds
+                    .iter()
+                    .position(|kind| matches!(kind, CellKind::Mine(_)))
+                    .expect("board has mines");
+                push(MoveKind::Dig, mine);
+            }
+            Mode::Memory => push(MoveKind::Peek, 0),
+            _ => {}
+        }
+
+        let mut openings = 0;
+        for (index, &kind) in kinds.iter().enumerate() {
+            if matches!(kind, CellKind::Clear(_)) && !revealed[index] {
+                if kind == CellKind::Clear(0) {
+                    openings += 1;
+                }
+                push(MoveKind::Dig, index);
+                reveal(index, &mut revealed);
+            }
+        }
+
+        let safe_cells = kinds
+            .iter()
+            .filter(|kind| matches!(kind, CellKind::Clear(_)))
+            .count() as i64;
+        (ScoreReplay { seed: SEED, moves }, safe_cells, openings)
+    }
+
+    #[test]
+    fn score_replay_round_trip() {
+        let replay = ScoreReplay {
+            seed: u64::MAX,
+            moves: vec![
+                ReplayMove {
+                    millis: 0,
+                    kind: MoveKind::Flag,
+                    index: 3,
+                },
+                ReplayMove {
+                    millis: 0,
+                    kind: MoveKind::Dig,
+                    index: 40,
+                },
+                ReplayMove {
+                    millis: 1_250,
+                    kind: MoveKind::Peek,
+                    index: 0,
+                },
+            ],
+        };
+        assert_eq!(replay.to_string().parse(), Ok(replay));
+
+        let empty = ScoreReplay::default();
+        assert_eq!(empty.to_string().parse(), Ok(empty));
+
+        // 操作时间倒序, 未知的操作类型和缺少种子都无法解析
+        assert!("1/5:d:1;4:d:2".parse::<ScoreReplay>().is_err());
+        assert!("1/5:x:1".parse::<ScoreReplay>().is_err());
+        assert!("5:d:1".parse::<ScoreReplay>().is_err());
+    }
+
+    #[test]
+    fn recorded_replays_verify_in_every_mode() {
+        for mode in REPLAY_MODES {
+            let (replay, safe_cells, openings) = record(mode);
+            let run = verify_replay(Difficulty::Easy, Size::Small, mode, &replay)
+                .unwrap_or_else(|error| panic!("{mode:?}: {error}"));
+
+            let seconds = replay.moves.last().expect("replay has moves").millis / 1000;
+            let time_in_seconds = match mode {
+                Mode::TimeAttack => {
+                    GameState::time_limit(Size::Small) + openings * GameState::OPENING_BONUS
+                        - seconds
+                }
+                Mode::Memory => seconds + GameState::MEMORY_PEEK_PENALTY,
+                _ => seconds,
+            };
+            let lives_used = match mode {
+                Mode::Lives => 1,
+                _ => 0,
+            };
+
+            assert_eq!(run.time_in_seconds, time_in_seconds, "{mode:?}");
+            assert_eq!(run.lives_used, lives_used, "{mode:?}");
+            assert_eq!(run.cells_cleared, safe_cells, "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn unfinished_replays_do_not_verify() {
+        let (mut replay, _, _) = record(Mode::Classic);
+        replay.moves.pop();
+        assert_eq!(
+            verify_replay(Difficulty::Easy, Size::Small, Mode::Classic, &replay),
+            Err(ReplayError::NotWon)
+        );
+    }
+
+    #[test]
+    fn positions_hide_the_layout_until_the_game_ends() {
+        let (mut replay, safe_cells, _) = record(Mode::Classic);
+        let start = replay.moves[0].index;
+        let position = |replay: &ScoreReplay| {
+            replay_position(Difficulty::Easy, Size::Small, Mode::Classic, start, replay)
+        };
+
+        let finished = position(&replay).expect("recorded replay is valid");
+        assert_eq!(finished.status, GameStatus::Victory);
+        assert_eq!(
+            finished.cells.len(),
+            safe_cells as usize + finished.mine_cells as usize
+        );
+
+        replay.moves.pop();
+        let unfinished = position(&replay).expect("recorded replay is valid");
+        assert_eq!(unfinished.status, GameStatus::Started);
+        assert!(unfinished
+            .cells
+            .iter()
+            .all(|(_, kind)| matches!(kind, CellKind::Clear(_))));
+        assert!(unfinished.cells.len() < safe_cells as usize);
+
+        replay.moves.truncate(1);
+        replay.moves[0].index = start + 1;
+        assert_eq!(position(&replay), Err(ReplayError::WrongStart));
+    }
+
+    #[test]
+    fn oversized_replays_are_rejected() {
+        let (mut replay, _, _) = record(Mode::Classic);
+        assert_eq!(
+            check_replay_len(&replay.to_string(), MAX_REPLAY_MOVES),
+            Ok(())
+        );
+        assert_eq!(
+            check_replay_len(&"1".repeat(1 << 20), MAX_REPLAY_MOVES),
+            Err(ReplayError::TooLarge)
+        );
+
+        let last_move = *replay.moves.last().expect("replay has moves");
+        replay.moves.resize(MAX_REPLAY_MOVES + 1, last_move);
+        assert_eq!(
+            verify_replay(Difficulty::Easy, Size::Small, Mode::Classic, &replay),
+            Err(ReplayError::TooLarge)
+        );
+    }
+
+    #[test]
+    fn endless_replays_are_bounded() {
+        let flag = EndlessMove {
+            millis: 0,
+            kind: MoveKind::Flag,
+            row: 0,
+            column: 0,
+        };
+        let replay = EndlessReplay {
+            seed: SEED,
+            moves: vec![flag; MAX_ENDLESS_MOVES + 1],
+        };
+        assert_eq!(
+            verify_endless(Difficulty::Easy, &replay),
+            Err(ReplayError::TooLarge)
+        );
+
+        // 没有地雷时一次挖掘会连锁翻开整个无尽的棋盘, 生成的区块达到上限时停止
+        let mut game = HeadlessEndless::new(Difficulty::Easy, SEED);
+        game.probability = 0.0;
+        assert_eq!(game.dig(0, 0), Err(ReplayError::TooLarge));
+    }
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game_logic::{CellInteraction, CellKind, GameStatus},
+    game_settings::{Difficulty, Mode, Size},
+    room::CellUpdate,
+};
+
+// 直播可选的延迟(秒), 比赛中使用延迟可以避免观众帮忙
+pub const WATCH_DELAYS: [u32; 3] = [0, 10, 30];
+
+// 记录成绩的游戏 (包括每日挑战和竞速) 直播时的最短延迟(秒)
+pub const RANKED_MIN_DELAY: u32 = 10;
+
+// 直播允许的最短延迟, 只有不记录成绩的谜题模式和夺旗模式可以不延迟
+pub fn min_delay(mode: Mode) -> u32 {
+    match mode {
+        Mode::Puzzle | Mode::Flags => 0,
+        _ => RANKED_MIN_DELAY,
+    }
+}
+
+// 玩家向服务器发送棋盘变化的间隔(毫秒)
+pub const WATCH_FLUSH_MS: u32 = 300;
+
+// 正在直播的游戏, 显示在主页的列表中
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LiveGame {
+    pub id: String,
+    pub username: String,
+    pub difficulty: Difficulty,
+    pub size: Size,
+    pub mode: Mode,
+    pub delay_seconds: u32,
+}
+
+// 推送给观众的事件
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum WatchEvent {
+    // 直播的信息和棋盘的形状, 观众连接后立即收到 (不受延迟影响)
+    Board {
+        game: LiveGame,
+        rows: isize,
+        columns: isize,
+        mask: String,
+    },
+    // 一段时间内改变的单元格和当前的游戏进度
+    Cells {
+        cells: Vec<CellUpdate>,
+        status: GameStatus,
+        cleared: isize,
+        clear_total: isize,
+    },
+    Ended, // 玩家停止了直播
+}
+
+// 观众可以看到的单元格状态, 未翻开和插旗的单元格不包含类型, 避免泄露地雷位置
+pub fn visible_cell(index: usize, interaction: CellInteraction, kind: CellKind) -> CellUpdate {
+    let kind = match interaction {
+        CellInteraction::Cleared | CellInteraction::Exploded => kind,
+        _ => Default::default(),
+    };
+
+    CellUpdate {
+        index,
+        interaction,
+        kind,
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use futures::stream;
+use rand::{distributions::Alphanumeric, Rng};
+use tokio::sync::watch;
+
+use crate::watch::{LiveGame, WatchEvent};
+
+// 超过这段时间没有收到玩家的更新时, 认为玩家已经离开
+const STALE_AFTER: Duration = Duration::from_secs(5 * 60);
+
+// 同时直播的游戏数量上限, 以及每局直播最多记录的事件数量 (每次发送一个事件, 约一小时)
+const MAX_BROADCASTS: usize = 100;
+const MAX_EVENTS: usize = 12_000;
+
+// 一局正在直播的游戏: 记录所有事件, 观众按延迟依次收到
+pub struct Broadcast {
+    pub game: LiveGame,
+    token: String,                             // 只有持有令牌的玩家可以发送更新
+    cells: usize,                              // 棋盘的单元格数量, 每个事件最多包含这么多单元格
+    events: Mutex<Vec<(Instant, WatchEvent)>>, // 事件及服务器收到的时刻
+    count: watch::Sender<usize>,               // 事件数量, 用于通知观众有新事件
+    last_active: Mutex<Instant>,
+}
+
+impl Broadcast {
+    // 记录一个新事件并通知所有观众, 事件数量达到上限时返回 false (结束事件除外)
+    fn push(&self, event: WatchEvent) -> bool {
+        let mut events = self
+            .events
+            .lock()
+            .expect("broadcast events lock is not poisoned");
+        if events.len() >= MAX_EVENTS && event != WatchEvent::Ended {
+            return false;
+        }
+        events.push((Instant::now(), event));
+        *self
+            .last_active
+            .lock()
+            .expect("broadcast activity lock is not poisoned") = Instant::now();
+        let _ = self.count.send(events.len());
+        true
+    }
+
+    fn event(&self, index: usize) -> Option<(Instant, WatchEvent)> {
+        self.events
+            .lock()
+            .expect("broadcast events lock is not poisoned")
+            .get(index)
+            .cloned()
+    }
+
+    fn is_stale(&self) -> bool {
+        self.last_active
+            .lock()
+            .expect("broadcast activity lock is not poisoned")
+            .elapsed()
+            > STALE_AFTER
+    }
+}
+
+// 所有正在直播的游戏, 以直播编号为键
+pub type Broadcasts = Arc<Mutex<HashMap<String, Arc<Broadcast>>>>;
+
+// 结束长时间没有更新的直播
+fn remove_stale(broadcasts: &mut HashMap<String, Arc<Broadcast>>) {
+    broadcasts.retain(|_, broadcast| {
+        let stale = broadcast.is_stale();
+        if stale {
+            broadcast.push(WatchEvent::Ended);
+        }
+        !stale
+    });
+}
+
+// 开始直播, 返回直播编号和玩家的令牌, 直播数量达到上限时返回 None
+pub fn publish(
+    broadcasts: &Broadcasts,
+    mut game: LiveGame,
+    rows: isize,
+    columns: isize,
+    mask: String,
+) -> Option<(String, String)> {
+    let mut broadcasts = broadcasts.lock().expect("broadcasts lock is not poisoned");
+    remove_stale(&mut broadcasts);
+    if broadcasts.len() >= MAX_BROADCASTS {
+        return None;
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut random_text = |len: usize| -> String {
+        (&mut rng)
+            .sample_iter(Alphanumeric)
+            .take(len)
+            .map(char::from)
+            .collect()
+    };
+    let id = random_text(8);
+    let token = random_text(24);
+    game.id = id.clone();
+
+    let board = WatchEvent::Board {
+        game: game.clone(),
+        rows,
+        columns,
+        mask,
+    };
+    let broadcast = Broadcast {
+        game,
+        token: token.clone(),
+        cells: (rows * columns) as usize,
+        events: Mutex::new(Vec::new()),
+        count: watch::channel(0).0,
+        last_active: Mutex::new(Instant::now()),
+    };
+    broadcast.push(board);
+
+    broadcasts.insert(id.clone(), Arc::new(broadcast));
+    Some((id, token))
+}
+
+// 记录玩家发送的更新, 令牌不匹配, 单元格超出棋盘或事件数量达到上限时返回 false
+pub fn update(broadcasts: &Broadcasts, id: &str, token: &str, event: WatchEvent) -> bool {
+    let broadcasts = broadcasts.lock().expect("broadcasts lock is not poisoned");
+    match broadcasts.get(id) {
+        Some(broadcast) if broadcast.token == token => {
+            let fits = match &event {
+                WatchEvent::Cells { cells, .. } => {
+                    cells.len() <= broadcast.cells
+                        && cells.iter().all(|cell| cell.index < broadcast.cells)
+                }
+                _ => true,
+            };
+            fits && broadcast.push(event)
+        }
+        _ => false,
+    }
+}
+
+// 结束直播, 令牌不匹配时忽略
+pub fn end(broadcasts: &Broadcasts, id: &str, token: &str) {
+    let mut broadcasts = broadcasts.lock().expect("broadcasts lock is not poisoned");
+    if broadcasts
+        .get(id)
+        .is_some_and(|broadcast| broadcast.token == token)
+    {
+        if let Some(broadcast) = broadcasts.remove(id) {
+            broadcast.push(WatchEvent::Ended);
+        }
+    }
+}
+
+// 正在直播的游戏列表
+pub fn live_games(broadcasts: &Broadcasts) -> Vec<LiveGame> {
+    let mut broadcasts = broadcasts.lock().expect("broadcasts lock is not poisoned");
+    remove_stale(&mut broadcasts);
+    broadcasts
+        .values()
+        .map(|broadcast| broadcast.game.clone())
+        .collect()
+}
+
+// 观看直播的服务器推送事件 (SSE) 入口
+// 第一个事件 (棋盘) 立即发送, 之后的事件在服务器收到后经过直播的延迟再发送
+pub async fn watch_handler(
+    Path(id): Path<String>,
+    State(broadcasts): State<Broadcasts>,
+) -> Response {
+    let Some(broadcast) = broadcasts
+        .lock()
+        .expect("broadcasts lock is not poisoned")
+        .get(&id)
+        .cloned()
+    else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let delay = Duration::from_secs(broadcast.game.delay_seconds as u64);
+    let receiver = broadcast.count.subscribe();
+
+    let events = stream::unfold(
+        (broadcast, receiver, 0, false),
+        move |(broadcast, mut receiver, index, ended)| async move {
+            if ended {
+                return None;
+            }
+            loop {
+                if let Some((received_at, event)) = broadcast.event(index) {
+                    if index > 0 {
+                        tokio::time::sleep_until((received_at + delay).into()).await;
+                    }
+                    let ended = event == WatchEvent::Ended;
+                    let event = Event::default().json_data(&event).ok()?;
+                    return Some((
+                        Ok::<_, Infallible>(event),
+                        (broadcast, receiver, index + 1, ended),
+                    ));
+                }
+                // 等待玩家发送新的更新
+                receiver.changed().await.ok()?;
+            }
+        },
+    );
+
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}